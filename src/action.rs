@@ -0,0 +1,1205 @@
+use crate::app::*;
+use crate::client::*;
+use crate::commands::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    CancelJobsAndQuit,
+    DetachAndQuit,
+    ConfirmReconnect,
+    ConfirmScope,
+    Refresh,
+    ClosePanel,
+    NavBack,
+    NavForward,
+    ToggleLastScope,
+    ScrollPanel(i32),
+    StartQuery,
+    StartCommand,
+    StartScopeFilter,
+    StartResultsFilter,
+    StartJump,
+    ToggleFocus,
+    SelectScopeKind(ScopeKind),
+    SelectPrev,
+    SelectNext,
+    OpenAliasDetails,
+    OpenLifecycleDetails,
+    OpenIndexDetails,
+    SetAliasExpanded(bool),
+    ToggleDocDrawer,
+    CloseDocDrawer,
+    ReloadDocs,
+    NextPage,
+    PrevPage,
+    CycleDocView,
+    ToggleLogView,
+    CopyPermalink,
+    RefetchDoc,
+    FollowTrace,
+    ExplainQuery,
+    ToggleOperator,
+    OpenClusterPicker,
+    ToggleMappingsTab,
+    OpenIndexSettings,
+    ToggleQueryMode,
+    MoveClusterSelection(isize),
+    ConfirmCluster,
+    OpenAggregationBuilder,
+    MoveAggregationField(isize),
+    CycleAggregationKind(isize),
+    ConfirmAggregation,
+    OpenColumnPicker,
+    MoveColumnSelection(isize),
+    ToggleColumn,
+    MoveCellCursor(isize),
+    OpenSortPicker,
+    StartDrawerSearch,
+    MoveDrawerMatch(isize),
+    StartExport,
+    OpenEventTimeline,
+    OpenHealthDetail,
+    OpenIndexActions,
+    StartAliasEdit,
+    ApplyAliasChange,
+    MoveIndexActionSelection(isize),
+    RunIndexOp,
+    ExplainAllocation,
+    MoveDrawerField(isize),
+    AddFieldFilter { exclude: bool },
+    OpenBlobPicker,
+    MoveBlobSelection(isize),
+    OpenSelectedBlob,
+    ToggleMark,
+    OpenMarkedExport,
+    MoveMarkedExportSelection(isize),
+    ExportMarked,
+    OpenBasket,
+    MoveBasketSelection(isize),
+    OpenBasketDoc,
+    DiffBasketDocs,
+    RemoveBasketDoc,
+    ClearBasket,
+    OpenViewPicker,
+    MoveViewSelection(isize),
+    LoadSelectedView,
+    DeleteSelectedView,
+    ToggleFavorite,
+    OpenFavoritePicker,
+    MoveFavoriteSelection(isize),
+    ConfirmFavorite,
+    MoveSortSelection(isize),
+    ToggleSortOrder,
+    ConfirmSort,
+    ExpandCell,
+    MoveTermsSelection(isize),
+    InputChar(char),
+    InputBackspace,
+    InputCancel,
+    InputSubmit,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::CancelJobsAndQuit => "CancelJobsAndQuit",
+            Action::DetachAndQuit => "DetachAndQuit",
+            Action::ConfirmReconnect => "ConfirmReconnect",
+            Action::ConfirmScope => "ConfirmScope",
+            Action::Refresh => "Refresh",
+            Action::ClosePanel => "ClosePanel",
+            Action::NavBack => "NavBack",
+            Action::NavForward => "NavForward",
+            Action::ToggleLastScope => "ToggleLastScope",
+            Action::ScrollPanel(_) => "ScrollPanel",
+            Action::StartQuery => "StartQuery",
+            Action::StartCommand => "StartCommand",
+            Action::StartScopeFilter => "StartScopeFilter",
+            Action::StartResultsFilter => "StartResultsFilter",
+            Action::StartJump => "StartJump",
+            Action::ToggleFocus => "ToggleFocus",
+            Action::SelectScopeKind(_) => "SelectScopeKind",
+            Action::SelectPrev => "SelectPrev",
+            Action::SelectNext => "SelectNext",
+            Action::OpenAliasDetails => "OpenAliasDetails",
+            Action::OpenLifecycleDetails => "OpenLifecycleDetails",
+            Action::OpenIndexDetails => "OpenIndexDetails",
+            Action::SetAliasExpanded(_) => "SetAliasExpanded",
+            Action::ToggleDocDrawer => "ToggleDocDrawer",
+            Action::CloseDocDrawer => "CloseDocDrawer",
+            Action::ReloadDocs => "ReloadDocs",
+            Action::NextPage => "NextPage",
+            Action::PrevPage => "PrevPage",
+            Action::CycleDocView => "CycleDocView",
+            Action::ToggleLogView => "ToggleLogView",
+            Action::CopyPermalink => "CopyPermalink",
+            Action::RefetchDoc => "RefetchDoc",
+            Action::FollowTrace => "FollowTrace",
+            Action::ExplainQuery => "ExplainQuery",
+            Action::ToggleOperator => "ToggleOperator",
+            Action::OpenClusterPicker => "OpenClusterPicker",
+            Action::ToggleMappingsTab => "ToggleMappingsTab",
+            Action::OpenIndexSettings => "OpenIndexSettings",
+            Action::ToggleQueryMode => "ToggleQueryMode",
+            Action::MoveClusterSelection(_) => "MoveClusterSelection",
+            Action::ConfirmCluster => "ConfirmCluster",
+            Action::OpenAggregationBuilder => "OpenAggregationBuilder",
+            Action::MoveAggregationField(_) => "MoveAggregationField",
+            Action::CycleAggregationKind(_) => "CycleAggregationKind",
+            Action::ConfirmAggregation => "ConfirmAggregation",
+            Action::OpenColumnPicker => "OpenColumnPicker",
+            Action::MoveColumnSelection(_) => "MoveColumnSelection",
+            Action::ToggleColumn => "ToggleColumn",
+            Action::MoveCellCursor(_) => "MoveCellCursor",
+            Action::OpenSortPicker => "OpenSortPicker",
+            Action::StartDrawerSearch => "StartDrawerSearch",
+            Action::MoveDrawerMatch(_) => "MoveDrawerMatch",
+            Action::StartExport => "StartExport",
+            Action::OpenEventTimeline => "OpenEventTimeline",
+            Action::OpenHealthDetail => "OpenHealthDetail",
+            Action::OpenIndexActions => "OpenIndexActions",
+            Action::StartAliasEdit => "StartAliasEdit",
+            Action::ApplyAliasChange => "ApplyAliasChange",
+            Action::MoveIndexActionSelection(_) => "MoveIndexActionSelection",
+            Action::RunIndexOp => "RunIndexOp",
+            Action::ExplainAllocation => "ExplainAllocation",
+            Action::MoveDrawerField(_) => "MoveDrawerField",
+            Action::AddFieldFilter { .. } => "AddFieldFilter",
+            Action::OpenBlobPicker => "OpenBlobPicker",
+            Action::MoveBlobSelection(_) => "MoveBlobSelection",
+            Action::OpenSelectedBlob => "OpenSelectedBlob",
+            Action::ToggleMark => "ToggleMark",
+            Action::OpenMarkedExport => "OpenMarkedExport",
+            Action::MoveMarkedExportSelection(_) => "MoveMarkedExportSelection",
+            Action::ExportMarked => "ExportMarked",
+            Action::OpenBasket => "OpenBasket",
+            Action::MoveBasketSelection(_) => "MoveBasketSelection",
+            Action::OpenBasketDoc => "OpenBasketDoc",
+            Action::DiffBasketDocs => "DiffBasketDocs",
+            Action::RemoveBasketDoc => "RemoveBasketDoc",
+            Action::ClearBasket => "ClearBasket",
+            Action::OpenViewPicker => "OpenViewPicker",
+            Action::MoveViewSelection(_) => "MoveViewSelection",
+            Action::LoadSelectedView => "LoadSelectedView",
+            Action::DeleteSelectedView => "DeleteSelectedView",
+            Action::ToggleFavorite => "ToggleFavorite",
+            Action::OpenFavoritePicker => "OpenFavoritePicker",
+            Action::MoveFavoriteSelection(_) => "MoveFavoriteSelection",
+            Action::ConfirmFavorite => "ConfirmFavorite",
+            Action::MoveSortSelection(_) => "MoveSortSelection",
+            Action::ToggleSortOrder => "ToggleSortOrder",
+            Action::ConfirmSort => "ConfirmSort",
+            Action::ExpandCell => "ExpandCell",
+            Action::MoveTermsSelection(_) => "MoveTermsSelection",
+            Action::InputChar(_) => "InputChar",
+            Action::InputBackspace => "InputBackspace",
+            Action::InputCancel => "InputCancel",
+            Action::InputSubmit => "InputSubmit",
+        }
+    }
+}
+
+impl Action {
+    fn records_usage(&self) -> bool {
+        !matches!(
+            self,
+            Action::InputChar(_)
+                | Action::InputBackspace
+                | Action::SelectPrev
+                | Action::SelectNext
+                | Action::ScrollPanel(_)
+                | Action::MoveClusterSelection(_)
+                | Action::MoveAggregationField(_)
+                | Action::MoveColumnSelection(_)
+                | Action::MoveCellCursor(_)
+                | Action::MoveDrawerMatch(_)
+                | Action::MoveIndexActionSelection(_)
+                | Action::MoveDrawerField(_)
+                | Action::MoveBlobSelection(_)
+                | Action::MoveMarkedExportSelection(_)
+                | Action::MoveBasketSelection(_)
+                | Action::MoveViewSelection(_)
+                | Action::MoveFavoriteSelection(_)
+                | Action::MoveSortSelection(_)
+                | Action::MoveTermsSelection(_)
+        )
+    }
+}
+
+impl App {
+    pub fn update(&mut self, action: Action) {
+        self.dirty = true;
+        if action.records_usage() {
+            self.usage.record(format!("action:{}", action.name()));
+        }
+        let scope_step = self.focus == Focus::LeftNav
+            && self.info_panel.is_none()
+            && matches!(action, Action::SelectPrev | Action::SelectNext);
+        let continuing_step = scope_step && self.nav_stepping;
+        if scope_step && !self.nav_stepping {
+            self.record_nav();
+        }
+        self.nav_stepping = scope_step;
+        let toggling_scope = action == Action::ToggleLastScope;
+        let before = self.nav_entry();
+        match action {
+            Action::Quit if self.jobs.is_empty() => self.should_quit = true,
+            Action::Quit => open_quit_confirm(self),
+            Action::CancelJobsAndQuit => {
+                self.close_panel();
+                match cancel_jobs(self) {
+                    Ok(()) => self.should_quit = true,
+                    Err(err) => self.last_error = Some(format!("jobs: {err:#}")),
+                }
+            }
+            Action::DetachAndQuit => self.should_quit = true,
+            Action::ConfirmReconnect => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::Reconnect(cluster)) = prompt
+                    && let Err(err) = reconnect(self, cluster)
+                {
+                    self.last_error = Some(format!("reconnect: {err:#}"));
+                }
+            }
+            Action::OpenClusterPicker => {
+                if let Err(err) = run_cluster_command(self, &[]) {
+                    self.last_error = Some(format!("cluster: {err:#}"));
+                }
+            }
+            Action::MoveClusterSelection(delta) => {
+                if let Some(Prompt::PickCluster(selected)) = self.prompt {
+                    open_cluster_picker(self, selected.saturating_add_signed(delta));
+                }
+            }
+            Action::ConfirmCluster => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickCluster(selected)) = prompt
+                    && let Some((name, _)) = cluster_profiles(self).into_iter().nth(selected)
+                    && name != self.cluster_name
+                    && let Err(err) = switch_cluster(self, name)
+                {
+                    self.last_error = Some(format!("cluster: {err:#}"));
+                }
+            }
+            Action::OpenAggregationBuilder => {
+                if let Err(err) = open_aggregation_builder(self, AggKind::Terms, 0) {
+                    self.last_error = Some(format!("aggregate: {err:#}"));
+                }
+            }
+            Action::MoveAggregationField(delta) => {
+                if let Some(Prompt::BuildAggregation { kind, field }) = self.prompt
+                    && let Err(err) =
+                        open_aggregation_builder(self, kind, field.saturating_add_signed(delta))
+                {
+                    self.last_error = Some(format!("aggregate: {err:#}"));
+                }
+            }
+            Action::CycleAggregationKind(delta) => {
+                if let Some(Prompt::BuildAggregation { kind, .. }) = self.prompt
+                    && let Err(err) = open_aggregation_builder(self, kind.cycled(delta), 0)
+                {
+                    self.last_error = Some(format!("aggregate: {err:#}"));
+                }
+            }
+            Action::ConfirmAggregation => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::BuildAggregation { kind, field }) = prompt
+                    && let Err(err) = confirm_aggregation(self, kind, field)
+                {
+                    self.last_error = Some(format!("aggregate: {err:#}"));
+                }
+            }
+            Action::OpenColumnPicker => {
+                if let Err(err) = open_column_picker(self, 0) {
+                    self.last_error = Some(format!("columns: {err:#}"));
+                }
+            }
+            Action::MoveColumnSelection(delta) => {
+                if let Some(Prompt::PickColumns(selected)) = self.prompt
+                    && let Err(err) =
+                        open_column_picker(self, selected.saturating_add_signed(delta))
+                {
+                    self.last_error = Some(format!("columns: {err:#}"));
+                }
+            }
+            Action::ToggleColumn => {
+                if let Some(Prompt::PickColumns(selected)) = self.prompt
+                    && let Err(err) = toggle_column(self, selected)
+                {
+                    self.last_error = Some(format!("columns: {err:#}"));
+                }
+            }
+            Action::MoveCellCursor(delta) => self.move_cell_cursor(delta),
+            Action::ToggleFavorite => {
+                if let Err(err) = toggle_favorite(self) {
+                    self.last_error = Some(format!("favorite: {err:#}"));
+                }
+            }
+            Action::OpenFavoritePicker => open_favorite_picker(self, 0),
+            Action::MoveFavoriteSelection(delta) => {
+                if let Some(Prompt::PickFavorite(selected)) = self.prompt {
+                    open_favorite_picker(self, selected.saturating_add_signed(delta));
+                }
+            }
+            Action::ConfirmFavorite => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickFavorite(selected)) = prompt
+                    && let Err(err) = select_favorite(self, selected)
+                {
+                    self.last_error = Some(format!("favorite: {err:#}"));
+                }
+            }
+            Action::OpenViewPicker => open_view_picker(self, 0),
+            Action::MoveViewSelection(delta) => {
+                if let Some(Prompt::PickView(selected)) = self.prompt {
+                    open_view_picker(self, selected.saturating_add_signed(delta));
+                }
+            }
+            Action::LoadSelectedView => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickView(selected)) = prompt
+                    && let Some(name) = self
+                        .state
+                        .saved_views
+                        .get(selected)
+                        .map(|view| view.name.clone())
+                    && let Err(err) = load_view(self, &name)
+                {
+                    self.last_error = Some(format!("view: {err:#}"));
+                }
+            }
+            Action::DeleteSelectedView => {
+                if let Some(Prompt::PickView(selected)) = self.prompt
+                    && let Some(name) = self
+                        .state
+                        .saved_views
+                        .get(selected)
+                        .map(|view| view.name.clone())
+                {
+                    if let Err(err) = delete_view(self, &name) {
+                        self.last_error = Some(format!("view: {err:#}"));
+                    }
+                    open_view_picker(self, selected);
+                }
+            }
+            Action::OpenBlobPicker => {
+                if let Err(err) = open_blob_picker(self, 0) {
+                    self.last_error = Some(format!("download: {err:#}"));
+                }
+            }
+            Action::MoveBlobSelection(delta) => {
+                if let Some(Prompt::PickBlob(selected)) = self.prompt
+                    && let Err(err) = open_blob_picker(self, selected.saturating_add_signed(delta))
+                {
+                    self.last_error = Some(format!("download: {err:#}"));
+                }
+            }
+            Action::OpenSelectedBlob => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickBlob(selected)) = prompt
+                    && let Err(err) = open_selected_blob(self, selected)
+                {
+                    self.last_error = Some(format!("download: {err:#}"));
+                }
+            }
+            Action::ToggleMark => toggle_mark(self),
+            Action::OpenBasket => {
+                if let Err(err) = open_basket(self, 0) {
+                    self.last_error = Some(format!("basket: {err:#}"));
+                }
+            }
+            Action::MoveBasketSelection(delta) => {
+                if let Some(Prompt::PickBasket(selected)) = self.prompt
+                    && let Err(err) = open_basket(self, selected.saturating_add_signed(delta))
+                {
+                    self.last_error = Some(format!("basket: {err:#}"));
+                }
+            }
+            Action::OpenBasketDoc | Action::DiffBasketDocs => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickBasket(selected)) = prompt {
+                    let result = if action == Action::OpenBasketDoc {
+                        open_basket_doc(self, selected)
+                    } else {
+                        open_basket_diff(self, selected)
+                    };
+                    if let Err(err) = result {
+                        self.last_error = Some(format!("basket: {err:#}"));
+                    }
+                }
+            }
+            Action::RemoveBasketDoc => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickBasket(selected)) = prompt {
+                    if let Err(err) = remove_basket_doc(self, selected) {
+                        self.last_error = Some(format!("basket: {err:#}"));
+                    } else if !self.basket.is_empty() {
+                        let _ = open_basket(self, selected);
+                    }
+                }
+            }
+            Action::ClearBasket => {
+                self.basket.clear();
+                self.close_panel();
+            }
+            Action::OpenMarkedExport => {
+                if let Err(err) = open_marked_export(self, 0) {
+                    self.last_error = Some(format!("export: {err:#}"));
+                }
+            }
+            Action::MoveMarkedExportSelection(delta) => {
+                if let Some(Prompt::ExportMarked(selected)) = self.prompt
+                    && let Err(err) =
+                        open_marked_export(self, selected.saturating_add_signed(delta))
+                {
+                    self.last_error = Some(format!("export: {err:#}"));
+                }
+            }
+            Action::ExportMarked => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::ExportMarked(selected)) = prompt
+                    && let Err(err) = export_marked(self, selected)
+                {
+                    self.last_error = Some(format!("export: {err:#}"));
+                }
+            }
+            Action::OpenSortPicker => {
+                let order = self
+                    .sort
+                    .as_ref()
+                    .map(|sort| sort.order)
+                    .unwrap_or_default();
+                if let Err(err) = open_sort_picker(self, 0, order) {
+                    self.last_error = Some(format!("sort: {err:#}"));
+                }
+            }
+            Action::MoveSortSelection(delta) => {
+                if let Some(Prompt::PickSort { field, order }) = self.prompt
+                    && let Err(err) =
+                        open_sort_picker(self, field.saturating_add_signed(delta), order)
+                {
+                    self.last_error = Some(format!("sort: {err:#}"));
+                }
+            }
+            Action::ToggleSortOrder => {
+                if let Some(Prompt::PickSort { field, order }) = self.prompt
+                    && let Err(err) = open_sort_picker(self, field, order.toggled())
+                {
+                    self.last_error = Some(format!("sort: {err:#}"));
+                }
+            }
+            Action::ConfirmSort => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::PickSort { field, order }) = prompt
+                    && let Err(err) = confirm_sort(self, field, order)
+                {
+                    self.last_error = Some(format!("sort: {err:#}"));
+                }
+            }
+            Action::ExpandCell => {
+                if let Err(err) = open_cell_popup(self) {
+                    self.last_error = Some(format!("cell: {err:#}"));
+                }
+            }
+            Action::ConfirmScope => {
+                let prompt = self.prompt.take();
+                self.close_panel();
+                if let Some(Prompt::OpenScope(pattern)) = prompt {
+                    open_scope_pattern(self, pattern);
+                }
+            }
+            Action::Refresh => refresh_all(self),
+            Action::ClosePanel => self.close_panel(),
+            Action::NavBack => navigate_back(self),
+            Action::NavForward => navigate_forward(self),
+            Action::ToggleLastScope => toggle_last_scope(self),
+            Action::ScrollPanel(delta) => scroll_info_panel(self, delta),
+            Action::StartQuery => {
+                self.input_mode = InputMode::Query;
+                self.query_edit = self.query.clone();
+            }
+            Action::StartCommand => {
+                self.input_mode = InputMode::Command;
+                self.command_edit.clear();
+            }
+            Action::StartScopeFilter => {
+                self.input_mode = InputMode::ScopeFilter;
+                self.scope_filter_edit = self.scope_filter.clone();
+            }
+            Action::StartResultsFilter => {
+                self.input_mode = InputMode::ResultsFilter;
+                self.focus = Focus::Results;
+            }
+            Action::StartDrawerSearch => {
+                self.input_mode = InputMode::DrawerSearch;
+                self.drawer_search.clear();
+                self.drawer_match = 0;
+            }
+            Action::MoveDrawerMatch(delta) => move_drawer_match(self, delta),
+            Action::OpenEventTimeline => open_event_timeline(self),
+            Action::OpenHealthDetail => {
+                if let Err(err) = open_health_detail(self) {
+                    self.last_error = Some(format!("health: {err:#}"));
+                }
+            }
+            Action::OpenIndexActions => {
+                if let Err(err) = open_index_actions(self, 0) {
+                    self.last_error = Some(format!("index: {err:#}"));
+                }
+            }
+            Action::StartAliasEdit => {
+                self.input_mode = InputMode::Command;
+                self.command_edit = "alias switch ".to_string();
+            }
+            Action::ApplyAliasChange => {
+                let result = apply_alias_change(self);
+                report_action_result(self, "alias", result);
+            }
+            Action::MoveIndexActionSelection(delta) => {
+                if let Some(Prompt::IndexActions { selected, .. }) = self.prompt {
+                    let _ = open_index_actions(self, selected.saturating_add_signed(delta));
+                }
+            }
+            Action::RunIndexOp => {
+                let result = confirm_index_action(self);
+                report_action_result(self, "index", result);
+            }
+            Action::ExplainAllocation => {
+                if let Err(err) = open_allocation_explain(self) {
+                    self.last_error = Some(format!("allocation: {err:#}"));
+                }
+            }
+            Action::StartExport => match default_export_path(self) {
+                Ok(path) => {
+                    self.export_edit = path;
+                    self.input_mode = InputMode::ExportPath;
+                }
+                Err(err) => self.last_error = Some(format!("export: {err:#}")),
+            },
+            Action::MoveDrawerField(delta) => move_drawer_field(self, delta),
+            Action::AddFieldFilter { exclude } => {
+                if let Err(err) = add_field_filter(self, exclude) {
+                    self.last_error = Some(format!("filter: {err:#}"));
+                }
+            }
+            Action::StartJump => {
+                self.input_mode = InputMode::Jump;
+                self.focus = Focus::Results;
+                self.jump_edit.clear();
+                self.jump_origin = self.docs_state.selected();
+            }
+            Action::ToggleFocus => {
+                self.focus = match self.focus {
+                    Focus::LeftNav => Focus::Results,
+                    Focus::Results => Focus::LeftNav,
+                };
+            }
+            Action::SelectScopeKind(kind) => {
+                if kind != self.scope_kind {
+                    self.record_nav();
+                }
+                self.set_scope_kind(kind);
+                handle_scope_change(self);
+            }
+            Action::SelectPrev if self.mappings_focused() => shift_mapping_selection(self, -1),
+            Action::SelectNext if self.mappings_focused() => shift_mapping_selection(self, 1),
+            Action::ToggleMappingsTab => toggle_mappings_tab(self),
+            Action::ToggleQueryMode => toggle_query_mode(self),
+            Action::OpenIndexSettings => {
+                if let Err(err) = open_index_settings(self) {
+                    self.last_error = Some(format!("settings: {err:#}"));
+                }
+            }
+            Action::SelectPrev => match self.focus {
+                Focus::LeftNav => {
+                    self.select_prev_scope_item();
+                    handle_scope_change(self);
+                }
+                Focus::Results if self.docs_state.selected() == Some(0) && self.docs_from > 0 => {
+                    let result = load_previous_docs(self);
+                    handle_docs_result(self, result);
+                    self.select_prev_doc();
+                }
+                Focus::Results => self.select_prev_doc(),
+            },
+            Action::SelectNext => match self.focus {
+                Focus::LeftNav => {
+                    self.select_next_scope_item();
+                    handle_scope_change(self);
+                }
+                Focus::Results if self.selection_at_end() && self.has_more_docs() => {
+                    let result = load_more_docs(self);
+                    handle_docs_result(self, result);
+                    self.select_next_doc();
+                }
+                Focus::Results => self.select_next_doc(),
+            },
+            Action::OpenAliasDetails => {
+                if let Err(err) = open_alias_details(self) {
+                    self.last_error = Some(format!("alias: {err:#}"));
+                }
+            }
+            Action::OpenLifecycleDetails => {
+                if let Err(err) = open_lifecycle_details(self) {
+                    self.last_error = Some(format!("lifecycle: {err:#}"));
+                }
+            }
+            Action::OpenIndexDetails => {
+                if let Err(err) = open_index_details(self) {
+                    self.last_error = Some(format!("index: {err:#}"));
+                }
+            }
+            Action::SetAliasExpanded(expanded) => self.set_alias_expanded(expanded),
+            Action::ToggleDocDrawer => {
+                if !self.show_doc_drawer {
+                    self.record_nav();
+                }
+                self.show_doc_drawer = !self.show_doc_drawer;
+            }
+            Action::CloseDocDrawer => self.show_doc_drawer = false,
+            Action::ReloadDocs => handle_docs_refresh(self),
+            Action::NextPage => {
+                let result = load_more_docs(self);
+                handle_docs_result(self, result);
+            }
+            Action::PrevPage => {
+                let result = load_previous_docs(self);
+                handle_docs_result(self, result);
+            }
+            Action::CycleDocView => self.doc_view_mode = self.next_doc_view_mode(),
+            Action::ToggleLogView => self.log_view = !self.log_view,
+            Action::CopyPermalink => {
+                if let Err(err) = copy_permalink(self) {
+                    self.last_error = Some(format!("permalink: {err:#}"));
+                }
+            }
+            Action::RefetchDoc => {
+                if let Err(err) = refetch_selected_doc(self) {
+                    self.last_error = Some(format!("re-fetch: {err:#}"));
+                }
+            }
+            Action::FollowTrace => {
+                if let Err(err) = open_trace_follow(self) {
+                    self.last_error = Some(format!("trace: {err:#}"));
+                }
+            }
+            Action::ExplainQuery => {
+                if let Err(err) = open_query_translation(self) {
+                    self.last_error = Some(format!("explain: {err:#}"));
+                }
+            }
+            Action::ToggleOperator => {
+                if let Err(err) = toggle_query_operator(self) {
+                    self.last_error = Some(format!("operator: {err:#}"));
+                }
+            }
+            Action::MoveTermsSelection(delta) => shift_terms_selection(self, delta),
+            Action::InputChar(ch) => self.input_char(ch),
+            Action::InputBackspace => self.input_backspace(),
+            Action::InputCancel => self.input_cancel(),
+            Action::InputSubmit => self.input_submit(),
+        }
+        let scope_changed = before.scope_kind != self.scope_kind
+            || before.scope.as_deref() != self.selected_scope_name();
+        if scope_changed && !toggling_scope && !continuing_step {
+            self.last_scope = Some(before);
+        }
+    }
+
+    fn close_panel(&mut self) {
+        self.info_panel = None;
+        self.prompt = None;
+    }
+
+    fn input_char(&mut self, ch: char) {
+        match self.input_mode {
+            InputMode::Normal => {}
+            InputMode::Query => self.query_edit.push(ch),
+            InputMode::Command => self.command_edit.push(ch),
+            InputMode::TermsPrefix => {
+                if let Some(lookup) = self.terms_lookup.as_mut() {
+                    lookup.prefix.push(ch);
+                }
+                refresh_terms_lookup(self);
+            }
+            InputMode::ScopeFilter => {
+                self.scope_filter_edit.push(ch);
+                self.scope_filter = self.scope_filter_edit.clone();
+            }
+            InputMode::ResultsFilter => {
+                self.results_filter.push(ch);
+                self.clamp_doc_selection();
+            }
+            InputMode::Jump => {
+                self.jump_edit.push(ch);
+                self.apply_jump();
+            }
+            InputMode::DrawerSearch => {
+                self.drawer_search.push(ch);
+                self.drawer_match = 0;
+            }
+            InputMode::ExportPath => self.export_edit.push(ch),
+            InputMode::ConfirmIndexAction => self.confirm_edit.push(ch),
+        }
+    }
+
+    fn input_backspace(&mut self) {
+        match self.input_mode {
+            InputMode::Normal => {}
+            InputMode::Query => {
+                self.query_edit.pop();
+            }
+            InputMode::Command => {
+                self.command_edit.pop();
+            }
+            InputMode::TermsPrefix => {
+                if let Some(lookup) = self.terms_lookup.as_mut() {
+                    lookup.prefix.pop();
+                }
+                refresh_terms_lookup(self);
+            }
+            InputMode::ScopeFilter => {
+                self.scope_filter_edit.pop();
+                self.scope_filter = self.scope_filter_edit.clone();
+            }
+            InputMode::ResultsFilter => {
+                self.results_filter.pop();
+                self.clamp_doc_selection();
+            }
+            InputMode::Jump => {
+                self.jump_edit.pop();
+                self.apply_jump();
+            }
+            InputMode::DrawerSearch => {
+                self.drawer_search.pop();
+                self.drawer_match = 0;
+            }
+            InputMode::ExportPath => {
+                self.export_edit.pop();
+            }
+            InputMode::ConfirmIndexAction => {
+                self.confirm_edit.pop();
+            }
+        }
+    }
+
+    fn input_cancel(&mut self) {
+        let mode = std::mem::replace(&mut self.input_mode, InputMode::Normal);
+        match mode {
+            InputMode::Normal => {}
+            InputMode::Query => self.query_edit.clear(),
+            InputMode::Command => self.command_edit.clear(),
+            InputMode::TermsPrefix => self.terms_lookup = None,
+            InputMode::ResultsFilter => {
+                self.results_filter.clear();
+                self.clamp_doc_selection();
+            }
+            InputMode::Jump => {
+                self.jump_edit.clear();
+                self.docs_state.select(self.jump_origin.take());
+            }
+            InputMode::DrawerSearch => self.drawer_search.clear(),
+            InputMode::ExportPath => self.export_edit.clear(),
+            InputMode::ConfirmIndexAction => {
+                self.confirm_edit.clear();
+                self.pending_index_action = None;
+                self.info_panel = None;
+            }
+            InputMode::ScopeFilter => {
+                self.scope_filter_edit.clear();
+                self.scope_filter.clear();
+                if self.ensure_scope_selection_visible() {
+                    handle_scope_change(self);
+                }
+            }
+        }
+    }
+
+    fn input_submit(&mut self) {
+        let mode = std::mem::replace(&mut self.input_mode, InputMode::Normal);
+        match mode {
+            InputMode::Normal => {}
+            InputMode::Query => {
+                let query = self.query_edit.trim().to_string();
+                if self.query_mode == QueryMode::Dsl && parse_dsl_query(&query).is_err() {
+                    self.input_mode = InputMode::Query;
+                    return;
+                }
+                if query != self.query {
+                    self.record_nav();
+                }
+                self.query = query;
+                self.reset_docs_paging();
+                handle_docs_refresh(self);
+            }
+            InputMode::Command => {
+                let command = std::mem::take(&mut self.command_edit);
+                run_command(self, &command);
+            }
+            InputMode::TermsPrefix => apply_selected_term(self),
+            InputMode::ResultsFilter => {
+                self.results_filter = self.results_filter.trim().to_string();
+                self.clamp_doc_selection();
+            }
+            InputMode::Jump => {
+                if self.jump_edit.trim().is_empty() {
+                    let visible = self.visible_docs().len();
+                    self.docs_state.select(visible.checked_sub(1));
+                }
+                self.jump_edit.clear();
+                self.jump_origin = None;
+            }
+            InputMode::DrawerSearch => self.drawer_search = self.drawer_search.trim().to_string(),
+            InputMode::ExportPath => {
+                let path = std::mem::take(&mut self.export_edit);
+                if let Err(err) = start_export(self, path.trim()) {
+                    self.last_error = Some(format!("export: {err:#}"));
+                }
+            }
+            InputMode::ConfirmIndexAction => {
+                let result = submit_index_confirmation(self);
+                report_action_result(self, "index", result);
+            }
+            InputMode::ScopeFilter => {
+                self.scope_filter = self.scope_filter_edit.trim().to_string();
+                if self.ensure_scope_selection_visible() {
+                    handle_scope_change(self);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::AliasEntry;
+
+    #[test]
+    fn action_names_drop_payloads_of_every_variant_shape() {
+        assert_eq!(Action::Refresh.name(), "Refresh");
+        assert_eq!(Action::ScrollPanel(-3).name(), "ScrollPanel");
+        assert_eq!(
+            Action::AddFieldFilter { exclude: true }.name(),
+            "AddFieldFilter"
+        );
+        let mut app = test_app();
+        app.update(Action::AddFieldFilter { exclude: false });
+        assert_eq!(app.usage.counts.get("action:AddFieldFilter"), Some(&1));
+    }
+
+    #[test]
+    fn usage_skips_keystrokes_and_cursor_moves() {
+        let mut app = test_app();
+        app.update(Action::StartQuery);
+        app.update(Action::InputChar('a'));
+        app.update(Action::InputBackspace);
+        app.update(Action::InputCancel);
+        app.update(Action::SelectNext);
+        app.update(Action::MoveCellCursor(1));
+        let keys: Vec<&str> = app.usage.counts.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["action:InputCancel", "action:StartQuery"]);
+    }
+
+    #[test]
+    fn query_editing_cancel_keeps_previous_query() {
+        let mut app = test_app();
+        app.query = "status:200".to_string();
+        app.update(Action::StartQuery);
+        assert_eq!(app.input_mode, InputMode::Query);
+        assert_eq!(app.query_edit, "status:200");
+        app.update(Action::InputBackspace);
+        app.update(Action::InputChar('4'));
+        assert_eq!(app.query_edit, "status:204");
+        app.update(Action::InputCancel);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.query, "status:200");
+        assert!(app.query_edit.is_empty());
+    }
+
+    #[test]
+    fn scope_filter_applies_while_typing_and_clears_on_cancel() {
+        let mut app = test_app();
+        app.update(Action::StartScopeFilter);
+        for ch in "logs".chars() {
+            app.update(Action::InputChar(ch));
+        }
+        assert_eq!(app.scope_filter, "logs");
+        app.update(Action::InputCancel);
+        assert!(app.scope_filter.is_empty());
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn cluster_picker_lists_profiles_and_clamps_selection() {
+        let mut app = test_app();
+        for name in ["prod", "staging"] {
+            app.config
+                .clusters
+                .insert(name.to_string(), crate::config::ClusterConfig::default());
+        }
+        app.update(Action::OpenClusterPicker);
+        assert_eq!(app.prompt, Some(Prompt::PickCluster(0)));
+        assert_eq!(
+            app.info_panel.as_ref().map(|panel| panel.lines.len()),
+            Some(3)
+        );
+        for _ in 0..5 {
+            app.update(Action::MoveClusterSelection(1));
+        }
+        assert_eq!(app.prompt, Some(Prompt::PickCluster(2)));
+        app.update(Action::MoveClusterSelection(-1));
+        assert_eq!(app.prompt, Some(Prompt::PickCluster(1)));
+        app.update(Action::ClosePanel);
+        assert_eq!(app.prompt, None);
+    }
+
+    #[test]
+    fn expand_cell_shows_the_full_selected_column_value() {
+        let mut app = test_app_with_index("logs");
+        app.documents = vec![test_doc(
+            "logs",
+            "a1",
+            serde_json::json!({ "level": "error", "error": { "stack": "boom\n  at main" } }),
+        )];
+        app.docs_state.select(Some(0));
+        let key = app.note_key("logs");
+        app.state
+            .columns
+            .insert(key, vec!["level".to_string(), "error.stack".to_string()]);
+        app.update(Action::MoveCellCursor(5));
+        assert_eq!(app.selected_column(), Some("error.stack"));
+        app.update(Action::ExpandCell);
+        let panel = app.info_panel.as_ref().unwrap();
+        assert_eq!(panel.title, "a1 error.stack");
+        assert_eq!(panel.lines.len(), 2);
+        assert!(panel.wrap);
+        app.documents[0].source = serde_json::json!({ "level": "warn" });
+        app.update(Action::ExpandCell);
+        let panel = app.info_panel.as_ref().unwrap();
+        assert_eq!(panel.lines[0].to_string(), "(missing)");
+        app.docs_state.select(None);
+        app.update(Action::ExpandCell);
+        assert_eq!(
+            app.last_error.as_deref(),
+            Some("cell: no document selected")
+        );
+    }
+
+    #[test]
+    fn quit_with_jobs_asks_for_confirmation() {
+        let mut app = test_app();
+        app.start_job("export logs.ndjson", None);
+        app.update(Action::Quit);
+        assert!(!app.should_quit);
+        assert_eq!(app.prompt, Some(Prompt::Quit));
+        assert!(app.info_panel.is_some());
+        app.update(Action::ClosePanel);
+        assert!(app.prompt.is_none());
+        assert!(!app.should_quit);
+        app.update(Action::Quit);
+        app.update(Action::DetachAndQuit);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn toggle_last_scope_restores_previous_scope_and_query() {
+        let mut app = test_app();
+        app.aliases = vec![
+            AliasEntry {
+                alias: "logs".to_string(),
+                targets: Vec::new(),
+            },
+            AliasEntry {
+                alias: "metrics".to_string(),
+                targets: Vec::new(),
+            },
+        ];
+        app.query = "status:500".to_string();
+        app.update(Action::ToggleLastScope);
+        assert_eq!(app.scope_kind, ScopeKind::Indices);
+        assert_eq!(app.query, "status:500");
+        assert!(app.nav_back.is_empty());
+        app.update(Action::SelectScopeKind(ScopeKind::Aliases));
+        app.aliases_state.select(Some(0));
+        app.update(Action::SelectNext);
+        assert_eq!(app.selected_scope_name(), Some("metrics"));
+        app.query = "cpu:>90".to_string();
+        app.update(Action::ToggleLastScope);
+        assert_eq!(app.selected_scope_name(), Some("logs"));
+        assert_eq!(app.query, "status:500");
+        app.update(Action::ToggleLastScope);
+        assert_eq!(app.selected_scope_name(), Some("metrics"));
+        assert_eq!(app.query, "cpu:>90");
+    }
+
+    #[test]
+    fn jump_selects_row_number_or_first_match() {
+        let mut app = test_app();
+        app.documents = ["a1", "b2", "c3"]
+            .iter()
+            .map(|id| {
+                test_doc(
+                    "logs-2024.01",
+                    id,
+                    serde_json::json!({ "host": format!("web-{id}") }),
+                )
+            })
+            .collect();
+        app.docs_state.select(Some(0));
+        app.update(Action::StartJump);
+        app.update(Action::InputChar('3'));
+        assert_eq!(app.docs_state.selected(), Some(2));
+        app.update(Action::InputBackspace);
+        for ch in "web-b".chars() {
+            app.update(Action::InputChar(ch));
+        }
+        assert_eq!(app.docs_state.selected(), Some(1));
+        app.update(Action::InputCancel);
+        assert_eq!(app.docs_state.selected(), Some(0));
+        app.update(Action::StartJump);
+        app.update(Action::InputSubmit);
+        assert_eq!(app.docs_state.selected(), Some(2));
+        app.docs_state.select(Some(0));
+        app.update(Action::StartJump);
+        app.update(Action::InputChar('9'));
+        app.update(Action::InputChar('9'));
+        assert_eq!(app.docs_state.selected(), Some(2));
+        app.update(Action::InputBackspace);
+        app.update(Action::InputBackspace);
+        for ch in "nowhere".chars() {
+            app.update(Action::InputChar(ch));
+        }
+        assert_eq!(app.docs_state.selected(), Some(2));
+        app.update(Action::InputCancel);
+        assert_eq!(app.docs_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn marked_rows_stay_in_the_basket_across_pages() {
+        let mut app = test_app();
+        app.documents = ["a1", "b2", "c3"]
+            .iter()
+            .map(|id| {
+                test_doc(
+                    "logs",
+                    id,
+                    serde_json::json!({ "host": format!("web-{id}") }),
+                )
+            })
+            .collect();
+        assert_eq!(
+            crate::commands::marked_export_text(&app, false)
+                .unwrap_err()
+                .to_string(),
+            "the basket is empty (Space marks a row)"
+        );
+        app.focus = Focus::Results;
+        app.docs_state.select(Some(0));
+        app.update(Action::ToggleMark);
+        assert_eq!(app.docs_state.selected(), Some(1));
+        app.update(Action::SelectNext);
+        app.update(Action::ToggleMark);
+        assert_eq!(
+            crate::commands::marked_export_text(&app, false).unwrap(),
+            "{\"_id\":\"a1\",\"_index\":\"logs\",\"_source\":{\"host\":\"web-a1\"}}\n\
+             {\"_id\":\"c3\",\"_index\":\"logs\",\"_source\":{\"host\":\"web-c3\"}}\n"
+        );
+        let array: serde_json::Value =
+            serde_json::from_str(&crate::commands::marked_export_text(&app, true).unwrap())
+                .unwrap();
+        assert_eq!(array.as_array().map(Vec::len), Some(2));
+        app.reset_docs_paging();
+        assert_eq!(app.marked_documents().len(), 2);
+        app.update(Action::OpenBasket);
+        app.update(Action::MoveBasketSelection(1));
+        app.update(Action::RemoveBasketDoc);
+        assert_eq!(app.prompt, Some(Prompt::PickBasket(0)));
+        assert_eq!(app.marked_documents()[0].id, "a1");
+        app.update(Action::OpenMarkedExport);
+        assert_eq!(app.prompt, Some(Prompt::ExportMarked(0)));
+    }
+
+    #[test]
+    fn destructive_index_actions_need_the_typed_name() {
+        let mut app = test_app_with_index("logs-1");
+        app.update(Action::OpenIndexActions);
+        app.update(Action::MoveIndexActionSelection(2));
+        assert_eq!(
+            app.prompt,
+            Some(Prompt::IndexActions {
+                index: "logs-1".to_string(),
+                selected: 2,
+            })
+        );
+        app.update(Action::RunIndexOp);
+        assert_eq!(app.input_mode, InputMode::ConfirmIndexAction);
+        assert_eq!(
+            app.pending_index_action,
+            Some((IndexAction::Delete, "logs-1".to_string()))
+        );
+        for ch in "logs-2".chars() {
+            app.update(Action::InputChar(ch));
+        }
+        app.update(Action::InputSubmit);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.pending_index_action.is_none());
+        assert!(app.info_panel.is_none());
+        let toast = app.active_toast().unwrap();
+        assert!(toast.error);
+        assert!(toast.message.contains("does not match logs-1"));
+        assert_eq!(
+            index_action_summary(&serde_json::json!({
+                "_shards": { "total": 4, "successful": 3, "failed": 1 }
+            })),
+            "3/4 shards ok, 1 failed"
+        );
+    }
+
+    #[test]
+    fn view_actions_toggle_state() {
+        let mut app = test_app();
+        app.update(Action::ToggleFocus);
+        assert_eq!(app.focus, Focus::Results);
+        app.update(Action::ToggleDocDrawer);
+        assert!(app.show_doc_drawer);
+        app.update(Action::CycleDocView);
+        assert_eq!(app.doc_view_mode, DocViewMode::Raw);
+        app.update(Action::CloseDocDrawer);
+        assert!(!app.show_doc_drawer);
+        app.update(Action::Quit);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn toggling_query_mode_keeps_the_request_body_and_the_typed_query() {
+        let mut app = test_app();
+        app.query = "status:500".to_string();
+        app.sample_seed = Some(7);
+        app.filters = vec![crate::client::FieldFilter {
+            field: "service".to_string(),
+            value: serde_json::json!("api"),
+            exclude: false,
+        }];
+        let before = crate::client::query_body(&app.query, &app.search_params());
+        app.update(Action::ToggleQueryMode);
+        assert_eq!(app.query_mode, QueryMode::Dsl);
+        let after = crate::client::query_body(&app.query, &app.search_params());
+        assert_eq!(after, before);
+        app.update(Action::ToggleQueryMode);
+        assert_eq!(app.query_mode, QueryMode::QueryString);
+        assert_eq!(app.query, "status:500");
+    }
+}
@@ -0,0 +1,2614 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use ratatui::text::Line;
+use ratatui::widgets::{ListState, TableState};
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+
+use crate::client::*;
+use crate::config::*;
+use crate::ecs::*;
+use crate::export::*;
+use crate::format::*;
+use crate::redact::*;
+use crate::state::*;
+use crate::stats::*;
+use crate::terminal::*;
+
+#[derive(Debug, Clone)]
+pub struct MasterChange {
+    pub at_millis: i64,
+    pub node: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataKind {
+    Health,
+    Indices,
+    Aliases,
+    DataStreams,
+    Docs,
+}
+
+impl DataKind {
+    pub fn for_scope(scope: ScopeKind) -> Self {
+        match scope {
+            ScopeKind::Indices => DataKind::Indices,
+            ScopeKind::Aliases => DataKind::Aliases,
+            ScopeKind::DataStreams => DataKind::DataStreams,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Freshness {
+    pub fetched_at: Option<Instant>,
+    pub failures: u32,
+}
+
+pub const TOAST_DURATION: Duration = Duration::from_secs(6);
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub error: bool,
+    pub at: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterEvent {
+    pub at_millis: i64,
+    pub level: EventLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    LeftNav,
+    Results,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputMode {
+    Normal,
+    Query,
+    ScopeFilter,
+    Command,
+    TermsPrefix,
+    ResultsFilter,
+    Jump,
+    DrawerSearch,
+    ExportPath,
+    ConfirmIndexAction,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonPathView {
+    pub expr: String,
+    pub column: bool,
+}
+
+pub struct TermsLookup {
+    pub field: String,
+    pub prefix: String,
+    pub terms: Vec<String>,
+    pub complete: bool,
+    pub selected: usize,
+    pub error: Option<String>,
+}
+
+pub enum BenchEvent {
+    Progress { done: u64, total: u64 },
+    Done(Vec<Line<'static>>),
+}
+
+pub struct Job {
+    pub label: String,
+    pub task_id: Option<String>,
+    pub started: Instant,
+    pub progress: Option<Receiver<ExportEvent>>,
+    pub bench: Option<Receiver<BenchEvent>>,
+    pub written: u64,
+    pub total: Option<u64>,
+}
+
+pub struct PitSession {
+    pub scope: String,
+    pub id: String,
+    pub keep_alive: String,
+    pub keep_alive_for: Duration,
+    pub expires: Instant,
+}
+
+impl PitSession {
+    pub fn remaining(&self) -> Duration {
+        self.expires.saturating_duration_since(Instant::now())
+    }
+
+    pub fn renew(&mut self) {
+        self.expires = Instant::now() + self.keep_alive_for;
+    }
+}
+
+pub struct InfoPanel {
+    pub title: String,
+    pub lines: Vec<Line<'static>>,
+    pub scroll: u16,
+    pub wrap: bool,
+}
+
+impl InfoPanel {
+    pub fn new(title: impl Into<String>, lines: Vec<Line<'static>>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+            scroll: 0,
+            wrap: false,
+        }
+    }
+
+    pub fn scroll_by(&mut self, delta: i32) {
+        let max = self.lines.len().saturating_sub(1) as i32;
+        self.scroll = (self.scroll as i32 + delta).clamp(0, max) as u16;
+    }
+}
+
+pub enum ScopeMatcher {
+    All,
+    Terms {
+        include: Vec<String>,
+        exclude: Vec<String>,
+    },
+    Regex(Regex),
+    Invalid(String),
+}
+
+impl ScopeMatcher {
+    pub fn parse(filter: &str) -> Self {
+        let filter = filter.trim();
+        if let Some(pattern) = filter.strip_prefix("re:") {
+            if pattern.is_empty() {
+                return Self::All;
+            }
+            return match RegexBuilder::new(pattern).case_insensitive(true).build() {
+                Ok(regex) => Self::Regex(regex),
+                Err(err) => Self::Invalid(regex_error_summary(&err)),
+            };
+        }
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for term in filter.to_lowercase().split_whitespace() {
+            match term.strip_prefix(['-', '!']) {
+                Some("") => {}
+                Some(negated) => exclude.push(negated.to_string()),
+                None => include.push(term.to_string()),
+            }
+        }
+        if include.is_empty() && exclude.is_empty() {
+            Self::All
+        } else {
+            Self::Terms { include, exclude }
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::All | Self::Invalid(_) => true,
+            Self::Terms { include, exclude } => {
+                let name = name.to_lowercase();
+                include.iter().all(|term| name.contains(term.as_str()))
+                    && !exclude.iter().any(|term| name.contains(term.as_str()))
+            }
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    Indices,
+    Aliases,
+    DataStreams,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Prompt {
+    Quit,
+    Reconnect(ClusterConfig),
+    PickCluster(usize),
+    BuildAggregation { kind: AggKind, field: usize },
+    PickSort { field: usize, order: SortOrder },
+    PickColumns(usize),
+    PickFavorite(usize),
+    PickView(usize),
+    PickBlob(usize),
+    ExportMarked(usize),
+    PickBasket(usize),
+    HealthDetail { unassigned: u64 },
+    IndexActions { index: String, selected: usize },
+    AliasChange(Vec<AliasOp>),
+    OpenScope(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavEntry {
+    pub scope_kind: ScopeKind,
+    pub scope: Option<String>,
+    pub query: String,
+    pub doc_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchTarget {
+    pub scope: Option<String>,
+    pub query: Option<String>,
+    pub doc_id: Option<String>,
+}
+
+impl LaunchTarget {
+    pub fn parse(args: &[&str]) -> Result<Self> {
+        let mut target = LaunchTarget::default();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let Some(value) = args.next() else {
+                anyhow::bail!("{flag} requires a value");
+            };
+            match *flag {
+                "--open" => match value.rsplit_once(':') {
+                    Some((scope, id)) if !scope.is_empty() && !id.is_empty() => {
+                        target.scope = Some(scope.to_string());
+                        target.doc_id = Some(id.to_string());
+                    }
+                    _ => anyhow::bail!("--open expects <scope>:<id>, got {value:?}"),
+                },
+                "--scope" => target.scope = Some(value.to_string()),
+                "--query" => target.query = Some(value.to_string()),
+                _ => anyhow::bail!("unrecognized argument {flag:?}"),
+            }
+        }
+        Ok(target)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    Unknown,
+    Connected,
+    Disconnected { since: Instant, failures: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocChange {
+    Unchanged,
+    Modified { version: Option<u64> },
+    Deleted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryMode {
+    QueryString,
+    Dsl,
+}
+
+impl QueryMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            QueryMode::QueryString => "QueryString",
+            QueryMode::Dsl => "DSL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RightTab {
+    Results,
+    Mappings,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MappingView {
+    pub scope: String,
+    pub fields: Vec<MappingField>,
+    pub state: ListState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocViewMode {
+    Pretty,
+    Raw,
+    Flatten,
+    Ecs,
+}
+
+pub struct App {
+    pub config: Config,
+    pub es_url: String,
+    pub client: reqwest::blocking::Client,
+    pub health: Option<ClusterHealth>,
+    pub indices: Vec<IndexEntry>,
+    pub aliases: Vec<AliasEntry>,
+    pub expanded_aliases: HashSet<String>,
+    pub tsdb: HashMap<String, TsdbInfo>,
+    pub index_tags: HashMap<String, IndexTags>,
+    pub enrichment: HashMap<String, HashMap<String, Option<String>>>,
+    pub master_changes: VecDeque<MasterChange>,
+    pub events: VecDeque<ClusterEvent>,
+    pub known_indices: Option<HashSet<String>>,
+    pub license: Option<LicenseInfo>,
+    pub datastreams: Vec<DataStreamEntry>,
+    pub documents: Vec<DocEntry>,
+    pub results_filter: String,
+    pub jump_edit: String,
+    pub jump_origin: Option<usize>,
+    pub docs_total: Option<u64>,
+    pub docs_from: u64,
+    pub docs_cursors: BTreeMap<u64, Value>,
+    pub docs_size: u64,
+    pub indices_state: ListState,
+    pub aliases_state: ListState,
+    pub datastreams_state: ListState,
+    pub docs_state: TableState,
+    pub focus: Focus,
+    pub input_mode: InputMode,
+    pub scope_kind: ScopeKind,
+    pub scope_pattern: Option<String>,
+    pub preference: Option<String>,
+    pub routing: Option<String>,
+    pub sample_seed: Option<u64>,
+    pub fuzzy: bool,
+    pub cluster_name: Option<String>,
+    pub default_cluster: ClusterConfig,
+    pub right_tab: RightTab,
+    pub query_mode: QueryMode,
+    pub saved_query_string: String,
+    pub mapping: Option<MappingView>,
+    pub cell_cursor: usize,
+    pub drawer_search: String,
+    pub export_edit: String,
+    pub confirm_edit: String,
+    pub pending_index_action: Option<(IndexAction, String)>,
+    pub toast: Option<Toast>,
+    pub drawer_match: usize,
+    pub basket: BTreeMap<(String, String), DocEntry>,
+    pub sort: Option<SortSpec>,
+    pub filters: Vec<FieldFilter>,
+    pub drawer_field: usize,
+    pub scope_filter: String,
+    pub scope_filter_edit: String,
+    pub query: String,
+    pub query_edit: String,
+    pub command_edit: String,
+    pub info_panel: Option<InfoPanel>,
+    pub terms_lookup: Option<TermsLookup>,
+    pub json_path: Option<JsonPathView>,
+    pub pending_reroute: Option<Value>,
+    pub jobs: Vec<Job>,
+    pub pit: Option<PitSession>,
+    pub nav_back: Vec<NavEntry>,
+    pub nav_forward: Vec<NavEntry>,
+    pub nav_stepping: bool,
+    pub last_scope: Option<NavEntry>,
+    pub prompt: Option<Prompt>,
+    pub state: AppState,
+    pub remote_notes: Option<BTreeMap<String, String>>,
+    pub config_modified: Option<SystemTime>,
+    pub time_zone: TimeZone,
+    pub redactor: Redactor,
+    pub unmasked: bool,
+    pub show_doc_drawer: bool,
+    pub doc_change: Option<(String, DocChange)>,
+    pub doc_view_mode: DocViewMode,
+    pub log_view: bool,
+    pub search_took_ms: Option<u64>,
+    pub search_shards_failed: Option<u64>,
+    pub search_timed_out: Option<bool>,
+    pub search_terminated_early: Option<bool>,
+    pub search_timeout: Option<String>,
+    pub terminate_after: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_fetch: Option<Instant>,
+    pub throttled: bool,
+    pub freshness: HashMap<DataKind, Freshness>,
+    pub connection: ConnectionState,
+    pub dirty: bool,
+    pub should_quit: bool,
+    pub caps: TerminalCaps,
+    pub update_available: Option<String>,
+    pub update_check: Option<Receiver<String>>,
+    pub usage: UsageStats,
+}
+
+#[cfg(test)]
+pub(crate) fn test_app() -> App {
+    let mut config = Config::default();
+    config.display.timezone = Some("UTC".to_string());
+    App::new(
+        config,
+        "http://127.0.0.1:9".to_string(),
+        reqwest::blocking::Client::new(),
+    )
+}
+
+#[cfg(test)]
+pub(crate) fn test_index(name: &str) -> IndexEntry {
+    IndexEntry {
+        health: "green".to_string(),
+        name: name.to_string(),
+        docs_count: None,
+        store_size: None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_doc(index: &str, id: &str, source: Value) -> DocEntry {
+    DocEntry {
+        index: index.to_string(),
+        id: id.to_string(),
+        source,
+        version: None,
+        seq_no: None,
+        sort: None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_app_with_index(name: &str) -> App {
+    let mut app = test_app();
+    app.indices = vec![test_index(name)];
+    app.indices_state.select(Some(0));
+    app
+}
+
+impl App {
+    pub fn new(config: Config, es_url: String, client: reqwest::blocking::Client) -> Self {
+        let mut indices_state = ListState::default();
+        indices_state.select(None);
+        let mut aliases_state = ListState::default();
+        aliases_state.select(None);
+        let docs_size = config.display.page_size.unwrap_or(5).max(1);
+        let preference = config.search.preference.clone();
+        let redactor = Redactor::new(&config.redaction.fields);
+        let log_view = config.display.log_view.unwrap_or(true);
+        let time_zone = TimeZone::from_setting(config.display.timezone.as_deref())
+            .unwrap_or_else(TimeZone::local);
+        let routing = config.search.routing.clone();
+        let default_cluster = config.cluster.clone();
+        let search_timeout = config.search.timeout.clone();
+        let terminate_after = config.search.terminate_after;
+        let mut datastreams_state = ListState::default();
+        datastreams_state.select(None);
+        let mut docs_state = TableState::default();
+        docs_state.select(None);
+        Self {
+            config,
+            es_url,
+            client,
+            health: None,
+            indices: Vec::new(),
+            aliases: Vec::new(),
+            expanded_aliases: HashSet::new(),
+            tsdb: HashMap::new(),
+            index_tags: HashMap::new(),
+            enrichment: HashMap::new(),
+            master_changes: VecDeque::new(),
+            events: VecDeque::new(),
+            known_indices: None,
+            license: None,
+            datastreams: Vec::new(),
+            documents: Vec::new(),
+            results_filter: String::new(),
+            jump_edit: String::new(),
+            jump_origin: None,
+            docs_total: None,
+            docs_from: 0,
+            docs_cursors: BTreeMap::new(),
+            docs_size,
+            indices_state,
+            aliases_state,
+            datastreams_state,
+            docs_state,
+            focus: Focus::LeftNav,
+            input_mode: InputMode::Normal,
+            scope_kind: ScopeKind::Indices,
+            scope_pattern: None,
+            preference,
+            routing,
+            sample_seed: None,
+            fuzzy: false,
+            cluster_name: None,
+            default_cluster,
+            right_tab: RightTab::Results,
+            query_mode: QueryMode::QueryString,
+            saved_query_string: String::new(),
+            mapping: None,
+            cell_cursor: 0,
+            drawer_search: String::new(),
+            export_edit: String::new(),
+            confirm_edit: String::new(),
+            pending_index_action: None,
+            toast: None,
+            drawer_match: 0,
+            basket: BTreeMap::new(),
+            sort: None,
+            filters: Vec::new(),
+            drawer_field: 0,
+            scope_filter: String::new(),
+            scope_filter_edit: String::new(),
+            query: String::new(),
+            query_edit: String::new(),
+            command_edit: String::new(),
+            info_panel: None,
+            terms_lookup: None,
+            json_path: None,
+            pending_reroute: None,
+            jobs: Vec::new(),
+            pit: None,
+            nav_back: Vec::new(),
+            nav_forward: Vec::new(),
+            nav_stepping: false,
+            last_scope: None,
+            prompt: None,
+            state: AppState::default(),
+            remote_notes: None,
+            config_modified: config_modified(),
+            time_zone,
+            redactor,
+            unmasked: false,
+            show_doc_drawer: false,
+            doc_change: None,
+            doc_view_mode: DocViewMode::Pretty,
+            log_view,
+            search_took_ms: None,
+            search_shards_failed: None,
+            search_timed_out: None,
+            search_terminated_early: None,
+            search_timeout,
+            terminate_after,
+            last_error: None,
+            last_fetch: None,
+            throttled: false,
+            freshness: HashMap::new(),
+            connection: ConnectionState::Unknown,
+            dirty: true,
+            should_quit: false,
+            caps: TerminalCaps::default(),
+            update_available: None,
+            update_check: None,
+            usage: UsageStats::default(),
+        }
+    }
+
+    pub fn selected_scope_name(&self) -> Option<&str> {
+        if let Some(pattern) = &self.scope_pattern {
+            return Some(pattern);
+        }
+        match self.scope_kind {
+            ScopeKind::Indices => self
+                .indices_state
+                .selected()
+                .and_then(|idx| self.indices.get(idx))
+                .map(|entry| entry.name.as_str()),
+            ScopeKind::Aliases => self
+                .aliases_state
+                .selected()
+                .and_then(|idx| self.aliases.get(idx))
+                .map(|entry| entry.alias.as_str()),
+            ScopeKind::DataStreams => self
+                .datastreams_state
+                .selected()
+                .and_then(|idx| self.datastreams.get(idx))
+                .map(|entry| entry.name.as_str()),
+        }
+    }
+
+    pub fn select_scope_by_name(&mut self, name: &str) -> bool {
+        let position = match self.scope_kind {
+            ScopeKind::Indices => self.indices.iter().position(|entry| entry.name == name),
+            ScopeKind::Aliases => self.aliases.iter().position(|entry| entry.alias == name),
+            ScopeKind::DataStreams => self.datastreams.iter().position(|entry| entry.name == name),
+        };
+        if position.is_some() {
+            self.scope_pattern = None;
+            self.set_scope_selected(position);
+        }
+        position.is_some()
+    }
+
+    pub fn log_view_active(&self) -> bool {
+        if !self.log_view {
+            return false;
+        }
+        let docs = self.visible_docs();
+        let ecs = docs.iter().filter(|doc| is_ecs_doc(&doc.source)).count();
+        ecs > 0 && ecs * 2 >= docs.len()
+    }
+
+    pub fn next_doc_view_mode(&self) -> DocViewMode {
+        let ecs = self
+            .selected_doc()
+            .is_some_and(|doc| is_ecs_doc(&doc.source));
+        match self.doc_view_mode {
+            DocViewMode::Pretty => DocViewMode::Raw,
+            DocViewMode::Raw => DocViewMode::Flatten,
+            DocViewMode::Flatten if ecs => DocViewMode::Ecs,
+            DocViewMode::Flatten | DocViewMode::Ecs => DocViewMode::Pretty,
+        }
+    }
+
+    pub fn enriched_value(&self, lookup: &EnrichConfig, doc: &DocEntry) -> Option<String> {
+        let value = ecs_text(&doc.source, &lookup.field)?;
+        let text = match self.enrichment.get(&lookup.field)?.get(&value)? {
+            Some(display) => display.clone(),
+            None => format!("{value}?"),
+        };
+        Some(text)
+    }
+
+    pub fn display_source<'a>(&self, doc: &'a DocEntry) -> Cow<'a, Value> {
+        if self.unmasked || self.redactor.is_empty() {
+            Cow::Borrowed(&doc.source)
+        } else {
+            Cow::Owned(self.redactor.redact(&doc.source, &mut 0))
+        }
+    }
+
+    pub fn search_params(&self) -> SearchParams {
+        let time_zone = (self.time_zone.offset_minutes != 0).then(|| self.time_zone.offset());
+        if let Some(pit) = &self.pit
+            && self.selected_scope_name() == Some(pit.scope.as_str())
+        {
+            return SearchParams {
+                pit: Some(PitRef {
+                    id: pit.id.clone(),
+                    keep_alive: pit.keep_alive.clone(),
+                }),
+                time_zone,
+                sample_seed: self.sample_seed,
+                query_options: self.query_options(),
+                fuzzy: self.fuzzy,
+                template: self.request_template(),
+                timeout: self.search_timeout.clone(),
+                terminate_after: self.terminate_after,
+                dsl: self.dsl_query(),
+                sort: self.sort.clone(),
+                filters: self.filters.clone(),
+                ..SearchParams::default()
+            };
+        }
+        let mut params = SearchParams {
+            time_zone,
+            sample_seed: self.sample_seed,
+            query_options: self.query_options(),
+            fuzzy: self.fuzzy,
+            template: self.request_template(),
+            timeout: self.search_timeout.clone(),
+            terminate_after: self.terminate_after,
+            dsl: self.dsl_query(),
+            sort: self.sort.clone(),
+            filters: self.filters.clone(),
+            preference: self.preference.clone(),
+            routing: self.routing.clone(),
+            ..SearchParams::default()
+        };
+        if self.scope_pattern.is_some() {
+            params.max_concurrent_shard_requests = self.config.search.max_concurrent_shard_requests;
+            params.ignore_unavailable = self.config.search.ignore_unavailable;
+        }
+        params
+    }
+
+    pub fn note_key(&self, scope: &str) -> String {
+        let cluster = self
+            .health
+            .as_ref()
+            .map(|health| health.cluster_name.as_str())
+            .unwrap_or(&self.es_url);
+        format!("{cluster}/{scope}")
+    }
+
+    pub fn request_template(&self) -> Option<Value> {
+        let scope = self.selected_scope_name()?;
+        let mut merged: Option<Value> = None;
+        for template in &self.config.templates {
+            if !glob_regex(&template.scope).is_some_and(|pattern| pattern.is_match(scope)) {
+                continue;
+            }
+            let body = Value::Object(template.body.clone());
+            match &mut merged {
+                Some(merged) => apply_template(merged, &body),
+                None => merged = Some(body),
+            }
+        }
+        merged
+    }
+
+    pub fn dsl_query(&self) -> Option<Value> {
+        match self.query_mode {
+            QueryMode::Dsl => parse_dsl_query(&self.query).ok(),
+            QueryMode::QueryString => None,
+        }
+    }
+
+    pub fn partial_results(&self) -> bool {
+        self.search_timed_out == Some(true) || self.search_terminated_early == Some(true)
+    }
+
+    pub fn mappings_focused(&self) -> bool {
+        self.right_tab == RightTab::Mappings && self.focus == Focus::Results
+    }
+
+    pub fn query_options(&self) -> QueryOptions {
+        self.selected_scope_name()
+            .and_then(|scope| self.state.query_options.get(&self.note_key(scope)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn result_columns(&self) -> &[String] {
+        self.selected_scope_name()
+            .and_then(|scope| self.state.columns.get(&self.note_key(scope)))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn selected_column(&self) -> Option<&str> {
+        let columns = self.result_columns();
+        columns
+            .get(self.cell_cursor.min(columns.len().saturating_sub(1)))
+            .map(String::as_str)
+    }
+
+    pub fn move_cell_cursor(&mut self, delta: isize) {
+        let last = self.result_columns().len().saturating_sub(1);
+        self.cell_cursor = self
+            .cell_cursor
+            .min(last)
+            .saturating_add_signed(delta)
+            .min(last);
+    }
+
+    pub fn favorites(&self) -> Vec<&str> {
+        let prefix = self.note_key("");
+        self.state
+            .favorites
+            .iter()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .collect()
+    }
+
+    pub fn note_for(&self, scope: &str) -> Option<&str> {
+        self.state
+            .notes
+            .get(&self.note_key(scope))
+            .or_else(|| self.remote_notes.as_ref()?.get(scope))
+            .map(|note| note.as_str())
+    }
+
+    pub fn nav_entry(&self) -> NavEntry {
+        NavEntry {
+            scope_kind: self.scope_kind,
+            scope: self.selected_scope_name().map(|name| name.to_string()),
+            query: self.query.clone(),
+            doc_id: self
+                .show_doc_drawer
+                .then(|| self.selected_doc().map(|doc| doc.id.clone()))
+                .flatten(),
+        }
+    }
+
+    pub fn record_nav(&mut self) {
+        let entry = self.nav_entry();
+        if self.nav_back.last() != Some(&entry) {
+            self.nav_back.push(entry);
+            if self.nav_back.len() > NAV_HISTORY_LIMIT {
+                self.nav_back.remove(0);
+            }
+        }
+        self.nav_forward.clear();
+    }
+
+    pub fn set_scope_kind(&mut self, scope: ScopeKind) {
+        if self.scope_kind == scope && self.scope_pattern.is_none() {
+            return;
+        }
+        self.scope_kind = scope;
+        self.scope_pattern = None;
+        self.reset_docs_paging();
+    }
+
+    pub fn select_next_scope_item(&mut self) {
+        self.shift_scope_selection(1);
+    }
+
+    pub fn select_prev_scope_item(&mut self) {
+        self.shift_scope_selection(-1);
+    }
+
+    pub fn shift_scope_selection(&mut self, delta: isize) {
+        let filtered = self.filtered_scope_indices();
+        if filtered.is_empty() {
+            self.set_scope_selected(None);
+            return;
+        }
+        let current = self.scope_selected();
+        let current_pos = current
+            .and_then(|idx| filtered.iter().position(|value| *value == idx))
+            .unwrap_or(0);
+        let next_pos = if delta >= 0 {
+            (current_pos + 1) % filtered.len()
+        } else if current_pos == 0 {
+            filtered.len() - 1
+        } else {
+            current_pos - 1
+        };
+        self.scope_pattern = None;
+        self.set_scope_selected(Some(filtered[next_pos]));
+        self.reset_docs_paging();
+    }
+
+    pub fn visible_docs(&self) -> Vec<&DocEntry> {
+        self.documents
+            .iter()
+            .filter(|doc| doc_matches_filter(doc, &self.results_filter))
+            .collect()
+    }
+
+    pub fn selected_doc(&self) -> Option<&DocEntry> {
+        let idx = self.docs_state.selected()?;
+        self.visible_docs().get(idx).copied()
+    }
+
+    pub fn clamp_doc_selection(&mut self) {
+        let visible = self.visible_docs().len();
+        if visible == 0 {
+            self.docs_state.select(None);
+        } else {
+            let selected = self.docs_state.selected().unwrap_or(0);
+            self.docs_state.select(Some(selected.min(visible - 1)));
+        }
+    }
+
+    pub fn apply_jump(&mut self) {
+        let target = self.jump_edit.trim();
+        let visible = self.visible_docs();
+        if target.is_empty() || visible.is_empty() {
+            return;
+        }
+        let row = match target.parse::<usize>() {
+            Ok(number) => {
+                let offset = (number as u64).saturating_sub(self.docs_from + 1) as usize;
+                Some(offset.min(visible.len() - 1))
+            }
+            Err(_) => {
+                let start = self.jump_origin.unwrap_or(0);
+                (0..visible.len())
+                    .map(|offset| (start + offset) % visible.len())
+                    .find(|idx| doc_matches_filter(visible[*idx], target))
+            }
+        };
+        if row.is_some() {
+            self.docs_state.select(row);
+        }
+    }
+
+    pub fn select_next_doc(&mut self) {
+        let visible = self.visible_docs().len();
+        if visible == 0 {
+            self.docs_state.select(None);
+            return;
+        }
+        let next = match self.docs_state.selected() {
+            Some(idx) if idx + 1 < visible => idx + 1,
+            _ => 0,
+        };
+        self.docs_state.select(Some(next));
+    }
+
+    pub fn select_prev_doc(&mut self) {
+        let visible = self.visible_docs().len();
+        if visible == 0 {
+            self.docs_state.select(None);
+            return;
+        }
+        let prev = match self.docs_state.selected() {
+            Some(0) | None => visible - 1,
+            Some(idx) => idx - 1,
+        };
+        self.docs_state.select(Some(prev));
+    }
+
+    pub fn set_alias_expanded(&mut self, expanded: bool) {
+        let Some(alias) = self.selected_scope_name().map(|name| name.to_string()) else {
+            return;
+        };
+        if expanded {
+            self.expanded_aliases.insert(alias);
+        } else {
+            self.expanded_aliases.remove(&alias);
+        }
+    }
+
+    pub fn start_job(&mut self, label: impl Into<String>, task_id: Option<String>) {
+        self.jobs.push(Job {
+            label: label.into(),
+            task_id,
+            started: Instant::now(),
+            progress: None,
+            bench: None,
+            written: 0,
+            total: None,
+        });
+    }
+
+    pub fn bench_running(&self) -> bool {
+        self.jobs.iter().any(|job| job.bench.is_some())
+    }
+
+    pub fn poll_jobs(&mut self) {
+        let mut finished = Vec::new();
+        let mut reports = HashMap::new();
+        for (idx, job) in self.jobs.iter_mut().enumerate() {
+            if let Some(receiver) = &job.bench {
+                loop {
+                    match receiver.try_recv() {
+                        Ok(BenchEvent::Progress { done, total }) => {
+                            job.written = done;
+                            job.total = Some(total);
+                        }
+                        Ok(BenchEvent::Done(lines)) => {
+                            reports.insert(idx, lines);
+                            finished.push((idx, None));
+                            break;
+                        }
+                        Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                            finished.push((idx, Some("benchmark stopped".to_string())));
+                            break;
+                        }
+                    }
+                }
+                self.dirty = true;
+                continue;
+            }
+            let Some(receiver) = &job.progress else {
+                continue;
+            };
+            loop {
+                match receiver.try_recv() {
+                    Ok(ExportEvent::Progress { written, total }) => {
+                        job.written = written;
+                        job.total = total;
+                    }
+                    Ok(ExportEvent::Done { written }) => {
+                        job.written = written;
+                        finished.push((idx, None));
+                        break;
+                    }
+                    Ok(ExportEvent::Failed(err)) => {
+                        finished.push((idx, Some(err)));
+                        break;
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        finished.push((idx, Some("export stopped".to_string())));
+                        break;
+                    }
+                }
+            }
+            self.dirty = true;
+        }
+        for (idx, err) in finished.into_iter().rev() {
+            let job = self.jobs.remove(idx);
+            match (err, reports.remove(&idx)) {
+                (Some(err), _) => self.last_error = Some(format!("{}: {err}", job.label)),
+                (None, Some(lines)) => self.info_panel = Some(InfoPanel::new("Benchmark", lines)),
+                (None, None) => {
+                    self.info_panel = Some(InfoPanel::new(
+                        "Export finished",
+                        vec![Line::from(format!(
+                            "{}: {} documents in {}s",
+                            job.label,
+                            job.written,
+                            job.started.elapsed().as_secs()
+                        ))],
+                    ))
+                }
+            }
+        }
+    }
+
+    pub fn reset_docs_paging(&mut self) {
+        self.docs_from = 0;
+        self.docs_cursors.clear();
+        self.docs_total = None;
+        self.documents.clear();
+        self.docs_state.select(None);
+    }
+
+    pub fn is_marked(&self, doc: &DocEntry) -> bool {
+        self.basket
+            .contains_key(&(doc.index.clone(), doc.id.clone()))
+    }
+
+    pub fn marked_documents(&self) -> Vec<&DocEntry> {
+        self.basket.values().collect()
+    }
+
+    pub fn max_buffered_docs(&self) -> usize {
+        let cap = self.config.display.max_buffered_docs.unwrap_or(500);
+        cap.max(self.docs_size as usize)
+    }
+
+    pub fn pages_by_cursor(&self) -> bool {
+        self.search_params().pit.is_some()
+    }
+
+    pub fn next_docs_page(&self) -> Option<DocsPage> {
+        let loaded_end = self.docs_from + self.documents.len() as u64;
+        if self.pages_by_cursor()
+            && let Some(after) = self.documents.last().and_then(|doc| doc.sort.clone())
+        {
+            return Some(DocsPage::After(after));
+        }
+        (loaded_end + self.docs_size * 2 <= MAX_RESULT_WINDOW || self.pages_by_cursor())
+            .then_some(DocsPage::From(loaded_end))
+    }
+
+    pub fn docs_page_at(&self, offset: u64) -> DocsPage {
+        match self.docs_cursors.get(&offset) {
+            Some(after) if offset > 0 && self.pages_by_cursor() => DocsPage::After(after.clone()),
+            _ => DocsPage::From(offset),
+        }
+    }
+
+    pub fn has_more_docs(&self) -> bool {
+        let loaded_end = self.docs_from + self.documents.len() as u64;
+        self.docs_total.is_some_and(|total| loaded_end < total)
+    }
+
+    pub fn selection_at_end(&self) -> bool {
+        let visible = self.visible_docs().len();
+        visible == 0 || self.docs_state.selected() == Some(visible - 1)
+    }
+
+    fn apply_search_summary(&mut self, summary: &SearchSummary) {
+        self.docs_total = summary.total;
+        self.search_took_ms = summary.took;
+        self.search_shards_failed = summary.shards_failed;
+        self.search_timed_out = summary.timed_out;
+        self.search_terminated_early = summary.terminated_early;
+        if let Some(pit) = self.pit.as_mut()
+            && let Some(id) = &summary.pit_id
+        {
+            pit.id = id.clone();
+            pit.renew();
+        }
+    }
+
+    fn count_visible(&self, docs: &[DocEntry]) -> usize {
+        docs.iter()
+            .filter(|doc| doc_matches_filter(doc, &self.results_filter))
+            .count()
+    }
+
+    fn shift_doc_selection(&mut self, removed: usize, inserted: usize) {
+        if let Some(selected) = self.docs_state.selected() {
+            self.docs_state
+                .select(Some((selected + inserted).saturating_sub(removed)));
+        }
+    }
+
+    pub fn scope_selected(&self) -> Option<usize> {
+        match self.scope_kind {
+            ScopeKind::Indices => self.indices_state.selected(),
+            ScopeKind::Aliases => self.aliases_state.selected(),
+            ScopeKind::DataStreams => self.datastreams_state.selected(),
+        }
+    }
+
+    pub fn set_scope_selected(&mut self, idx: Option<usize>) {
+        match self.scope_kind {
+            ScopeKind::Indices => self.indices_state.select(idx),
+            ScopeKind::Aliases => self.aliases_state.select(idx),
+            ScopeKind::DataStreams => self.datastreams_state.select(idx),
+        }
+    }
+
+    pub fn filtered_scope_indices(&self) -> Vec<usize> {
+        self.filtered_scope_indices_for(self.scope_kind)
+    }
+
+    pub fn filtered_scope_indices_for(&self, kind: ScopeKind) -> Vec<usize> {
+        let matcher = ScopeMatcher::parse(&self.scope_filter);
+        match kind {
+            ScopeKind::Indices => {
+                let texts: Vec<String> = self
+                    .indices
+                    .iter()
+                    .map(|entry| index_search_text(&entry.name, self.index_tags.get(&entry.name)))
+                    .collect();
+                filter_indices_by(&texts, &matcher, |text| text)
+            }
+            ScopeKind::Aliases => filter_indices_by(&self.aliases, &matcher, |entry| &entry.alias),
+            ScopeKind::DataStreams => {
+                filter_indices_by(&self.datastreams, &matcher, |entry| &entry.name)
+            }
+        }
+    }
+
+    pub fn poll_config_reload(&mut self) -> Option<ClusterConfig> {
+        let modified = config_modified();
+        if modified.is_none() || modified == self.config_modified {
+            return None;
+        }
+        self.config_modified = modified;
+        match load_config() {
+            Ok(Some(config)) => self.apply_config(config),
+            Ok(None) => None,
+            Err(err) => {
+                self.last_error = Some(format!("config: {err:#}"));
+                None
+            }
+        }
+    }
+
+    pub fn apply_config(&mut self, mut config: Config) -> Option<ClusterConfig> {
+        if self.caps.color == ColorDepth::None {
+            config.display.plain = true;
+        }
+        let profile = self
+            .cluster_name
+            .as_ref()
+            .and_then(|name| config.clusters.get(name))
+            .cloned();
+        let cluster = std::mem::replace(&mut config.cluster, self.config.cluster.clone());
+        self.default_cluster = cluster.clone();
+        let cluster = profile.unwrap_or(cluster);
+        self.docs_size = config.display.page_size.unwrap_or(5).max(1);
+        self.redactor = Redactor::new(&config.redaction.fields);
+        if config.enrich != self.config.enrich {
+            self.enrichment.clear();
+        }
+        self.time_zone = TimeZone::from_setting(config.display.timezone.as_deref())
+            .unwrap_or_else(TimeZone::local);
+        self.config = config;
+        self.dirty = true;
+        (cluster != self.config.cluster).then_some(cluster)
+    }
+
+    pub fn poll_update_check(&mut self) {
+        let Some(receiver) = &self.update_check else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(version) => {
+                self.update_available = Some(version);
+                self.update_check = None;
+                self.dirty = true;
+            }
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => self.update_check = None,
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+        }
+    }
+
+    pub fn plain(&self) -> bool {
+        self.config.display.plain
+    }
+
+    pub fn tick_rate(&self) -> Duration {
+        let display = &self.config.display;
+        let default_ms = if display.low_refresh { 1000 } else { 200 };
+        Duration::from_millis(display.tick_ms.unwrap_or(default_ms).max(10))
+    }
+
+    pub fn needs_redraw(&self) -> bool {
+        !self.config.display.low_refresh || self.dirty
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        match self.connection {
+            ConnectionState::Disconnected { failures, .. } => {
+                let backoff =
+                    REFRESH_INTERVAL * 2u32.saturating_pow(failures.saturating_sub(1).min(6));
+                backoff.min(MAX_REFRESH_BACKOFF)
+            }
+            _ => REFRESH_INTERVAL,
+        }
+    }
+
+    pub fn show_toast(&mut self, message: impl Into<String>, error: bool) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            error,
+            at: Instant::now(),
+        });
+        self.dirty = true;
+    }
+
+    pub fn active_toast(&self) -> Option<&Toast> {
+        self.toast
+            .as_ref()
+            .filter(|toast| toast.at.elapsed() < TOAST_DURATION)
+    }
+
+    pub fn track_fetch<T>(&mut self, kind: DataKind, result: Result<T>) -> Result<T> {
+        let freshness = self.freshness.entry(kind).or_default();
+        match &result {
+            Ok(_) => {
+                freshness.fetched_at = Some(Instant::now());
+                freshness.failures = 0;
+            }
+            Err(_) => freshness.failures += 1,
+        }
+        result
+    }
+
+    pub fn over_request_budget(&self) -> bool {
+        self.config
+            .refresh
+            .max_requests_per_minute
+            .is_some_and(|max| recent_request_count() >= max as usize)
+    }
+
+    pub fn refresh_due(&self) -> bool {
+        self.last_fetch
+            .is_none_or(|at| at.elapsed() >= self.refresh_interval())
+    }
+
+    pub fn record_event(&mut self, level: EventLevel, message: impl Into<String>) {
+        self.events.push_back(ClusterEvent {
+            at_millis: now_millis(),
+            level,
+            message: message.into(),
+        });
+        while self.events.len() > EVENT_HISTORY_LIMIT {
+            self.events.pop_front();
+        }
+    }
+
+    pub fn mark_connected(&mut self) -> bool {
+        let reconnected = matches!(self.connection, ConnectionState::Disconnected { .. });
+        if reconnected {
+            self.record_event(EventLevel::Info, "connection restored");
+        }
+        self.connection = ConnectionState::Connected;
+        reconnected
+    }
+
+    pub fn mark_disconnected(&mut self, err: &anyhow::Error) {
+        if matches!(self.connection, ConnectionState::Connected) {
+            self.record_event(EventLevel::Error, format!("connection lost: {err:#}"));
+        }
+        self.connection = match self.connection {
+            ConnectionState::Disconnected { since, failures } => ConnectionState::Disconnected {
+                since,
+                failures: failures.saturating_add(1),
+            },
+            _ => ConnectionState::Disconnected {
+                since: Instant::now(),
+                failures: 1,
+            },
+        };
+        self.last_error = Some(format!("cluster unreachable: {err:#}"));
+    }
+
+    pub fn ensure_scope_selection_visible(&mut self) -> bool {
+        let filtered = self.filtered_scope_indices();
+        if filtered.is_empty() {
+            if self.scope_selected().is_some() {
+                self.set_scope_selected(None);
+                return true;
+            }
+            return false;
+        }
+        if let Some(current) = self.scope_selected()
+            && filtered.contains(&current)
+        {
+            return false;
+        }
+        self.set_scope_selected(Some(filtered[0]));
+        true
+    }
+}
+
+pub fn scroll_info_panel(app: &mut App, delta: i32) {
+    if let Some(panel) = app.info_panel.as_mut() {
+        panel.scroll_by(delta);
+    }
+}
+
+pub fn refresh_background(app: &mut App) {
+    if app.over_request_budget() {
+        app.throttled = true;
+        app.dirty = true;
+        app.last_fetch = Some(Instant::now());
+        return;
+    }
+    refresh_all(app);
+}
+
+pub fn refresh_all(app: &mut App) {
+    let mut errors = Vec::new();
+    app.dirty = true;
+    app.last_fetch = Some(Instant::now());
+
+    let result = refresh_health(app);
+    match app.track_fetch(DataKind::Health, result) {
+        Err(err) if is_unreachable(&err) => {
+            app.mark_disconnected(&err);
+            return;
+        }
+        Err(err) => {
+            app.mark_connected();
+            errors.push(format!("health: {err:#}"));
+        }
+        Ok(()) => {
+            app.mark_connected();
+        }
+    }
+    let result = refresh_indices(app);
+    if let Err(err) = app.track_fetch(DataKind::Indices, result) {
+        errors.push(format!("indices: {err:#}"));
+    }
+    app.throttled = app.over_request_budget();
+    if !app.throttled {
+        refresh_metadata(app, &mut errors);
+    }
+    if app
+        .pit
+        .as_ref()
+        .is_some_and(|pit| pit.remaining().is_zero())
+    {
+        app.pit = None;
+        errors.push("pit: point in time expired, reading live data".to_string());
+    }
+    let result = refresh_docs(app);
+    if let Err(err) = app.track_fetch(DataKind::Docs, result) {
+        errors.push(format!("docs: {err:#}"));
+    }
+    if !app.throttled {
+        if let Err(err) = refresh_enrichment(app) {
+            errors.push(format!("enrich: {err:#}"));
+        }
+        if app.config.notes.remote
+            && app.remote_notes.is_none()
+            && let Err(err) = refresh_remote_notes(app)
+        {
+            errors.push(format!("notes: {err:#}"));
+        }
+        if let Err(err) = refresh_jobs(app) {
+            errors.push(format!("jobs: {err:#}"));
+        }
+    }
+
+    if errors.is_empty() {
+        app.last_error = None;
+    } else {
+        app.last_error = Some(errors.join(" | "));
+    }
+}
+
+fn refresh_metadata(app: &mut App, errors: &mut Vec<String>) {
+    let result = refresh_aliases(app);
+    if let Err(err) = app.track_fetch(DataKind::Aliases, result) {
+        errors.push(format!("aliases: {err:#}"));
+    }
+    if app.license.is_none()
+        && let Err(err) = refresh_license(app)
+    {
+        errors.push(format!("license: {err:#}"));
+    }
+    if let Err(err) = refresh_master(app) {
+        errors.push(format!("master: {err:#}"));
+    }
+    if let Err(err) = refresh_tsdb(app) {
+        errors.push(format!("tsdb: {err:#}"));
+    }
+    if let Err(err) = refresh_index_tags(app) {
+        errors.push(format!("tags: {err:#}"));
+    }
+    let result = refresh_datastreams(app);
+    if let Err(err) = app.track_fetch(DataKind::DataStreams, result) {
+        errors.push(format!("datastreams: {err:#}"));
+    }
+}
+
+pub const EVENT_HISTORY_LIMIT: usize = 200;
+
+pub fn health_events(before: &ClusterHealth, after: &ClusterHealth) -> Vec<(EventLevel, String)> {
+    let mut events = Vec::new();
+    if before.cluster_name != after.cluster_name {
+        return events;
+    }
+    if before.status != after.status {
+        let level = match after.status.as_str() {
+            "green" => EventLevel::Info,
+            "yellow" => EventLevel::Warn,
+            _ => EventLevel::Error,
+        };
+        events.push((
+            level,
+            format!("health {} -> {}", before.status, after.status),
+        ));
+    }
+    if before.number_of_nodes != after.number_of_nodes {
+        let level = if after.number_of_nodes < before.number_of_nodes {
+            EventLevel::Warn
+        } else {
+            EventLevel::Info
+        };
+        events.push((
+            level,
+            format!(
+                "nodes {} -> {}",
+                before.number_of_nodes, after.number_of_nodes
+            ),
+        ));
+    }
+    events
+}
+
+pub fn index_events(
+    before: &HashSet<String>,
+    after: &HashSet<String>,
+) -> Vec<(EventLevel, String)> {
+    let mut created: Vec<&String> = after.difference(before).collect();
+    let mut deleted: Vec<&String> = before.difference(after).collect();
+    created.sort();
+    deleted.sort();
+    created
+        .into_iter()
+        .map(|name| (EventLevel::Info, format!("index created: {name}")))
+        .chain(
+            deleted
+                .into_iter()
+                .map(|name| (EventLevel::Warn, format!("index deleted: {name}"))),
+        )
+        .collect()
+}
+
+pub fn refresh_health(app: &mut App) -> Result<()> {
+    let health = fetch_cluster_health(&app.client, &app.es_url)?;
+    if let Some(before) = &app.health {
+        for (level, message) in health_events(before, &health) {
+            app.record_event(level, message);
+        }
+    }
+    app.health = Some(health);
+    Ok(())
+}
+
+pub fn refresh_indices(app: &mut App) -> Result<()> {
+    let selected_name = app
+        .indices_state
+        .selected()
+        .and_then(|idx| app.indices.get(idx))
+        .map(|entry| entry.name.to_string());
+    let indices = fetch_indices(&app.client, &app.es_url)?;
+    let names: HashSet<String> = indices.iter().map(|entry| entry.name.clone()).collect();
+    if let Some(before) = &app.known_indices {
+        for (level, message) in index_events(before, &names) {
+            app.record_event(level, message);
+        }
+    }
+    app.known_indices = Some(names);
+    app.indices = indices;
+
+    let next_selected = if let Some(name) = selected_name {
+        app.indices.iter().position(|entry| entry.name == name)
+    } else {
+        None
+    };
+    if app.indices.is_empty() {
+        app.indices_state.select(None);
+    } else if let Some(idx) = next_selected {
+        app.indices_state.select(Some(idx));
+    } else {
+        app.indices_state.select(Some(0));
+    }
+    Ok(())
+}
+
+pub fn refresh_aliases(app: &mut App) -> Result<()> {
+    let selected_name = app
+        .aliases_state
+        .selected()
+        .and_then(|idx| app.aliases.get(idx))
+        .map(|entry| entry.alias.to_string());
+    let aliases = fetch_aliases(&app.client, &app.es_url)?;
+    app.aliases = aliases;
+
+    let next_selected = if let Some(name) = selected_name {
+        app.aliases.iter().position(|entry| entry.alias == name)
+    } else {
+        None
+    };
+    if app.aliases.is_empty() {
+        app.aliases_state.select(None);
+    } else if let Some(idx) = next_selected {
+        app.aliases_state.select(Some(idx));
+    } else {
+        app.aliases_state.select(Some(0));
+    }
+    Ok(())
+}
+
+pub fn refresh_license(app: &mut App) -> Result<()> {
+    let license = fetch_license(&app.client, &app.es_url);
+    app.license = Some(license.as_ref().cloned().unwrap_or_default());
+    license.map(|_| ())
+}
+
+pub const MASTER_HISTORY_LIMIT: usize = 20;
+
+pub fn refresh_master(app: &mut App) -> Result<()> {
+    let Some(node) = fetch_master_name(&app.client, &app.es_url)? else {
+        return Ok(());
+    };
+    if app.master_changes.back().map(|change| &change.node) != Some(&node) {
+        if !app.master_changes.is_empty() {
+            app.record_event(EventLevel::Warn, format!("master changed to {node}"));
+        }
+        app.master_changes.push_back(MasterChange {
+            at_millis: now_millis(),
+            node,
+        });
+        while app.master_changes.len() > MASTER_HISTORY_LIMIT {
+            app.master_changes.pop_front();
+        }
+    }
+    Ok(())
+}
+
+pub fn refresh_index_tags(app: &mut App) -> Result<()> {
+    let tags = &app.config.tags;
+    app.index_tags = fetch_index_tags(
+        &app.client,
+        &app.es_url,
+        &tags.meta_fields,
+        &tags.settings_keys,
+    )?;
+    Ok(())
+}
+
+pub fn refresh_tsdb(app: &mut App) -> Result<()> {
+    app.tsdb = fetch_tsdb_settings(&app.client, &app.es_url)?;
+    Ok(())
+}
+
+pub fn refresh_datastreams(app: &mut App) -> Result<()> {
+    let selected_name = app
+        .datastreams_state
+        .selected()
+        .and_then(|idx| app.datastreams.get(idx))
+        .map(|entry| entry.name.to_string());
+    let datastreams = fetch_datastreams(&app.client, &app.es_url)?;
+    app.datastreams = datastreams;
+
+    let next_selected = if let Some(name) = selected_name {
+        app.datastreams.iter().position(|entry| entry.name == name)
+    } else {
+        None
+    };
+    if app.datastreams.is_empty() {
+        app.datastreams_state.select(None);
+    } else if let Some(idx) = next_selected {
+        app.datastreams_state.select(Some(idx));
+    } else {
+        app.datastreams_state.select(Some(0));
+    }
+    Ok(())
+}
+
+pub fn refresh_docs(app: &mut App) -> Result<()> {
+    let Some(scope) = app.selected_scope_name().map(|name| name.to_string()) else {
+        app.documents.clear();
+        app.docs_total = None;
+        app.search_took_ms = None;
+        app.search_shards_failed = None;
+        app.search_timed_out = None;
+        app.search_terminated_early = None;
+        app.docs_state.select(None);
+        return Ok(());
+    };
+    let size = app.docs_size.max(app.documents.len() as u64);
+    let (docs, summary) = fetch_documents(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &app.docs_page_at(app.docs_from),
+        size,
+        &app.query,
+        &app.search_params(),
+    )?;
+    app.documents = docs;
+    app.apply_search_summary(&summary);
+    app.clamp_doc_selection();
+    Ok(())
+}
+
+const ENRICHMENT_CACHE_LIMIT: usize = 10_000;
+const MAX_RESULT_WINDOW: u64 = 10_000;
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_REFRESH_BACKOFF: Duration = Duration::from_secs(60);
+const AUTO_PIT_KEEP_ALIVE: &str = "5m";
+
+pub fn refresh_enrichment(app: &mut App) -> Result<()> {
+    for lookup in app.config.enrich.clone() {
+        let cache = app.enrichment.entry(lookup.field.clone()).or_default();
+        if cache.len() > ENRICHMENT_CACHE_LIMIT {
+            cache.clear();
+        }
+        let mut values: Vec<String> = Vec::new();
+        for doc in &app.documents {
+            if let Some(value) = ecs_text(&doc.source, &lookup.field)
+                && !cache.contains_key(&value)
+                && !values.contains(&value)
+            {
+                values.push(value);
+            }
+        }
+        if values.is_empty() {
+            continue;
+        }
+        let mut found = fetch_lookup(&app.client, &app.es_url, &lookup, &values)?;
+        let cache = app.enrichment.entry(lookup.field.clone()).or_default();
+        for value in values {
+            let display = found.remove(&value);
+            cache.insert(value, display);
+        }
+    }
+    Ok(())
+}
+
+pub fn refresh_remote_notes(app: &mut App) -> Result<()> {
+    app.remote_notes = Some(fetch_remote_notes(&app.client, &app.es_url)?);
+    Ok(())
+}
+
+pub fn set_note(app: &mut App, scope: &str, note: Option<String>) -> Result<()> {
+    let key = app.note_key(scope);
+    match &note {
+        Some(note) => app.state.notes.insert(key, note.clone()),
+        None => app.state.notes.remove(&key),
+    };
+    save_state(&app.state)?;
+    if app.config.notes.remote {
+        put_remote_note(&app.client, &app.es_url, scope, note.as_deref())?;
+        let remote = app.remote_notes.get_or_insert_with(BTreeMap::new);
+        match note {
+            Some(note) => remote.insert(scope.to_string(), note),
+            None => remote.remove(scope),
+        };
+    }
+    Ok(())
+}
+
+pub fn refresh_jobs(app: &mut App) -> Result<()> {
+    let mut finished = Vec::new();
+    for (idx, job) in app.jobs.iter().enumerate() {
+        if let Some(task_id) = &job.task_id
+            && fetch_task_completed(&app.client, &app.es_url, task_id)?
+        {
+            finished.push(idx);
+        }
+    }
+    for idx in finished.into_iter().rev() {
+        app.jobs.remove(idx);
+    }
+    Ok(())
+}
+
+pub fn cancel_jobs(app: &mut App) -> Result<()> {
+    while let Some(job) = app.jobs.first() {
+        if let Some(task_id) = &job.task_id {
+            cancel_task(&app.client, &app.es_url, task_id)
+                .with_context(|| format!("cancel {}", job.label))?;
+        }
+        app.jobs.remove(0);
+    }
+    Ok(())
+}
+
+pub fn load_more_docs(app: &mut App) -> Result<()> {
+    if !app.has_more_docs() {
+        return Ok(());
+    }
+    let Some(scope) = app.selected_scope_name().map(|name| name.to_string()) else {
+        return Ok(());
+    };
+    let page = match app.next_docs_page() {
+        Some(page) => page,
+        None => {
+            start_pit(app, AUTO_PIT_KEEP_ALIVE)?;
+            DocsPage::From(app.docs_from + app.documents.len() as u64)
+        }
+    };
+    let cursor = app.pages_by_cursor();
+    let (docs, summary) = fetch_documents(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &page,
+        app.docs_size,
+        &app.query,
+        &app.search_params(),
+    )?;
+    app.documents.extend(docs);
+    app.apply_search_summary(&summary);
+    let excess = app.documents.len().saturating_sub(app.max_buffered_docs());
+    if excess > 0 {
+        let removed = app.count_visible(&app.documents[..excess]);
+        if cursor && let Some(after) = app.documents[excess - 1].sort.clone() {
+            app.docs_cursors
+                .insert(app.docs_from + excess as u64, after);
+        }
+        app.documents.drain(..excess);
+        app.docs_from += excess as u64;
+        app.shift_doc_selection(removed, 0);
+    }
+    app.clamp_doc_selection();
+    Ok(())
+}
+
+pub fn load_previous_docs(app: &mut App) -> Result<()> {
+    if app.docs_from == 0 {
+        return Ok(());
+    }
+    let Some(scope) = app.selected_scope_name().map(|name| name.to_string()) else {
+        return Ok(());
+    };
+    let cursor = app.pages_by_cursor();
+    let (from, page) = match app.docs_cursors.range(..app.docs_from).next_back() {
+        Some((&from, after)) if from > 0 && cursor => (from, DocsPage::After(after.clone())),
+        _ => {
+            let from = app.docs_from.saturating_sub(app.docs_size);
+            (from, DocsPage::From(from))
+        }
+    };
+    let (docs, summary) = fetch_documents(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &page,
+        app.docs_from - from,
+        &app.query,
+        &app.search_params(),
+    )?;
+    let inserted = app.count_visible(&docs);
+    app.documents.splice(0..0, docs);
+    app.docs_from = from;
+    app.apply_search_summary(&summary);
+    app.shift_doc_selection(0, inserted);
+    let cap = app.max_buffered_docs();
+    app.documents.truncate(cap);
+    app.clamp_doc_selection();
+    Ok(())
+}
+
+pub fn refetch_selected_doc(app: &mut App) -> Result<()> {
+    let loaded = app
+        .selected_doc()
+        .cloned()
+        .context("no document selected")?;
+    let fetched = fetch_document(&app.client, &app.es_url, &loaded.index, &loaded.id)?;
+    let change = match &fetched {
+        None => DocChange::Deleted,
+        Some(doc) if doc.version == loaded.version && doc.seq_no == loaded.seq_no => {
+            DocChange::Unchanged
+        }
+        Some(_) => DocChange::Modified {
+            version: loaded.version,
+        },
+    };
+    if let Some(doc) = fetched
+        && let Some(slot) = app
+            .documents
+            .iter_mut()
+            .find(|entry| entry.index == doc.index && entry.id == doc.id)
+    {
+        *slot = doc;
+    }
+    app.doc_change = Some((loaded.id, change));
+    Ok(())
+}
+
+pub fn filter_ignores_case(filter: &str) -> bool {
+    !filter.chars().any(char::is_uppercase)
+}
+
+pub fn doc_matches_filter(doc: &DocEntry, filter: &str) -> bool {
+    if filter.is_empty() {
+        return true;
+    }
+    let ignore_case = filter_ignores_case(filter);
+    let needle = if ignore_case {
+        filter.to_ascii_lowercase()
+    } else {
+        filter.to_string()
+    };
+    let matches = |text: &str| {
+        if ignore_case {
+            text.to_ascii_lowercase().contains(&needle)
+        } else {
+            text.contains(&needle)
+        }
+    };
+    matches(&doc.id) || value_matches(&doc.source, &matches)
+}
+
+fn value_matches(value: &Value, matches: &impl Fn(&str) -> bool) -> bool {
+    match value {
+        Value::Object(map) => map.values().any(|value| value_matches(value, matches)),
+        Value::Array(values) => values.iter().any(|value| value_matches(value, matches)),
+        Value::String(text) => matches(text),
+        Value::Null => false,
+        other => matches(&other.to_string()),
+    }
+}
+
+pub fn handle_docs_refresh(app: &mut App) {
+    let result = refresh_docs(app);
+    handle_docs_result(app, result);
+}
+
+pub fn handle_docs_result(app: &mut App, result: Result<()>) {
+    match result {
+        Err(err) if is_unreachable(&err) => app.mark_disconnected(&err),
+        Err(err) => app.last_error = Some(format!("docs: {err:#}")),
+        Ok(()) => {
+            if app.mark_connected() {
+                refresh_all(app);
+            } else if let Err(err) = refresh_enrichment(app) {
+                app.last_error = Some(format!("enrich: {err:#}"));
+            }
+        }
+    }
+}
+
+pub fn handle_config_reload(app: &mut App) -> Option<ClusterConfig> {
+    let page_size = app.docs_size;
+    let cluster = app.poll_config_reload();
+    if app.docs_size != page_size {
+        app.reset_docs_paging();
+        handle_docs_refresh(app);
+    }
+    cluster
+}
+
+fn start_pit(app: &mut App, keep_alive: &str) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let millis = parse_duration_millis(keep_alive)
+        .filter(|millis| *millis > 0)
+        .with_context(|| format!("invalid keep-alive {keep_alive:?}, expected e.g. 5m"))?;
+    close_pit_session(app)?;
+    let params = app.search_params();
+    let id = open_pit(&app.client, &app.es_url, &scope, keep_alive, &params)?;
+    let keep_alive_for = Duration::from_millis(millis as u64);
+    app.pit = Some(PitSession {
+        scope,
+        id,
+        keep_alive: keep_alive.to_string(),
+        keep_alive_for,
+        expires: Instant::now() + keep_alive_for,
+    });
+    Ok(())
+}
+
+pub fn open_pit_session(app: &mut App, keep_alive: &str) -> Result<()> {
+    start_pit(app, keep_alive)?;
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    Ok(())
+}
+
+pub fn close_pit_session(app: &mut App) -> Result<()> {
+    let Some(pit) = app.pit.take() else {
+        return Ok(());
+    };
+    if pit.remaining().is_zero() {
+        return Ok(());
+    }
+    close_pit(&app.client, &app.es_url, &pit.id)
+}
+
+pub fn cluster_profiles(app: &App) -> Vec<(Option<String>, ClusterConfig)> {
+    let mut profiles = vec![(None, app.default_cluster.clone())];
+    for (name, cluster) in &app.config.clusters {
+        profiles.push((Some(name.clone()), cluster.clone()));
+    }
+    profiles
+}
+
+pub fn switch_cluster(app: &mut App, name: Option<String>) -> Result<()> {
+    let cluster = match &name {
+        Some(name) => app
+            .config
+            .clusters
+            .get(name)
+            .cloned()
+            .with_context(|| format!("no cluster profile named {name:?}"))?,
+        None => app.default_cluster.clone(),
+    };
+    app.cluster_name = name;
+    app.scope_pattern = None;
+    app.nav_back.clear();
+    app.nav_forward.clear();
+    app.last_scope = None;
+    app.remote_notes = None;
+    app.doc_change = None;
+    app.preference = app.config.search.preference.clone();
+    app.routing = app.config.search.routing.clone();
+    app.last_error = None;
+    reconnect(app, cluster)
+}
+
+pub fn reconnect(app: &mut App, cluster: ClusterConfig) -> Result<()> {
+    let _ = close_pit_session(app);
+    app.client = build_client(&cluster)?;
+    app.es_url = match &app.cluster_name {
+        Some(_) => cluster.url.clone(),
+        None => std::env::var("ES_URL").unwrap_or_else(|_| cluster.url.clone()),
+    };
+    app.config.cluster = cluster;
+    app.health = None;
+    app.license = None;
+    app.master_changes.clear();
+    app.known_indices = None;
+    app.enrichment.clear();
+    app.mapping = None;
+    app.reset_docs_paging();
+    refresh_all(app);
+    Ok(())
+}
+
+pub const NAV_HISTORY_LIMIT: usize = 50;
+
+pub fn navigate_back(app: &mut App) {
+    if let Some(entry) = app.nav_back.pop() {
+        let current = app.nav_entry();
+        app.nav_forward.push(current);
+        restore_nav(app, &entry);
+    }
+}
+
+pub fn navigate_forward(app: &mut App) {
+    if let Some(entry) = app.nav_forward.pop() {
+        let current = app.nav_entry();
+        app.nav_back.push(current);
+        restore_nav(app, &entry);
+    }
+}
+
+pub fn toggle_last_scope(app: &mut App) {
+    if let Some(entry) = app.last_scope.take() {
+        app.record_nav();
+        app.last_scope = Some(app.nav_entry());
+        restore_nav(app, &entry);
+    }
+}
+
+fn restore_nav(app: &mut App, entry: &NavEntry) {
+    app.set_scope_kind(entry.scope_kind);
+    if let Some(scope) = &entry.scope
+        && !app.select_scope_by_name(scope)
+    {
+        app.scope_pattern = Some(scope.clone());
+    }
+    app.query = entry.query.clone();
+    app.show_doc_drawer = false;
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    if let Some(doc_id) = &entry.doc_id
+        && let Some(pos) = app.visible_docs().iter().position(|doc| &doc.id == doc_id)
+    {
+        app.docs_state.select(Some(pos));
+        app.focus = Focus::Results;
+        app.show_doc_drawer = true;
+    }
+}
+
+pub fn open_scope_pattern(app: &mut App, pattern: String) {
+    app.record_nav();
+    app.scope_pattern = Some(pattern);
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+}
+
+pub fn is_scope_pattern(scope: &str) -> bool {
+    scope.contains(['*', ',', ':']) || scope == "_all"
+}
+
+pub fn open_launch_target(app: &mut App, target: LaunchTarget) {
+    let scope_kind = target
+        .scope
+        .as_deref()
+        .and_then(|scope| scope_kind_of(app, scope))
+        .unwrap_or(app.scope_kind);
+    let entry = NavEntry {
+        scope_kind,
+        scope: target.scope,
+        query: target.query.unwrap_or_default(),
+        doc_id: target.doc_id,
+    };
+    restore_nav(app, &entry);
+    if let Some(doc_id) = &entry.doc_id
+        && !app.show_doc_drawer
+        && entry.query.is_empty()
+    {
+        let lookup = NavEntry {
+            query: format!("_id:\"{doc_id}\""),
+            ..entry.clone()
+        };
+        restore_nav(app, &lookup);
+    }
+    if entry.doc_id.is_some() && !app.show_doc_drawer {
+        app.last_error = Some(format!(
+            "open: document {} not found",
+            entry.doc_id.as_deref().unwrap_or_default()
+        ));
+    }
+}
+
+pub fn scope_kind_of(app: &App, scope: &str) -> Option<ScopeKind> {
+    if app.indices.iter().any(|entry| entry.name == scope) {
+        Some(ScopeKind::Indices)
+    } else if app.aliases.iter().any(|entry| entry.alias == scope) {
+        Some(ScopeKind::Aliases)
+    } else if app.datastreams.iter().any(|entry| entry.name == scope) {
+        Some(ScopeKind::DataStreams)
+    } else {
+        None
+    }
+}
+
+pub fn handle_scope_change(app: &mut App) {
+    handle_docs_refresh(app);
+    if app.right_tab == RightTab::Mappings
+        && let Err(err) = refresh_mapping(app)
+    {
+        app.last_error = Some(format!("mapping: {err:#}"));
+    }
+}
+
+pub fn refresh_mapping(app: &mut App) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    if app.mapping.as_ref().is_some_and(|view| view.scope == scope) {
+        return Ok(());
+    }
+    let fields = fetch_mapping(&app.client, &app.es_url, &scope)?;
+    let mut state = ListState::default();
+    state.select((!fields.is_empty()).then_some(0));
+    app.mapping = Some(MappingView {
+        scope,
+        fields,
+        state,
+    });
+    Ok(())
+}
+
+pub fn toggle_query_mode(app: &mut App) {
+    match app.query_mode {
+        QueryMode::QueryString => {
+            let base = SearchParams {
+                filters: Vec::new(),
+                sample_seed: None,
+                dsl: None,
+                ..app.search_params()
+            };
+            let query = query_body(&app.query, &base);
+            app.query_mode = QueryMode::Dsl;
+            app.saved_query_string = std::mem::take(&mut app.query);
+            if !app.saved_query_string.trim().is_empty() {
+                app.query = serde_json::json!({ "query": query }).to_string();
+            }
+        }
+        QueryMode::Dsl => {
+            app.query_mode = QueryMode::QueryString;
+            app.query = std::mem::take(&mut app.saved_query_string);
+        }
+    }
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+}
+
+pub fn toggle_mappings_tab(app: &mut App) {
+    app.right_tab = match app.right_tab {
+        RightTab::Results => RightTab::Mappings,
+        RightTab::Mappings => RightTab::Results,
+    };
+    if app.right_tab == RightTab::Mappings {
+        app.show_doc_drawer = false;
+        app.focus = Focus::Results;
+        app.mapping = None;
+        if let Err(err) = refresh_mapping(app) {
+            app.last_error = Some(format!("mapping: {err:#}"));
+        }
+    }
+}
+
+pub fn shift_mapping_selection(app: &mut App, delta: isize) {
+    let Some(view) = app.mapping.as_mut() else {
+        return;
+    };
+    if view.fields.is_empty() {
+        return;
+    }
+    let last = view.fields.len() - 1;
+    let next = match view.state.selected() {
+        Some(idx) => idx.saturating_add_signed(delta).min(last),
+        None => 0,
+    };
+    view.state.select(Some(next));
+}
+
+pub fn filter_indices_by<T, F>(items: &[T], matcher: &ScopeMatcher, extract: F) -> Vec<usize>
+where
+    F: Fn(&T) -> &str,
+{
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| matcher.matches(extract(entry)).then_some(idx))
+        .collect()
+}
+
+pub fn index_search_text(name: &str, tags: Option<&IndexTags>) -> String {
+    let mut text = name.to_string();
+    for (key, value) in tags.into_iter().flatten() {
+        text.push_str(&format!(" {key}:{value}"));
+    }
+    text
+}
+
+pub fn regex_error_summary(err: &regex::Error) -> String {
+    err.to_string()
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("invalid pattern")
+        .trim()
+        .trim_start_matches("error: ")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_config_keeps_connection_until_confirmed() {
+        let mut app = test_app();
+        let mut config = app.config.clone();
+        config.display.page_size = Some(20);
+        assert_eq!(app.apply_config(config.clone()), None);
+        assert_eq!(app.docs_size, 20);
+        config.cluster.url = "http://other:9200".to_string();
+        let pending = app.apply_config(config.clone()).expect("connection change");
+        assert_eq!(pending.url, "http://other:9200");
+        assert_ne!(app.config.cluster.url, "http://other:9200");
+        config.display.page_size = Some(0);
+        config
+            .clusters
+            .insert("prod".to_string(), app.config.cluster.clone());
+        app.cluster_name = Some("prod".to_string());
+        assert_eq!(app.apply_config(config), None);
+        assert_eq!(app.docs_size, 1);
+    }
+
+    #[test]
+    fn favorites_are_scoped_to_the_connected_cluster() {
+        let mut app = test_app();
+        app.state.favorites = vec![
+            app.note_key("logs-*"),
+            "other-cluster/metrics".to_string(),
+            app.note_key("orders"),
+        ];
+        assert_eq!(app.favorites(), ["logs-*", "orders"]);
+        app.health = Some(ClusterHealth {
+            cluster_name: "other-cluster".to_string(),
+            status: "green".to_string(),
+            number_of_nodes: 1,
+        });
+        assert_eq!(app.favorites(), ["metrics"]);
+    }
+
+    #[test]
+    fn scope_filter_matches_index_tags() {
+        let mut app = test_app();
+        app.indices = ["logs-payments", "logs-search"]
+            .iter()
+            .map(|name| test_index(name))
+            .collect();
+        app.index_tags.insert(
+            "logs-search".to_string(),
+            vec![("team".to_string(), "Search".to_string())],
+        );
+        app.scope_filter = "team:search".to_string();
+        assert_eq!(app.filtered_scope_indices(), vec![1]);
+        app.scope_filter = "logs -team:search".to_string();
+        assert_eq!(app.filtered_scope_indices(), vec![0]);
+    }
+
+    #[test]
+    fn local_notes_take_precedence_over_remote() {
+        let mut app = test_app();
+        app.remote_notes = Some(BTreeMap::from([
+            ("logs".to_string(), "shared".to_string()),
+            ("metrics".to_string(), "owned by infra".to_string()),
+        ]));
+        let key = app.note_key("logs");
+        app.state
+            .notes
+            .insert(key, "payments, do not delete".to_string());
+        assert_eq!(app.note_for("logs"), Some("payments, do not delete"));
+        assert_eq!(app.note_for("metrics"), Some("owned by infra"));
+        assert_eq!(app.note_for("traces"), None);
+    }
+
+    #[test]
+    fn launch_target_parses_open_scope_and_query() {
+        let target = LaunchTarget::parse(&[
+            "--open",
+            "remote:logs-prod-*:AbC123",
+            "--query",
+            "level:error",
+        ])
+        .unwrap();
+        assert_eq!(target.scope.as_deref(), Some("remote:logs-prod-*"));
+        assert_eq!(target.doc_id.as_deref(), Some("AbC123"));
+        assert_eq!(target.query.as_deref(), Some("level:error"));
+        let target = LaunchTarget::parse(&["--scope", "metrics"]).unwrap();
+        assert_eq!(target.scope.as_deref(), Some("metrics"));
+        assert_eq!(target.doc_id, None);
+        assert!(LaunchTarget::parse(&["--open", "logs"]).is_err());
+        assert!(LaunchTarget::parse(&["--query"]).is_err());
+        assert!(LaunchTarget::parse(&["--bogus", "x"]).is_err());
+    }
+
+    #[test]
+    fn pit_applies_only_to_its_scope() {
+        let mut app = test_app();
+        app.preference = Some("_local".to_string());
+        app.scope_pattern = Some("logs-*".to_string());
+        app.pit = Some(PitSession {
+            scope: "logs-*".to_string(),
+            id: "pit-1".to_string(),
+            keep_alive: "5m".to_string(),
+            keep_alive_for: Duration::from_secs(300),
+            expires: Instant::now() + Duration::from_secs(300),
+        });
+        let params = app.search_params();
+        assert_eq!(params.pit.map(|pit| pit.id).as_deref(), Some("pit-1"));
+        assert_eq!(params.preference, None);
+        app.scope_pattern = Some("metrics-*".to_string());
+        let params = app.search_params();
+        assert_eq!(params.pit, None);
+        assert_eq!(params.preference.as_deref(), Some("_local"));
+    }
+
+    #[test]
+    fn nav_history_skips_duplicates_and_clears_forward() {
+        let mut app = test_app();
+        app.query = "status:500".to_string();
+        app.record_nav();
+        app.record_nav();
+        assert_eq!(app.nav_back.len(), 1);
+        assert_eq!(app.nav_back[0].query, "status:500");
+        app.nav_forward.push(app.nav_entry());
+        app.query = "status:404".to_string();
+        app.record_nav();
+        assert_eq!(app.nav_back.len(), 2);
+        assert!(app.nav_forward.is_empty());
+    }
+
+    #[test]
+    fn refetch_rejects_a_malformed_index_before_sending() {
+        let mut app = test_app();
+        app.documents = vec![test_doc("logs/_doc", "1", serde_json::json!({}))];
+        app.docs_state.select(Some(0));
+        let err = refetch_selected_doc(&mut app).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "scope \"logs/_doc\" contains invalid character '/'"
+        );
+        assert_eq!(app.doc_change, None);
+        app.docs_state.select(None);
+        assert!(refetch_selected_doc(&mut app).is_err());
+    }
+
+    #[test]
+    fn scope_tab_counts_apply_the_filter_to_every_kind() {
+        let mut app = test_app();
+        app.indices = ["logs-1", "logs-2", "metrics-1"]
+            .into_iter()
+            .map(test_index)
+            .collect();
+        app.aliases = ["logs", "web"]
+            .into_iter()
+            .map(|alias| AliasEntry {
+                alias: alias.to_string(),
+                targets: Vec::new(),
+            })
+            .collect();
+        let counts = |app: &App| {
+            [
+                ScopeKind::Indices,
+                ScopeKind::Aliases,
+                ScopeKind::DataStreams,
+            ]
+            .map(|kind| app.filtered_scope_indices_for(kind).len())
+        };
+        assert_eq!(counts(&app), [3, 2, 0]);
+        app.scope_filter = "logs -2".to_string();
+        app.scope_kind = ScopeKind::DataStreams;
+        assert_eq!(counts(&app), [1, 1, 0]);
+        app.scope_filter = "re:(".to_string();
+        assert_eq!(counts(&app), [3, 2, 0]);
+    }
+
+    #[test]
+    fn nav_history_is_capped_and_empty_stacks_are_no_ops() {
+        let mut app = test_app();
+        app.query = "status:500".to_string();
+        navigate_back(&mut app);
+        navigate_forward(&mut app);
+        assert_eq!(app.query, "status:500");
+        assert!(app.nav_back.is_empty() && app.nav_forward.is_empty());
+        for step in 0..NAV_HISTORY_LIMIT + 5 {
+            app.query = format!("q{step}");
+            app.record_nav();
+        }
+        assert_eq!(app.nav_back.len(), NAV_HISTORY_LIMIT);
+        assert_eq!(app.nav_back[0].query, "q5");
+    }
+
+    #[test]
+    fn paging_switches_to_search_after_before_the_result_window() {
+        let mut app = test_app_with_index("logs");
+        app.docs_size = 100;
+        app.documents = (0..100u64)
+            .map(|n| DocEntry {
+                sort: Some(serde_json::json!([n])),
+                ..test_doc("logs", &n.to_string(), Value::Null)
+            })
+            .collect();
+        assert_eq!(app.next_docs_page(), Some(DocsPage::From(100)));
+        app.docs_from = 9_800;
+        assert_eq!(app.next_docs_page(), None);
+        app.pit = Some(PitSession {
+            scope: "logs".to_string(),
+            id: "pit-1".to_string(),
+            keep_alive: AUTO_PIT_KEEP_ALIVE.to_string(),
+            keep_alive_for: Duration::from_secs(300),
+            expires: Instant::now() + Duration::from_secs(300),
+        });
+        let page = app.next_docs_page().unwrap();
+        assert_eq!(page, DocsPage::After(serde_json::json!([99])));
+        let body = documents_body("", &app.search_params(), &page);
+        assert_eq!(body["search_after"], serde_json::json!([99]));
+        assert_eq!(body["pit"]["id"], "pit-1");
+    }
+
+    #[test]
+    fn enriched_value_reads_cached_lookup() {
+        let mut app = test_app();
+        let lookup = EnrichConfig {
+            field: "user.id".to_string(),
+            index: "users".to_string(),
+            ..EnrichConfig::default()
+        };
+        let doc = |id: &str| test_doc("logs", "1", serde_json::json!({ "user": { "id": id } }));
+        app.enrichment.insert(
+            "user.id".to_string(),
+            HashMap::from([
+                ("u1".to_string(), Some("Ann".to_string())),
+                ("u2".to_string(), None),
+            ]),
+        );
+        assert_eq!(
+            app.enriched_value(&lookup, &doc("u1")).as_deref(),
+            Some("Ann")
+        );
+        assert_eq!(
+            app.enriched_value(&lookup, &doc("u2")).as_deref(),
+            Some("u2?")
+        );
+        assert_eq!(app.enriched_value(&lookup, &doc("u3")), None);
+    }
+
+    #[test]
+    fn doc_filter_matches_any_value_with_smart_case() {
+        let doc = test_doc(
+            "logs-2024.01",
+            "abc",
+            serde_json::json!({
+                "message": "Connection Reset",
+                "http": { "status": 502 },
+                "tags": ["edge", null]
+            }),
+        );
+        assert!(doc_matches_filter(&doc, ""));
+        assert!(doc_matches_filter(&doc, "reset"));
+        assert!(doc_matches_filter(&doc, "Reset"));
+        assert!(!doc_matches_filter(&doc, "RESET"));
+        assert!(doc_matches_filter(&doc, "502"));
+        assert!(doc_matches_filter(&doc, "edge"));
+        assert!(doc_matches_filter(&doc, "ab"));
+        assert!(!doc_matches_filter(&doc, "message"));
+    }
+
+    #[test]
+    fn scope_matcher_supports_regex_prefix() {
+        let matcher = ScopeMatcher::parse("re:^logs-\\d{4}$");
+        assert!(matcher.matches("logs-2024"));
+        assert!(!matcher.matches("logs-prod"));
+        assert!(ScopeMatcher::parse("PROD").matches("logs-prod"));
+        assert!(matches!(
+            ScopeMatcher::parse("re:logs-("),
+            ScopeMatcher::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn scope_matcher_combines_terms_and_negation() {
+        let matcher = ScopeMatcher::parse("logs -2023 !restored");
+        assert!(matcher.matches("logs-2024.01"));
+        assert!(!matcher.matches("logs-2023.12"));
+        assert!(!matcher.matches("restored-logs-2024"));
+        assert!(!matcher.matches("metrics-2024"));
+        assert!(ScopeMatcher::parse("- !").matches("anything"));
+    }
+
+    #[test]
+    fn refresh_backs_off_while_disconnected_and_resets_on_reconnect() {
+        let mut app = test_app();
+        assert_eq!(app.connection, ConnectionState::Unknown);
+        assert_eq!(app.refresh_interval(), Duration::from_secs(10));
+        assert!(!app.mark_connected());
+        let err = anyhow::anyhow!("connection refused");
+        let mut schedule = Vec::new();
+        for _ in 0..8 {
+            app.mark_disconnected(&err);
+            schedule.push(app.refresh_interval().as_secs());
+        }
+        assert_eq!(schedule, [10, 20, 40, 60, 60, 60, 60, 60]);
+        assert!(matches!(
+            app.connection,
+            ConnectionState::Disconnected { failures: 8, .. }
+        ));
+        assert!(app.mark_connected());
+        assert_eq!(app.connection, ConnectionState::Connected);
+        assert_eq!(app.refresh_interval(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn poll_jobs_tracks_export_progress_until_done() {
+        let mut app = test_app();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.start_job("export logs.ndjson", None);
+        app.jobs[0].progress = Some(receiver);
+        sender
+            .send(ExportEvent::Progress {
+                written: 1000,
+                total: Some(2500),
+            })
+            .unwrap();
+        app.poll_jobs();
+        assert_eq!((app.jobs[0].written, app.jobs[0].total), (1000, Some(2500)));
+        sender.send(ExportEvent::Done { written: 2500 }).unwrap();
+        app.poll_jobs();
+        assert!(app.jobs.is_empty());
+        assert!(app.info_panel.is_some());
+
+        let mut app = test_app();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.start_job("export logs.csv", None);
+        app.jobs[0].progress = Some(receiver);
+        sender
+            .send(ExportEvent::Failed("disk full".to_string()))
+            .unwrap();
+        app.poll_jobs();
+        assert!(app.jobs.is_empty());
+        assert!(app.info_panel.is_none());
+        assert_eq!(
+            app.last_error.as_deref(),
+            Some("export logs.csv: disk full")
+        );
+        app.start_job("export logs.json", None);
+        app.jobs[0].progress = Some(std::sync::mpsc::channel().1);
+        app.poll_jobs();
+        assert_eq!(
+            app.last_error.as_deref(),
+            Some("export logs.json: export stopped")
+        );
+    }
+
+    #[test]
+    fn poll_jobs_shows_the_benchmark_report_when_done() {
+        let mut app = test_app();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.start_job("bench logs", None);
+        app.jobs[0].bench = Some(receiver);
+        assert!(app.bench_running());
+        sender
+            .send(BenchEvent::Progress { done: 3, total: 10 })
+            .unwrap();
+        app.poll_jobs();
+        assert_eq!((app.jobs[0].written, app.jobs[0].total), (3, Some(10)));
+        sender
+            .send(BenchEvent::Done(vec![Line::from("took")]))
+            .unwrap();
+        app.poll_jobs();
+        assert!(!app.bench_running());
+        assert_eq!(app.info_panel.as_ref().unwrap().title, "Benchmark");
+    }
+
+    #[test]
+    fn health_and_index_changes_become_timeline_events() {
+        let health = |status: &str, nodes| ClusterHealth {
+            cluster_name: "prod".to_string(),
+            status: status.to_string(),
+            number_of_nodes: nodes,
+        };
+        assert_eq!(
+            health_events(&health("green", 3), &health("yellow", 2)),
+            [
+                (EventLevel::Warn, "health green -> yellow".to_string()),
+                (EventLevel::Warn, "nodes 3 -> 2".to_string()),
+            ]
+        );
+        assert!(health_events(&health("red", 3), &health("red", 3)).is_empty());
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        assert_eq!(
+            index_events(&names(&["logs-1", "logs-2"]), &names(&["logs-2", "logs-3"])),
+            [
+                (EventLevel::Info, "index created: logs-3".to_string()),
+                (EventLevel::Warn, "index deleted: logs-1".to_string()),
+            ]
+        );
+        let mut app = test_app();
+        app.mark_connected();
+        app.mark_disconnected(&anyhow::anyhow!("refused"));
+        app.mark_disconnected(&anyhow::anyhow!("refused"));
+        app.mark_connected();
+        let messages: Vec<&str> = app
+            .events
+            .iter()
+            .map(|event| event.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            ["connection lost: refused", "connection restored"]
+        );
+    }
+
+    #[test]
+    fn background_refresh_is_skipped_once_the_request_budget_is_spent() {
+        let mut app = test_app();
+        app.config.refresh.max_requests_per_minute = Some(u32::MAX);
+        assert!(!app.over_request_budget());
+        app.config.refresh.max_requests_per_minute = Some(0);
+        refresh_background(&mut app);
+        assert!(app.throttled);
+        assert!(app.last_fetch.is_some());
+        assert!(!app.refresh_due());
+        assert!(app.last_error.is_none());
+    }
+}
@@ -0,0 +1,2965 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::*;
+use crate::format::*;
+use crate::metrics::*;
+use crate::state::*;
+use crate::terminal::*;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClusterHealth {
+    pub cluster_name: String,
+    pub status: String,
+    #[serde(default)]
+    pub number_of_nodes: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HealthDetail {
+    pub cluster_name: String,
+    pub status: String,
+    pub number_of_nodes: u64,
+    pub active_shards: u64,
+    pub relocating_shards: u64,
+    pub initializing_shards: u64,
+    pub unassigned_shards: u64,
+    pub number_of_pending_tasks: u64,
+    pub active_shards_percent_as_number: f64,
+    pub indices: BTreeMap<String, IndexHealth>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct IndexHealth {
+    pub status: String,
+    pub number_of_shards: u64,
+    pub number_of_replicas: u64,
+    pub active_shards: u64,
+    pub relocating_shards: u64,
+    pub initializing_shards: u64,
+    pub unassigned_shards: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct IndexEntry {
+    pub health: String,
+    #[serde(rename = "index")]
+    pub name: String,
+    #[serde(rename = "docs.count")]
+    pub docs_count: Option<String>,
+    #[serde(rename = "store.size")]
+    pub store_size: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AliasRow {
+    pub alias: String,
+    #[serde(rename = "index")]
+    pub index_name: String,
+    pub is_write_index: Option<String>,
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AliasEntry {
+    pub alias: String,
+    pub targets: Vec<AliasTarget>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AliasTarget {
+    pub index_name: String,
+    pub is_write_index: bool,
+    pub filtered: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct AliasDetail {
+    pub filter: Option<Value>,
+    pub index_routing: Option<String>,
+    pub search_routing: Option<String>,
+    pub is_write_index: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DataStreamResponse {
+    #[serde(default)]
+    pub data_streams: Vec<DataStreamEntry>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DataStreamEntry {
+    pub name: String,
+    pub status: Option<String>,
+    pub generation: Option<u64>,
+    pub indices: Option<Vec<DataStreamIndex>>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ResolvedScope {
+    #[serde(default)]
+    pub indices: Vec<ResolvedIndex>,
+    #[serde(default)]
+    pub aliases: Vec<ResolvedAlias>,
+    #[serde(default)]
+    pub data_streams: Vec<ResolvedDataStream>,
+}
+
+impl ResolvedScope {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty() && self.aliases.is_empty() && self.data_streams.is_empty()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResolvedIndex {
+    pub name: String,
+    #[serde(default)]
+    pub data_stream: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResolvedAlias {
+    pub name: String,
+    #[serde(default)]
+    pub indices: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResolvedDataStream {
+    pub name: String,
+    #[serde(default)]
+    pub backing_indices: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DataStreamIndex {
+    #[serde(rename = "index_name")]
+    #[allow(dead_code)]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LifecycleResponse {
+    #[serde(default)]
+    pub data_streams: Vec<DataStreamLifecycle>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DataStreamLifecycle {
+    pub name: String,
+    pub lifecycle: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlmPolicyEntry {
+    pub policy: SlmPolicy,
+    pub last_success: Option<SlmInvocation>,
+    pub last_failure: Option<SlmInvocation>,
+    pub next_execution_millis: Option<i64>,
+    pub in_progress: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlmPolicy {
+    pub name: Option<String>,
+    pub schedule: Option<String>,
+    pub repository: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlmInvocation {
+    pub snapshot_name: Option<String>,
+    pub time: Option<i64>,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllocationRow {
+    pub node: Option<String>,
+    pub shards: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShardRow {
+    pub node: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShardPlacement {
+    pub shard: String,
+    pub prirep: String,
+    pub state: String,
+    pub node: Option<String>,
+    pub docs: Option<String>,
+    pub store: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LicenseResponse {
+    pub license: LicenseInfo,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LicenseInfo {
+    #[serde(rename = "type")]
+    pub license_type: Option<String>,
+    pub status: Option<String>,
+    pub issued_to: Option<String>,
+    pub expiry_date_in_millis: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TsdbInfo {
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub downsample_status: Option<String>,
+    pub downsample_source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchResponse {
+    pub took: Option<u64>,
+    pub timed_out: Option<bool>,
+    #[serde(rename = "_shards")]
+    pub shards: Option<SearchShards>,
+    pub hits: SearchHits,
+    #[serde(default)]
+    pub pit_id: Option<String>,
+    #[serde(default)]
+    pub terminated_early: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchShards {
+    pub failed: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchHits {
+    pub total: Option<SearchTotal>,
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchTotal {
+    pub value: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchHit {
+    #[serde(rename = "_index", default)]
+    pub index: String,
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_source")]
+    pub source: Value,
+    #[serde(rename = "_version", default)]
+    pub version: Option<u64>,
+    #[serde(rename = "_seq_no", default)]
+    pub seq_no: Option<u64>,
+    #[serde(default)]
+    pub sort: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateResponse {
+    pub valid: bool,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub explanations: Vec<ValidateExplanation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateExplanation {
+    pub index: Option<String>,
+    pub explanation: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermsEnumResponse {
+    #[serde(default)]
+    pub terms: Vec<String>,
+    #[serde(default)]
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocEntry {
+    pub index: String,
+    pub id: String,
+    pub source: Value,
+    pub version: Option<u64>,
+    pub seq_no: Option<u64>,
+    pub sort: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocsPage {
+    From(u64),
+    After(Value),
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchSummary {
+    pub total: Option<u64>,
+    pub took: Option<u64>,
+    pub shards_failed: Option<u64>,
+    pub timed_out: Option<bool>,
+    pub pit_id: Option<String>,
+    pub terminated_early: Option<bool>,
+}
+
+pub struct DeprecationWarning {
+    pub endpoint: String,
+    pub message: String,
+    pub count: u64,
+}
+
+pub static DEPRECATION_WARNINGS: Mutex<Vec<DeprecationWarning>> = Mutex::new(Vec::new());
+
+pub const REQUEST_BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+static RECENT_REQUESTS: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+fn prune_recent_requests(recent: &mut VecDeque<Instant>) {
+    while recent
+        .front()
+        .is_some_and(|at| at.elapsed() >= REQUEST_BUDGET_WINDOW)
+    {
+        recent.pop_front();
+    }
+}
+
+pub fn recent_request_count() -> usize {
+    let Ok(mut recent) = RECENT_REQUESTS.lock() else {
+        return 0;
+    };
+    prune_recent_requests(&mut recent);
+    recent.len()
+}
+
+pub fn record_deprecation_warnings(endpoint: &str, headers: &reqwest::header::HeaderMap) {
+    let messages: Vec<String> = headers
+        .get_all(reqwest::header::WARNING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(parse_warning_header)
+        .collect();
+    if messages.is_empty() {
+        return;
+    }
+    let Ok(mut warnings) = DEPRECATION_WARNINGS.lock() else {
+        return;
+    };
+    for message in messages {
+        match warnings
+            .iter_mut()
+            .find(|warning| warning.endpoint == endpoint && warning.message == message)
+        {
+            Some(warning) => warning.count += 1,
+            None => warnings.push(DeprecationWarning {
+                endpoint: endpoint.to_string(),
+                message,
+                count: 1,
+            }),
+        }
+    }
+}
+
+pub fn parse_warning_header(value: &str) -> Option<String> {
+    let start = value.find('"')? + 1;
+    let mut message = String::new();
+    let mut chars = value[start..].chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => message.push(chars.next()?),
+            '"' => return Some(message),
+            _ => message.push(ch),
+        }
+    }
+    None
+}
+
+pub fn deprecation_warning_count() -> usize {
+    DEPRECATION_WARNINGS
+        .lock()
+        .map(|warnings| warnings.len())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationIssue {
+    pub level: String,
+    pub section: String,
+    pub target: Option<String>,
+    pub message: String,
+    pub details: Option<String>,
+}
+
+impl MigrationIssue {
+    pub fn label(&self) -> String {
+        match &self.target {
+            Some(target) => format!("{} {target}", self.section),
+            None => self.section.clone(),
+        }
+    }
+}
+
+pub fn migration_issues(payload: &Value) -> Vec<MigrationIssue> {
+    let mut issues = Vec::new();
+    let mut push = |section: &str, target: Option<&str>, issue: &Value| {
+        let text = |key: &str| issue.get(key).and_then(Value::as_str).map(str::to_string);
+        issues.push(MigrationIssue {
+            level: text("level").unwrap_or_else(|| "warning".to_string()),
+            section: section.to_string(),
+            target: target.map(str::to_string),
+            message: text("message").unwrap_or_else(|| "-".to_string()),
+            details: text("details"),
+        });
+    };
+    for (section, value) in payload.as_object().into_iter().flatten() {
+        match value {
+            Value::Array(items) => items.iter().for_each(|issue| push(section, None, issue)),
+            Value::Object(targets) => {
+                for (target, items) in targets {
+                    for issue in items.as_array().into_iter().flatten() {
+                        push(section, Some(target), issue);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    issues
+}
+
+pub fn is_unreachable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(reqwest::Error::is_connect)
+    })
+}
+
+pub fn send_request(
+    request: reqwest::blocking::RequestBuilder,
+) -> Result<reqwest::blocking::Response> {
+    let (client, request) = request.build_split();
+    let request = request.context("invalid request")?;
+    let label = format!("{} {}", request.method(), redact_url(request.url()));
+    let endpoint = format!("{} {}", request.method(), request.url().path());
+    let request_method = request.method().clone();
+    let request_path = request.url().path().to_string();
+    let started = Instant::now();
+    if let Ok(mut recent) = RECENT_REQUESTS.lock() {
+        prune_recent_requests(&mut recent);
+        recent.push_back(started);
+    }
+    let result = client.execute(request);
+    let duration = started.elapsed();
+    let elapsed = duration.as_millis();
+    let method = request_method.as_str();
+    match &result {
+        Ok(response) => {
+            let status = response.status();
+            record_request(format!("{label} -> {status} ({elapsed}ms)"));
+            record_deprecation_warnings(&endpoint, response.headers());
+            let error = if status.is_server_error() {
+                Some("http_5xx")
+            } else if status.is_client_error() {
+                Some("http_4xx")
+            } else {
+                None
+            };
+            record_request_metrics(method, &request_path, error, duration);
+        }
+        Err(err) => {
+            let (kind, metric) = if err.is_timeout() {
+                ("timeout", "timeout")
+            } else if err.is_connect() {
+                ("connect error", "connect")
+            } else {
+                ("error", "error")
+            };
+            record_request(format!("{label} -> {kind} ({elapsed}ms)"));
+            record_request_metrics(method, &request_path, Some(metric), duration);
+        }
+    }
+    let response = result
+        .context("request failed")?
+        .error_for_status()
+        .context("http error")?;
+    Ok(response)
+}
+
+pub fn redact_url(url: &reqwest::Url) -> String {
+    let mut url = url.clone();
+    url.set_username("").ok();
+    url.set_password(None).ok();
+    url.to_string()
+}
+
+pub fn endpoint_url(es_url: &str, segments: &[&str]) -> Result<reqwest::Url> {
+    let mut url = reqwest::Url::parse(es_url.trim()).context("invalid cluster url")?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("cluster url cannot be used as a base"))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+pub fn validate_scope(scope: &str) -> Result<String> {
+    let mut parts = Vec::new();
+    for part in scope.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            anyhow::bail!("empty scope name in {scope:?}");
+        }
+        if part.chars().any(|ch| ch.is_control() || ch.is_whitespace()) {
+            anyhow::bail!("scope {part:?} contains whitespace or control characters");
+        }
+        if let Some(ch) = part
+            .chars()
+            .find(|ch| matches!(ch, '\\' | '/' | '?' | '"' | '<' | '>' | '|' | '#'))
+        {
+            anyhow::bail!("scope {part:?} contains invalid character {ch:?}");
+        }
+        if part == "." || part == ".." {
+            anyhow::bail!("scope {part:?} is not a valid name");
+        }
+        if part.starts_with('_') && part != "_all" {
+            anyhow::bail!("scope {part:?} must not start with '_'");
+        }
+        parts.push(part);
+    }
+    Ok(parts.join(","))
+}
+
+pub fn validate_index_name(index: &str) -> Result<String> {
+    let index = validate_scope(index)?;
+    if index.contains([',', '*']) || index == "_all" {
+        anyhow::bail!("{index:?} must name a single index, not a pattern or list");
+    }
+    Ok(index)
+}
+
+pub fn fetch_cluster_health(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<ClusterHealth> {
+    let url = endpoint_url(es_url, &["_cluster", "health"])?;
+    let response = send_request(client.get(url))?;
+    let health: ClusterHealth = response.json().context("invalid response json")?;
+    Ok(health)
+}
+
+pub fn fetch_health_detail(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<HealthDetail> {
+    let mut url = endpoint_url(es_url, &["_cluster", "health"])?;
+    url.query_pairs_mut().append_pair("level", "indices");
+    let response = send_request(client.get(url))?;
+    let health: HealthDetail = response.json().context("invalid response json")?;
+    Ok(health)
+}
+
+pub fn fetch_allocation_explain(client: &reqwest::blocking::Client, es_url: &str) -> Result<Value> {
+    let url = endpoint_url(es_url, &["_cluster", "allocation", "explain"])?;
+    let response = send_request(client.post(url))?;
+    let explain: Value = response.json().context("invalid response json")?;
+    Ok(explain)
+}
+
+pub fn fetch_indices(client: &reqwest::blocking::Client, es_url: &str) -> Result<Vec<IndexEntry>> {
+    let mut url = endpoint_url(es_url, &["_cat", "indices"])?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("bytes", "b");
+    let response = send_request(client.get(url))?;
+    let indices: Vec<IndexEntry> = response.json().context("invalid response json")?;
+    Ok(indices)
+}
+
+pub fn fetch_aliases(client: &reqwest::blocking::Client, es_url: &str) -> Result<Vec<AliasEntry>> {
+    let mut url = endpoint_url(es_url, &["_cat", "aliases"])?;
+    url.query_pairs_mut().append_pair("format", "json");
+    let response = send_request(client.get(url))?;
+    let rows: Vec<AliasRow> = response.json().context("invalid response json")?;
+    Ok(group_alias_rows(rows))
+}
+
+pub fn group_alias_rows(rows: Vec<AliasRow>) -> Vec<AliasEntry> {
+    let mut aliases: Vec<AliasEntry> = Vec::new();
+    for row in rows {
+        let target = AliasTarget {
+            index_name: row.index_name,
+            is_write_index: row.is_write_index.as_deref() == Some("true"),
+            filtered: row.filter.as_deref().is_some_and(|value| value != "-"),
+        };
+        match aliases.iter_mut().find(|entry| entry.alias == row.alias) {
+            Some(entry) => entry.targets.push(target),
+            None => aliases.push(AliasEntry {
+                alias: row.alias,
+                targets: vec![target],
+            }),
+        }
+    }
+    aliases.sort_by(|a, b| a.alias.cmp(&b.alias));
+    for entry in &mut aliases {
+        entry
+            .targets
+            .sort_by(|a, b| a.index_name.cmp(&b.index_name));
+    }
+    aliases
+}
+
+pub fn resolve_scope(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    pattern: &str,
+) -> Result<ResolvedScope> {
+    let pattern = validate_scope(pattern)?;
+    let mut url = endpoint_url(es_url, &["_resolve", "index", &pattern])?;
+    url.query_pairs_mut()
+        .append_pair("expand_wildcards", "open");
+    let response = send_request(client.get(url))?;
+    response.json().context("invalid response json")
+}
+
+pub fn fetch_search_shard_count(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    pattern: &str,
+) -> Result<usize> {
+    let pattern = validate_scope(pattern)?;
+    let mut url = endpoint_url(es_url, &[&pattern, "_search_shards"])?;
+    url.query_pairs_mut()
+        .append_pair("expand_wildcards", "open");
+    let response = send_request(client.get(url))?;
+    let payload: SearchShardsResponse = response.json().context("invalid response json")?;
+    Ok(payload.shards.len())
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchShardsResponse {
+    #[serde(default)]
+    shards: Vec<Vec<Value>>,
+}
+
+pub fn fetch_datastreams(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<Vec<DataStreamEntry>> {
+    let url = endpoint_url(es_url, &["_data_stream"])?;
+    let response = send_request(client.get(url))?;
+    let payload: DataStreamResponse = response.json().context("invalid response json")?;
+    Ok(payload.data_streams)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchParams {
+    pub max_concurrent_shard_requests: Option<u32>,
+    pub ignore_unavailable: bool,
+    pub preference: Option<String>,
+    pub routing: Option<String>,
+    pub pit: Option<PitRef>,
+    pub time_zone: Option<String>,
+    pub sample_seed: Option<u64>,
+    pub query_options: QueryOptions,
+    pub fuzzy: bool,
+    pub template: Option<Value>,
+    pub timeout: Option<String>,
+    pub terminate_after: Option<u64>,
+    pub dsl: Option<Value>,
+    pub sort: Option<SortSpec>,
+    pub filters: Vec<FieldFilter>,
+    pub request_cache: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortSpec {
+    pub field: String,
+    pub order: SortOrder,
+}
+
+impl SortSpec {
+    pub fn body(&self) -> Value {
+        serde_json::json!([{ self.field.as_str(): { "order": self.order.as_str() } }])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldFilter {
+    pub field: String,
+    pub value: Value,
+    pub exclude: bool,
+}
+
+impl FieldFilter {
+    pub fn term(&self) -> Value {
+        serde_json::json!({ "term": { self.field.as_str(): self.value } })
+    }
+}
+
+pub fn filtered_query(query: Value, filters: &[FieldFilter]) -> Value {
+    if filters.is_empty() {
+        return query;
+    }
+    let mut must = vec![query];
+    let mut must_not = Vec::new();
+    for filter in filters {
+        if filter.exclude {
+            must_not.push(filter.term());
+        } else {
+            must.push(filter.term());
+        }
+    }
+    serde_json::json!({ "bool": { "must": must, "must_not": must_not } })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PitRef {
+    pub id: String,
+    pub keep_alive: String,
+}
+
+impl SearchParams {
+    pub fn apply(&self, url: &mut reqwest::Url) {
+        let mut pairs = url.query_pairs_mut();
+        if let Some(limit) = self.max_concurrent_shard_requests {
+            pairs.append_pair("max_concurrent_shard_requests", &limit.to_string());
+        }
+        if self.ignore_unavailable {
+            pairs.append_pair("ignore_unavailable", "true");
+        }
+        if let Some(preference) = &self.preference {
+            pairs.append_pair("preference", preference);
+        }
+        if let Some(routing) = &self.routing {
+            pairs.append_pair("routing", routing);
+        }
+        if let Some(enabled) = self.request_cache {
+            pairs.append_pair("request_cache", &enabled.to_string());
+        }
+    }
+
+    pub fn timed(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        let Some(millis) = self.timeout.as_deref().and_then(parse_duration_millis) else {
+            return request;
+        };
+        let limit = Duration::from_millis(millis.max(0) as u64) + SEARCH_TIMEOUT_GRACE;
+        request.timeout(limit.max(REQUEST_TIMEOUT))
+    }
+}
+
+const SEARCH_TIMEOUT_GRACE: Duration = Duration::from_secs(2);
+
+pub fn search_url(es_url: &str, index: &str, from: u64, size: u64) -> Result<reqwest::Url> {
+    let index = validate_scope(index)?;
+    let mut url = endpoint_url(es_url, &[&index, "_search"])?;
+    url.query_pairs_mut()
+        .append_pair("from", &from.to_string())
+        .append_pair("size", &size.to_string());
+    Ok(url)
+}
+
+pub fn search_body(query: &str) -> Value {
+    let query = query.trim();
+    if query.is_empty() {
+        serde_json::json!({ "query": { "match_all": {} } })
+    } else {
+        serde_json::json!({
+            "query": {
+                "query_string": {
+                    "query": query,
+                    "default_operator": "AND"
+                }
+            }
+        })
+    }
+}
+
+const REQUEST_BODY_KEYS: [&str; 18] = [
+    "size",
+    "from",
+    "sort",
+    "aggs",
+    "aggregations",
+    "_source",
+    "fields",
+    "docvalue_fields",
+    "stored_fields",
+    "highlight",
+    "track_total_hits",
+    "search_after",
+    "post_filter",
+    "collapse",
+    "min_score",
+    "timeout",
+    "terminate_after",
+    "explain",
+];
+
+pub fn parse_dsl_query(text: &str) -> Result<Value> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(serde_json::json!({ "match_all": {} }));
+    }
+    let value: Value = serde_json::from_str(text).context("invalid JSON")?;
+    let Value::Object(mut body) = value else {
+        anyhow::bail!("DSL must be a JSON object");
+    };
+    let has_query = body.contains_key("query");
+    let unsupported: Vec<&str> = body
+        .keys()
+        .map(String::as_str)
+        .filter(|key| {
+            if has_query {
+                *key != "query"
+            } else {
+                REQUEST_BODY_KEYS.contains(key)
+            }
+        })
+        .collect();
+    if !unsupported.is_empty() {
+        anyhow::bail!(
+            "unsupported request keys: {}; only \"query\" is used",
+            unsupported.join(", ")
+        );
+    }
+    match body.remove("query") {
+        Some(query @ Value::Object(_)) => Ok(query),
+        Some(_) => anyhow::bail!("\"query\" must be an object"),
+        None if body.is_empty() => anyhow::bail!("DSL must not be empty"),
+        None => Ok(Value::Object(body)),
+    }
+}
+
+pub fn query_body(query: &str, params: &SearchParams) -> Value {
+    let mut body = if let Some(dsl) = &params.dsl {
+        serde_json::json!({ "query": dsl })
+    } else if params.fuzzy && !query.trim().is_empty() {
+        fuzzy_body(query, &params.query_options)
+    } else {
+        search_body(query)
+    };
+    if let Some(query_string) = body["query"].get_mut("query_string") {
+        if let Some(time_zone) = &params.time_zone {
+            query_string["time_zone"] = Value::String(time_zone.clone());
+        }
+        params.query_options.apply(query_string);
+    }
+    body["query"] = filtered_query(body["query"].take(), &params.filters);
+    if let Some(seed) = params.sample_seed {
+        body["query"] = sample_query(body["query"].take(), seed);
+    }
+    body["query"].take()
+}
+
+fn fuzzy_body(query: &str, options: &QueryOptions) -> Value {
+    let mut multi_match = serde_json::json!({
+        "query": query.trim(),
+        "fuzziness": "AUTO",
+        "operator": options.operator.unwrap_or_default().as_str(),
+        "lenient": true,
+    });
+    if !options.fields.is_empty() {
+        multi_match["fields"] = serde_json::json!(options.fields);
+    } else if let Some(field) = &options.default_field {
+        multi_match["fields"] = serde_json::json!([field]);
+    }
+    if let Some(msm) = &options.minimum_should_match {
+        multi_match["minimum_should_match"] = serde_json::json!(msm);
+    }
+    serde_json::json!({ "query": { "multi_match": multi_match } })
+}
+
+pub fn apply_template(body: &mut Value, template: &Value) {
+    let Some(template) = template.as_object() else {
+        return;
+    };
+    let Some(body) = body.as_object_mut() else {
+        return;
+    };
+    for (key, value) in template {
+        match (key.as_str(), body.get_mut(key)) {
+            ("query", Some(query)) => {
+                *query = serde_json::json!({
+                    "bool": { "must": [query.take()], "filter": [value] }
+                });
+            }
+            (_, Some(existing)) if existing.is_object() && value.is_object() => {
+                let mut merged = value.clone();
+                merge_json(&mut merged, existing.take());
+                *existing = merged;
+            }
+            (_, Some(_)) => {}
+            (_, None) => {
+                body.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn merge_json(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+pub fn sample_query(query: Value, seed: u64) -> Value {
+    serde_json::json!({
+        "function_score": {
+            "query": query,
+            "random_score": { "seed": seed, "field": "_seq_no" },
+            "boost_mode": "replace"
+        }
+    })
+}
+
+pub fn fetch_search_took(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    from: u64,
+    size: u64,
+    query: &str,
+    params: &SearchParams,
+) -> Result<u64> {
+    let mut url = documents_url(es_url, index, from, size, params)?;
+    params.apply(&mut url);
+    let body = documents_body(query, params, &DocsPage::From(from));
+    let response = send_request(params.timed(client.post(url).json(&body)))?;
+    let payload: SearchResponse = response.json().context("invalid response json")?;
+    Ok(payload.took.unwrap_or(0))
+}
+
+pub fn fetch_validate_query(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    query: &str,
+    params: &SearchParams,
+) -> Result<ValidateResponse> {
+    let index = validate_scope(index)?;
+    let mut url = endpoint_url(es_url, &[&index, "_validate", "query"])?;
+    url.query_pairs_mut().append_pair("rewrite", "true");
+    let body = serde_json::json!({ "query": query_body(query, params) });
+    let response = send_request(client.post(url).json(&body))?;
+    let validation: ValidateResponse = response.json().context("invalid response json")?;
+    Ok(validation)
+}
+
+pub fn fetch_alias_details(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    alias: &str,
+) -> Result<Vec<(String, AliasDetail)>> {
+    let alias = validate_scope(alias)?;
+    let url = endpoint_url(es_url, &["_alias", &alias])?;
+    let response = send_request(client.get(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    let mut details = Vec::new();
+    for (index, value) in payload.as_object().into_iter().flatten() {
+        let aliases = value.get("aliases").and_then(Value::as_object);
+        for detail in aliases.into_iter().flat_map(|aliases| aliases.values()) {
+            let detail: AliasDetail =
+                serde_json::from_value(detail.clone()).context("invalid alias json")?;
+            details.push((index.clone(), detail));
+        }
+    }
+    details.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(details)
+}
+
+pub fn fetch_lifecycle(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    name: &str,
+) -> Result<Vec<DataStreamLifecycle>> {
+    let name = validate_scope(name)?;
+    let mut url = endpoint_url(es_url, &["_data_stream", &name, "_lifecycle"])?;
+    url.query_pairs_mut()
+        .append_pair("include_defaults", "true");
+    let response = send_request(client.get(url))?;
+    let payload: LifecycleResponse = response.json().context("invalid response json")?;
+    Ok(payload.data_streams)
+}
+
+pub fn put_lifecycle_retention(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    name: &str,
+    retention: &str,
+) -> Result<()> {
+    let name = validate_scope(name)?;
+    let url = endpoint_url(es_url, &["_data_stream", &name, "_lifecycle"])?;
+    let body = serde_json::json!({ "data_retention": retention });
+    send_request(client.put(url).json(&body))?;
+    Ok(())
+}
+
+pub const NOTES_INDEX: &str = ".index-lens";
+
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .and_then(reqwest::Error::status)
+            .is_some_and(|status| status == reqwest::StatusCode::NOT_FOUND)
+    })
+}
+
+pub fn fetch_remote_notes(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<BTreeMap<String, String>> {
+    let url = endpoint_url(es_url, &[NOTES_INDEX, "_search"])?;
+    let body = serde_json::json!({
+        "size": 1000,
+        "query": { "term": { "type": "note" } }
+    });
+    let response = match send_request(client.post(url).json(&body)) {
+        Err(err) if is_not_found(&err) => return Ok(BTreeMap::new()),
+        result => result?,
+    };
+    let payload: Value = response.json().context("invalid response json")?;
+    let hits = payload
+        .pointer("/hits/hits")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    Ok(hits
+        .iter()
+        .filter_map(|hit| {
+            let source = hit.get("_source")?;
+            let scope = source.get("scope")?.as_str()?;
+            let note = source.get("note")?.as_str()?;
+            Some((scope.to_string(), note.to_string()))
+        })
+        .collect())
+}
+
+pub fn put_remote_note(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    scope: &str,
+    note: Option<&str>,
+) -> Result<()> {
+    let id = format!("note:{scope}");
+    let url = endpoint_url(es_url, &[NOTES_INDEX, "_doc", &id])?;
+    match note {
+        Some(note) => {
+            let body = serde_json::json!({
+                "type": "note",
+                "scope": scope,
+                "note": note,
+                "updated_at": now_millis(),
+            });
+            send_request(client.put(url).query(&[("refresh", "true")]).json(&body))?;
+        }
+        None => match send_request(client.delete(url).query(&[("refresh", "true")])) {
+            Err(err) if is_not_found(&err) => {}
+            result => {
+                result?;
+            }
+        },
+    }
+    Ok(())
+}
+
+pub fn fetch_task_completed(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    task_id: &str,
+) -> Result<bool> {
+    let url = endpoint_url(es_url, &["_tasks", task_id])?;
+    let response = send_request(client.get(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    Ok(payload
+        .get("completed")
+        .and_then(Value::as_bool)
+        .unwrap_or(false))
+}
+
+pub fn cancel_task(client: &reqwest::blocking::Client, es_url: &str, task_id: &str) -> Result<()> {
+    let url = endpoint_url(es_url, &["_tasks", task_id, "_cancel"])?;
+    send_request(client.post(url))?;
+    Ok(())
+}
+
+pub fn fetch_document(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    id: &str,
+) -> Result<Option<DocEntry>> {
+    let index = validate_scope(index)?;
+    let url = endpoint_url(es_url, &[&index, "_doc", id])?;
+    let response = match send_request(client.get(url)) {
+        Err(err) if is_not_found(&err) => return Ok(None),
+        result => result?,
+    };
+    let hit: SearchHit = response.json().context("invalid response json")?;
+    Ok(Some(DocEntry {
+        index: hit.index,
+        id: hit.id,
+        source: hit.source,
+        version: hit.version,
+        seq_no: hit.seq_no,
+        sort: hit.sort,
+    }))
+}
+
+pub fn fetch_trace_documents(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    indices: &str,
+    trace_id: &str,
+    size: u64,
+) -> Result<Vec<DocEntry>> {
+    let mut url = search_url(es_url, indices, 0, size)?;
+    url.query_pairs_mut()
+        .append_pair("ignore_unavailable", "true")
+        .append_pair("allow_no_indices", "true");
+    let body = serde_json::json!({
+        "query": { "term": { "trace.id": trace_id } },
+        "sort": [{ "@timestamp": { "order": "asc", "unmapped_type": "date" } }],
+    });
+    let response = send_request(client.post(url).json(&body))?;
+    let payload: SearchResponse = response.json().context("invalid response json")?;
+    Ok(payload
+        .hits
+        .hits
+        .into_iter()
+        .map(|hit| DocEntry {
+            index: hit.index,
+            id: hit.id,
+            source: hit.source,
+            version: hit.version,
+            seq_no: hit.seq_no,
+            sort: hit.sort,
+        })
+        .collect())
+}
+
+pub enum MgetResult {
+    Found(DocEntry),
+    Missing,
+    Failed(String),
+}
+
+pub fn fetch_mget(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    ids: &[String],
+) -> Result<Vec<(String, MgetResult)>> {
+    let index = validate_scope(index)?;
+    let url = endpoint_url(es_url, &[&index, "_mget"])?;
+    let body = serde_json::json!({ "ids": ids });
+    let response = send_request(client.post(url).json(&body))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    let docs = payload
+        .get("docs")
+        .and_then(Value::as_array)
+        .context("mget response did not contain docs")?;
+    Ok(docs.iter().map(mget_result).collect())
+}
+
+fn mget_result(doc: &Value) -> (String, MgetResult) {
+    let text = |key: &str| doc.get(key).and_then(Value::as_str).unwrap_or_default();
+    let id = text("_id").to_string();
+    if let Some(error) = doc.get("error") {
+        let reason = error
+            .get("reason")
+            .and_then(Value::as_str)
+            .map(|reason| reason.to_string())
+            .unwrap_or_else(|| error.to_string());
+        return (id, MgetResult::Failed(reason));
+    }
+    if doc.get("found").and_then(Value::as_bool) != Some(true) {
+        return (id, MgetResult::Missing);
+    }
+    let entry = DocEntry {
+        index: text("_index").to_string(),
+        id: id.clone(),
+        source: doc.get("_source").cloned().unwrap_or(Value::Null),
+        version: doc.get("_version").and_then(Value::as_u64),
+        seq_no: doc.get("_seq_no").and_then(Value::as_u64),
+        sort: None,
+    };
+    (id, MgetResult::Found(entry))
+}
+
+pub fn fetch_lookup(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    lookup: &EnrichConfig,
+    values: &[String],
+) -> Result<HashMap<String, String>> {
+    let index = validate_scope(&lookup.index)?;
+    let (url, body) = if lookup.key == "_id" {
+        let url = endpoint_url(es_url, &[&index, "_mget"])?;
+        let body = serde_json::json!({ "ids": values, "_source": [lookup.display] });
+        (url, body)
+    } else {
+        let url = search_url(es_url, &index, 0, values.len() as u64)?;
+        let body = serde_json::json!({
+            "query": { "terms": { lookup.key.as_str(): values } },
+            "_source": [lookup.key, lookup.display],
+        });
+        (url, body)
+    };
+    let response = send_request(client.post(url).json(&body))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    let hits = payload
+        .get("docs")
+        .or_else(|| payload.pointer("/hits/hits"))
+        .and_then(Value::as_array)
+        .context("lookup response did not contain documents")?;
+    let mut found = HashMap::new();
+    for hit in hits {
+        let Some(source) = hit.get("_source") else {
+            continue;
+        };
+        let key = if lookup.key == "_id" {
+            hit.get("_id").map(json_value_compact)
+        } else {
+            lookup_field(source, &lookup.key).map(json_value_compact)
+        };
+        if let (Some(key), Some(display)) = (key, lookup_field(source, &lookup.display)) {
+            found.insert(key, json_value_compact(display));
+        }
+    }
+    Ok(found)
+}
+
+fn lookup_field<'a>(source: &'a Value, path: &str) -> Option<&'a Value> {
+    source
+        .get(path)
+        .or_else(|| source.pointer(&format!("/{}", path.replace('.', "/"))))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappingField {
+    pub path: String,
+    pub name: String,
+    pub depth: usize,
+    pub field_type: String,
+    pub analyzer: Option<String>,
+    pub search_analyzer: Option<String>,
+    pub multi_field: bool,
+}
+
+pub fn fetch_mapping(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+) -> Result<Vec<MappingField>> {
+    let index = validate_scope(index)?;
+    let url = endpoint_url(es_url, &[&index, "_mapping"])?;
+    let response = send_request(client.get(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    Ok(mapping_fields(&payload))
+}
+
+pub fn mapping_fields(payload: &Value) -> Vec<MappingField> {
+    let mut fields = Vec::new();
+    for mapping in payload.as_object().into_iter().flat_map(|map| map.values()) {
+        let mut index_fields = Vec::new();
+        if let Some(properties) = mapping.pointer("/mappings/properties") {
+            collect_mapping_fields(properties, "", 0, &mut index_fields);
+        }
+        for field in index_fields {
+            if !fields
+                .iter()
+                .any(|seen: &MappingField| seen.path == field.path)
+            {
+                fields.push(field);
+            }
+        }
+    }
+    fields
+}
+
+fn collect_mapping_fields(
+    properties: &Value,
+    prefix: &str,
+    depth: usize,
+    out: &mut Vec<MappingField>,
+) {
+    let Some(properties) = properties.as_object() else {
+        return;
+    };
+    for (name, definition) in properties {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}.{name}")
+        };
+        out.push(mapping_field(&path, name, depth, definition, false));
+        if let Some(multi) = definition.get("fields").and_then(Value::as_object) {
+            for (sub, definition) in multi {
+                out.push(mapping_field(
+                    &format!("{path}.{sub}"),
+                    sub,
+                    depth + 1,
+                    definition,
+                    true,
+                ));
+            }
+        }
+        if let Some(children) = definition.get("properties") {
+            collect_mapping_fields(children, &path, depth + 1, out);
+        }
+    }
+}
+
+fn mapping_field(
+    path: &str,
+    name: &str,
+    depth: usize,
+    definition: &Value,
+    multi_field: bool,
+) -> MappingField {
+    let text = |key: &str| {
+        definition
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    MappingField {
+        path: path.to_string(),
+        name: name.to_string(),
+        depth,
+        field_type: text("type").unwrap_or_else(|| "object".to_string()),
+        analyzer: text("analyzer"),
+        search_analyzer: text("search_analyzer"),
+        multi_field,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasOp {
+    Add {
+        index: String,
+        alias: String,
+        write: bool,
+    },
+    Remove {
+        index: String,
+        alias: String,
+    },
+}
+
+impl AliasOp {
+    pub fn body(&self) -> Value {
+        match self {
+            AliasOp::Add {
+                index,
+                alias,
+                write,
+            } => {
+                let mut add = serde_json::json!({ "index": index, "alias": alias });
+                if *write {
+                    add["is_write_index"] = Value::Bool(true);
+                }
+                serde_json::json!({ "add": add })
+            }
+            AliasOp::Remove { index, alias } => {
+                serde_json::json!({ "remove": { "index": index, "alias": alias } })
+            }
+        }
+    }
+}
+
+pub fn update_aliases(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    ops: &[AliasOp],
+) -> Result<Value> {
+    let url = endpoint_url(es_url, &["_aliases"])?;
+    let actions: Vec<Value> = ops.iter().map(AliasOp::body).collect();
+    let body = serde_json::json!({ "actions": actions });
+    let response = send_request(client.post(url).json(&body))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    Ok(payload)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexAction {
+    Open,
+    Close,
+    Delete,
+    Refresh,
+    Flush,
+    ForceMerge,
+}
+
+impl IndexAction {
+    pub const ALL: [IndexAction; 6] = [
+        IndexAction::Open,
+        IndexAction::Close,
+        IndexAction::Delete,
+        IndexAction::Refresh,
+        IndexAction::Flush,
+        IndexAction::ForceMerge,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IndexAction::Open => "open",
+            IndexAction::Close => "close",
+            IndexAction::Delete => "delete",
+            IndexAction::Refresh => "refresh",
+            IndexAction::Flush => "flush",
+            IndexAction::ForceMerge => "forcemerge",
+        }
+    }
+
+    pub fn destructive(self) -> bool {
+        matches!(self, IndexAction::Close | IndexAction::Delete)
+    }
+
+    pub fn changes_listing(self) -> bool {
+        matches!(
+            self,
+            IndexAction::Open | IndexAction::Close | IndexAction::Delete
+        )
+    }
+}
+
+const INDEX_ACTION_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub fn index_action_request(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    action: IndexAction,
+) -> Result<reqwest::blocking::RequestBuilder> {
+    let index = validate_index_name(index)?;
+    let index = index.as_str();
+    let request = match action {
+        IndexAction::Delete => client.delete(endpoint_url(es_url, &[index])?),
+        IndexAction::Open => client.post(endpoint_url(es_url, &[index, "_open"])?),
+        IndexAction::Close => client.post(endpoint_url(es_url, &[index, "_close"])?),
+        IndexAction::Refresh => client.post(endpoint_url(es_url, &[index, "_refresh"])?),
+        IndexAction::Flush => client.post(endpoint_url(es_url, &[index, "_flush"])?),
+        IndexAction::ForceMerge => {
+            let mut url = endpoint_url(es_url, &[index, "_forcemerge"])?;
+            url.query_pairs_mut()
+                .append_pair("wait_for_completion", "false");
+            client.post(url)
+        }
+    };
+    Ok(request.timeout(INDEX_ACTION_TIMEOUT))
+}
+
+pub fn run_index_action(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    action: IndexAction,
+) -> Result<Value> {
+    let request = index_action_request(client, es_url, index, action)?;
+    let response = send_request(request)?;
+    let payload: Value = response.json().context("invalid response json")?;
+    Ok(payload)
+}
+
+pub fn index_action_summary(response: &Value) -> String {
+    if let Some(shards) = response.get("_shards") {
+        let count = |key: &str| shards.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let failed = count("failed");
+        let mut summary = format!("{}/{} shards ok", count("successful"), count("total"));
+        if failed > 0 {
+            summary.push_str(&format!(", {failed} failed"));
+        }
+        return summary;
+    }
+    match response.get("acknowledged").and_then(Value::as_bool) {
+        Some(true) => "acknowledged".to_string(),
+        Some(false) => "not acknowledged (the cluster may still apply it)".to_string(),
+        None => truncate_string(&response.to_string(), 80),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggKind {
+    Terms,
+    DateHistogram,
+    Stats,
+}
+
+impl AggKind {
+    pub const ALL: [AggKind; 3] = [AggKind::Terms, AggKind::DateHistogram, AggKind::Stats];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AggKind::Terms => "terms",
+            AggKind::DateHistogram => "date_histogram",
+            AggKind::Stats => "stats",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    pub fn cycled(self, delta: isize) -> Self {
+        let position = Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0);
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(position as isize + delta).rem_euclid(len) as usize]
+    }
+
+    pub fn accepts(self, field_type: &str) -> bool {
+        const NUMERIC: [&str; 9] = [
+            "long",
+            "integer",
+            "short",
+            "byte",
+            "double",
+            "float",
+            "half_float",
+            "scaled_float",
+            "unsigned_long",
+        ];
+        match self {
+            AggKind::Terms => {
+                NUMERIC.contains(&field_type)
+                    || matches!(
+                        field_type,
+                        "keyword" | "constant_keyword" | "boolean" | "ip" | "date" | "date_nanos"
+                    )
+            }
+            AggKind::DateHistogram => matches!(field_type, "date" | "date_nanos"),
+            AggKind::Stats => NUMERIC.contains(&field_type),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggBucket {
+    pub key: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggResult {
+    Buckets {
+        buckets: Vec<AggBucket>,
+        other: u64,
+    },
+    Stats {
+        count: u64,
+        min: Option<f64>,
+        max: Option<f64>,
+        avg: Option<f64>,
+        sum: f64,
+    },
+}
+
+pub const AGG_TERMS_SIZE: u64 = 20;
+pub const AGG_DEFAULT_INTERVAL: &str = "1h";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggRequest {
+    pub kind: AggKind,
+    pub field: String,
+    pub interval: String,
+}
+
+impl AggRequest {
+    pub fn body(&self) -> Value {
+        let field = &self.field;
+        match self.kind {
+            AggKind::Terms => serde_json::json!({
+                "terms": { "field": field, "size": AGG_TERMS_SIZE }
+            }),
+            AggKind::DateHistogram => serde_json::json!({
+                "date_histogram": {
+                    "field": field,
+                    "fixed_interval": self.interval,
+                    "min_doc_count": 1
+                }
+            }),
+            AggKind::Stats => serde_json::json!({ "stats": { "field": field } }),
+        }
+    }
+}
+
+fn agg_bucket(bucket: &Value) -> AggBucket {
+    AggBucket {
+        key: bucket
+            .get("key_as_string")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                bucket
+                    .get("key")
+                    .map(json_value_compact)
+                    .unwrap_or_default()
+            }),
+        count: bucket.get("doc_count").and_then(Value::as_u64).unwrap_or(0),
+    }
+}
+
+pub fn parse_aggregation(kind: AggKind, value: &Value) -> Result<AggResult> {
+    if kind == AggKind::Stats {
+        let number = |key: &str| value.get(key).and_then(Value::as_f64);
+        return Ok(AggResult::Stats {
+            count: value.get("count").and_then(Value::as_u64).unwrap_or(0),
+            min: number("min"),
+            max: number("max"),
+            avg: number("avg"),
+            sum: number("sum").unwrap_or(0.0),
+        });
+    }
+    let buckets = value
+        .get("buckets")
+        .and_then(Value::as_array)
+        .context("aggregation response did not contain buckets")?
+        .iter()
+        .map(agg_bucket)
+        .collect();
+    Ok(AggResult::Buckets {
+        buckets,
+        other: value
+            .get("sum_other_doc_count")
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+    })
+}
+
+fn search_aggregations(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    query: &str,
+    params: &SearchParams,
+    aggs: Value,
+) -> Result<Value> {
+    let mut url = search_url(es_url, index, 0, 0)?;
+    params.apply(&mut url);
+    let mut body = serde_json::json!({
+        "query": query_body(query, params),
+        "aggs": aggs,
+    });
+    if let Some(timeout) = &params.timeout {
+        body["timeout"] = Value::String(timeout.clone());
+    }
+    if let Some(template) = &params.template {
+        apply_template(&mut body, template);
+    }
+    let response = send_request(params.timed(client.post(url).json(&body)))?;
+    let mut payload: Value = response.json().context("invalid response json")?;
+    match payload.get_mut("aggregations") {
+        Some(aggregations) => Ok(aggregations.take()),
+        None => anyhow::bail!("search response did not contain the aggregation"),
+    }
+}
+
+pub fn fetch_aggregation(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    query: &str,
+    params: &SearchParams,
+    request: &AggRequest,
+) -> Result<AggResult> {
+    let aggs = serde_json::json!({ "agg": request.body() });
+    let aggregations = search_aggregations(client, es_url, index, query, params, aggs)?;
+    let value = aggregations
+        .get("agg")
+        .context("search response did not contain the aggregation")?;
+    parse_aggregation(request.kind, value)
+}
+
+pub const PERCENTS: [f64; 3] = [50.0, 90.0, 99.0];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PercentileRow {
+    pub key: String,
+    pub count: u64,
+    pub values: Vec<Option<f64>>,
+}
+
+pub fn percentiles_body(field: &str, split: Option<&str>) -> Value {
+    let percentiles = serde_json::json!({
+        "percentiles": { "field": field, "percents": PERCENTS }
+    });
+    let mut aggs = serde_json::json!({
+        "overall": percentiles,
+        "count": { "value_count": { "field": field } },
+    });
+    if let Some(split) = split {
+        aggs["split"] = serde_json::json!({
+            "terms": { "field": split, "size": AGG_TERMS_SIZE },
+            "aggs": { "pct": percentiles },
+        });
+    }
+    aggs
+}
+
+fn percentile_values(value: &Value) -> Vec<Option<f64>> {
+    PERCENTS
+        .iter()
+        .map(|percent| {
+            value
+                .get("values")
+                .and_then(|values| values.get(format!("{percent:.1}")))
+                .and_then(Value::as_f64)
+        })
+        .collect()
+}
+
+pub fn parse_percentiles(aggregations: &Value) -> Result<Vec<PercentileRow>> {
+    let overall = aggregations
+        .get("overall")
+        .context("search response did not contain percentiles")?;
+    let mut rows = vec![PercentileRow {
+        key: "(all)".to_string(),
+        count: aggregations
+            .pointer("/count/value")
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+        values: percentile_values(overall),
+    }];
+    let buckets = aggregations
+        .pointer("/split/buckets")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+    for bucket in buckets {
+        let AggBucket { key, count } = agg_bucket(bucket);
+        rows.push(PercentileRow {
+            key,
+            count,
+            values: bucket.get("pct").map(percentile_values).unwrap_or_default(),
+        });
+    }
+    Ok(rows)
+}
+
+pub fn fetch_percentiles(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    query: &str,
+    params: &SearchParams,
+    field: &str,
+    split: Option<&str>,
+) -> Result<Vec<PercentileRow>> {
+    let aggs = percentiles_body(field, split);
+    let aggregations = search_aggregations(client, es_url, index, query, params, aggs)?;
+    parse_percentiles(&aggregations)
+}
+
+pub fn open_pit(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    keep_alive: &str,
+    params: &SearchParams,
+) -> Result<String> {
+    let index = validate_scope(index)?;
+    let mut url = endpoint_url(es_url, &[&index, "_pit"])?;
+    url.query_pairs_mut().append_pair("keep_alive", keep_alive);
+    let params = SearchParams {
+        max_concurrent_shard_requests: None,
+        pit: None,
+        ..params.clone()
+    };
+    params.apply(&mut url);
+    let response = send_request(client.post(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    payload
+        .get("id")
+        .and_then(Value::as_str)
+        .map(|id| id.to_string())
+        .context("point in time response did not contain an id")
+}
+
+pub fn close_pit(client: &reqwest::blocking::Client, es_url: &str, id: &str) -> Result<()> {
+    let url = endpoint_url(es_url, &["_pit"])?;
+    send_request(client.delete(url).json(&serde_json::json!({ "id": id })))?;
+    Ok(())
+}
+
+pub fn fetch_slm_policies(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<Vec<(String, SlmPolicyEntry)>> {
+    let url = endpoint_url(es_url, &["_slm", "policy"])?;
+    let response = send_request(client.get(url))?;
+    let payload: HashMap<String, SlmPolicyEntry> =
+        response.json().context("invalid response json")?;
+    let mut policies: Vec<(String, SlmPolicyEntry)> = payload.into_iter().collect();
+    policies.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(policies)
+}
+
+pub fn execute_slm_policy(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    policy: &str,
+) -> Result<String> {
+    let url = endpoint_url(es_url, &["_slm", "policy", policy, "_execute"])?;
+    let response = send_request(client.post(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    Ok(payload
+        .get("snapshot_name")
+        .and_then(Value::as_str)
+        .unwrap_or("-")
+        .to_string())
+}
+
+pub const EXCLUDE_NAME_SETTING: &str = "cluster.routing.allocation.exclude._name";
+
+pub fn fetch_node_names(client: &reqwest::blocking::Client, es_url: &str) -> Result<Vec<String>> {
+    let mut url = endpoint_url(es_url, &["_cat", "nodes"])?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("h", "name");
+    let response = send_request(client.get(url))?;
+    let rows: Vec<Value> = response.json().context("invalid response json")?;
+    let mut names: Vec<String> = rows
+        .iter()
+        .filter_map(|row| row.get("name").and_then(Value::as_str).map(str::to_string))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExcludedNodes {
+    pub persistent: Vec<String>,
+    pub transient: Vec<String>,
+}
+
+impl ExcludedNodes {
+    pub fn from_settings(payload: &Value) -> Self {
+        let names = |scope: &str| -> Vec<String> {
+            payload
+                .get(scope)
+                .and_then(|settings| settings.get(EXCLUDE_NAME_SETTING))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        ExcludedNodes {
+            persistent: names("persistent"),
+            transient: names("transient"),
+        }
+    }
+
+    pub fn contains(&self, node: &str) -> bool {
+        self.persistent
+            .iter()
+            .chain(&self.transient)
+            .any(|name| name == node)
+    }
+}
+
+pub fn fetch_excluded_nodes(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<ExcludedNodes> {
+    let mut url = endpoint_url(es_url, &["_cluster", "settings"])?;
+    url.query_pairs_mut().append_pair("flat_settings", "true");
+    let response = send_request(client.get(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    Ok(ExcludedNodes::from_settings(&payload))
+}
+
+pub fn put_excluded_nodes(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    nodes: &[String],
+) -> Result<()> {
+    let url = endpoint_url(es_url, &["_cluster", "settings"])?;
+    let value = if nodes.is_empty() {
+        Value::Null
+    } else {
+        Value::String(nodes.join(","))
+    };
+    let mut settings = serde_json::Map::new();
+    settings.insert(EXCLUDE_NAME_SETTING.to_string(), value);
+    let body = serde_json::json!({ "persistent": settings });
+    send_request(client.put(url).json(&body))?;
+    Ok(())
+}
+
+pub fn fetch_node_shard_counts(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<(Vec<AllocationRow>, Vec<ShardRow>)> {
+    let mut url = endpoint_url(es_url, &["_cat", "allocation"])?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("h", "node,shards");
+    let allocation: Vec<AllocationRow> = send_request(client.get(url))?
+        .json()
+        .context("invalid response json")?;
+    let mut url = endpoint_url(es_url, &["_cat", "shards"])?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("h", "node,state");
+    let shards: Vec<ShardRow> = send_request(client.get(url))?
+        .json()
+        .context("invalid response json")?;
+    Ok((allocation, shards))
+}
+
+pub fn fetch_shard_placement(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+) -> Result<Vec<ShardPlacement>> {
+    let index = validate_scope(index)?;
+    let mut url = endpoint_url(es_url, &["_cat", "shards", &index])?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("h", "shard,prirep,state,node,docs,store")
+        .append_pair("bytes", "b");
+    let response = send_request(client.get(url))?;
+    let mut shards: Vec<ShardPlacement> = response.json().context("invalid response json")?;
+    shards.sort_by(|a, b| {
+        let key = |shard: &ShardPlacement| shard.shard.parse::<u64>().unwrap_or(u64::MAX);
+        key(a).cmp(&key(b)).then_with(|| a.prirep.cmp(&b.prirep))
+    });
+    Ok(shards)
+}
+
+pub fn post_reroute(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    command: &Value,
+    dry_run: bool,
+) -> Result<Value> {
+    let mut url = endpoint_url(es_url, &["_cluster", "reroute"])?;
+    url.query_pairs_mut()
+        .append_pair("dry_run", if dry_run { "true" } else { "false" })
+        .append_pair("explain", "true")
+        .append_pair("metric", "none");
+    let body = serde_json::json!({ "commands": [command] });
+    let response = send_request(client.post(url).json(&body))?;
+    response.json().context("invalid response json")
+}
+
+pub fn fetch_license(client: &reqwest::blocking::Client, es_url: &str) -> Result<LicenseInfo> {
+    let url = endpoint_url(es_url, &["_license"])?;
+    let response = send_request(client.get(url))?;
+    let payload: LicenseResponse = response.json().context("invalid response json")?;
+    Ok(payload.license)
+}
+
+pub fn fetch_xpack_usage(client: &reqwest::blocking::Client, es_url: &str) -> Result<Value> {
+    let url = endpoint_url(es_url, &["_xpack", "usage"])?;
+    let response = send_request(client.get(url))?;
+    response.json().context("invalid response json")
+}
+
+pub fn fetch_migration_deprecations(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<Value> {
+    let url = endpoint_url(es_url, &["_migration", "deprecations"])?;
+    let response = send_request(client.get(url))?;
+    response.json().context("invalid response json")
+}
+
+pub fn fetch_master_name(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<Option<String>> {
+    let mut url = endpoint_url(es_url, &["_cat", "master"])?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("h", "node");
+    let response = send_request(client.get(url))?;
+    let rows: Vec<Value> = response.json().context("invalid response json")?;
+    Ok(rows
+        .first()
+        .and_then(|row| row.get("node"))
+        .and_then(Value::as_str)
+        .map(str::to_string))
+}
+
+pub fn fetch_coordination_state(client: &reqwest::blocking::Client, es_url: &str) -> Result<Value> {
+    let mut url = endpoint_url(es_url, &["_cluster", "state", "master_node,nodes,metadata"])?;
+    url.query_pairs_mut().append_pair(
+        "filter_path",
+        "master_node,nodes.*.name,metadata.cluster_coordination",
+    );
+    let response = send_request(client.get(url))?;
+    response.json().context("invalid response json")
+}
+
+pub type IndexTags = Vec<(String, String)>;
+
+pub fn fetch_index_tags(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    meta_fields: &[String],
+    settings_keys: &[String],
+) -> Result<HashMap<String, IndexTags>> {
+    let mut tags: HashMap<String, IndexTags> = HashMap::new();
+    if !meta_fields.is_empty() {
+        let mut url = endpoint_url(es_url, &["_mapping"])?;
+        url.query_pairs_mut()
+            .append_pair("filter_path", "*.mappings._meta");
+        let response = send_request(client.get(url))?;
+        let payload: Value = response.json().context("invalid response json")?;
+        for (index, value) in payload.as_object().into_iter().flatten() {
+            let Some(meta) = value.pointer("/mappings/_meta") else {
+                continue;
+            };
+            for field in meta_fields {
+                if let Some(value) = meta.get(field) {
+                    tags.entry(index.clone())
+                        .or_default()
+                        .push((field.clone(), json_value_inline(value)));
+                }
+            }
+        }
+    }
+    if !settings_keys.is_empty() {
+        let keys = settings_keys.join(",");
+        let mut url = endpoint_url(es_url, &["_all", "_settings", &keys])?;
+        url.query_pairs_mut().append_pair("flat_settings", "true");
+        let response = send_request(client.get(url))?;
+        let payload: Value = response.json().context("invalid response json")?;
+        for (index, value) in payload.as_object().into_iter().flatten() {
+            let Some(settings) = value.get("settings") else {
+                continue;
+            };
+            for key in settings_keys {
+                if let Some(value) = settings.get(key) {
+                    let label = key.rsplit('.').next().unwrap_or(key);
+                    tags.entry(index.clone())
+                        .or_default()
+                        .push((label.to_string(), json_value_inline(value)));
+                }
+            }
+        }
+    }
+    Ok(tags)
+}
+
+pub fn fetch_tsdb_settings(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+) -> Result<HashMap<String, TsdbInfo>> {
+    let mut url = endpoint_url(
+        es_url,
+        &[
+            "_settings",
+            "index.mode,index.time_series.*,index.downsample.status,index.downsample.source.name",
+        ],
+    )?;
+    url.query_pairs_mut().append_pair("flat_settings", "true");
+    let response = send_request(client.get(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    let mut tsdb = HashMap::new();
+    for (index, value) in payload.as_object().into_iter().flatten() {
+        let Some(settings) = value.get("settings") else {
+            continue;
+        };
+        let setting = |key: &str| {
+            settings
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        if setting("index.mode").as_deref() != Some("time_series") {
+            continue;
+        }
+        tsdb.insert(
+            index.clone(),
+            TsdbInfo {
+                start_time: setting("index.time_series.start_time"),
+                end_time: setting("index.time_series.end_time"),
+                downsample_status: setting("index.downsample.status"),
+                downsample_source: setting("index.downsample.source.name"),
+            },
+        );
+    }
+    Ok(tsdb)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexSettings {
+    pub settings: BTreeMap<String, String>,
+    pub defaults: BTreeMap<String, String>,
+}
+
+impl IndexSettings {
+    pub fn get(&self, key: &str) -> Option<(&str, bool)> {
+        if let Some(value) = self.settings.get(key) {
+            return Some((value, false));
+        }
+        self.defaults.get(key).map(|value| (value.as_str(), true))
+    }
+}
+
+pub fn fetch_index_settings(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+) -> Result<IndexSettings> {
+    let index = validate_scope(index)?;
+    let mut url = endpoint_url(es_url, &[&index, "_settings"])?;
+    url.query_pairs_mut()
+        .append_pair("flat_settings", "true")
+        .append_pair("include_defaults", "true");
+    let response = send_request(client.get(url))?;
+    let payload: Value = response.json().context("invalid response json")?;
+    let value = payload
+        .as_object()
+        .and_then(|map| map.values().next())
+        .context("settings response did not contain the index")?;
+    let flat = |key: &str| -> BTreeMap<String, String> {
+        value
+            .get(key)
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.clone(), json_value_compact(value)))
+            .collect()
+    };
+    Ok(IndexSettings {
+        settings: flat("settings"),
+        defaults: flat("defaults"),
+    })
+}
+
+pub fn fetch_terms_enum(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    field: &str,
+    prefix: &str,
+) -> Result<TermsEnumResponse> {
+    let index = validate_scope(index)?;
+    let url = endpoint_url(es_url, &[&index, "_terms_enum"])?;
+    let body = serde_json::json!({
+        "field": field,
+        "string": prefix,
+        "size": 20,
+    });
+    let response = send_request(client.post(url).json(&body))?;
+    let terms: TermsEnumResponse = response.json().context("invalid response json")?;
+    Ok(terms)
+}
+
+pub struct PageAfter {
+    pub docs: Vec<DocEntry>,
+    pub total: Option<u64>,
+    pub next: Option<Value>,
+}
+
+pub fn fetch_page_after(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    query: &str,
+    params: &SearchParams,
+    size: u64,
+    after: Option<&Value>,
+) -> Result<PageAfter> {
+    let pit = params
+        .pit
+        .as_ref()
+        .context("search_after paging needs a point in time")?;
+    let mut url = endpoint_url(es_url, &["_search"])?;
+    params.apply(&mut url);
+    let mut sort = match &params.sort {
+        Some(sort) => sort.body(),
+        None => serde_json::json!([]),
+    };
+    if let Some(sort) = sort.as_array_mut() {
+        sort.push(serde_json::json!({ "_shard_doc": "asc" }));
+    }
+    let mut body = serde_json::json!({
+        "query": query_body(query, params),
+        "pit": { "id": pit.id, "keep_alive": pit.keep_alive },
+        "size": size,
+        "sort": sort,
+        "track_total_hits": true,
+    });
+    if let Some(after) = after {
+        body["search_after"] = after.clone();
+    }
+    if let Some(timeout) = &params.timeout {
+        body["timeout"] = Value::String(timeout.clone());
+    }
+    if let Some(template) = &params.template {
+        apply_template(&mut body, template);
+    }
+    let response = send_request(params.timed(client.post(url).json(&body)))?;
+    let payload: SearchResponse = response.json().context("invalid response json")?;
+    let next = payload.hits.hits.last().and_then(|hit| hit.sort.clone());
+    Ok(PageAfter {
+        total: payload.hits.total.map(|value| value.value),
+        next,
+        docs: payload
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| DocEntry {
+                index: hit.index,
+                id: hit.id,
+                source: hit.source,
+                version: hit.version,
+                seq_no: hit.seq_no,
+                sort: hit.sort,
+            })
+            .collect(),
+    })
+}
+
+pub fn paging_sort(params: &SearchParams) -> Value {
+    let mut sort = match &params.sort {
+        Some(sort) => sort.body(),
+        None => serde_json::json!([{ "_score": "desc" }]),
+    };
+    if params.pit.is_some()
+        && let Some(sort) = sort.as_array_mut()
+    {
+        sort.push(serde_json::json!({ "_shard_doc": "asc" }));
+    }
+    sort
+}
+
+pub fn documents_body(query: &str, params: &SearchParams, page: &DocsPage) -> Value {
+    let mut body = serde_json::json!({ "query": query_body(query, params) });
+    if let Some(pit) = &params.pit {
+        body["pit"] = serde_json::json!({ "id": pit.id, "keep_alive": pit.keep_alive });
+    }
+    body["version"] = Value::Bool(true);
+    body["seq_no_primary_term"] = Value::Bool(true);
+    if let Some(timeout) = &params.timeout {
+        body["timeout"] = Value::String(timeout.clone());
+    }
+    if let Some(terminate_after) = params.terminate_after {
+        body["terminate_after"] = Value::from(terminate_after);
+    }
+    body["sort"] = paging_sort(params);
+    if let DocsPage::After(after) = page {
+        body["search_after"] = after.clone();
+    }
+    if let Some(template) = &params.template {
+        apply_template(&mut body, template);
+    }
+    body
+}
+
+fn documents_url(
+    es_url: &str,
+    index: &str,
+    from: u64,
+    size: u64,
+    params: &SearchParams,
+) -> Result<reqwest::Url> {
+    match &params.pit {
+        Some(_) => {
+            let mut url = endpoint_url(es_url, &["_search"])?;
+            url.query_pairs_mut()
+                .append_pair("from", &from.to_string())
+                .append_pair("size", &size.to_string());
+            Ok(url)
+        }
+        None => search_url(es_url, index, from, size),
+    }
+}
+
+pub fn fetch_documents(
+    client: &reqwest::blocking::Client,
+    es_url: &str,
+    index: &str,
+    page: &DocsPage,
+    size: u64,
+    query: &str,
+    params: &SearchParams,
+) -> Result<(Vec<DocEntry>, SearchSummary)> {
+    let from = match page {
+        DocsPage::From(from) => *from,
+        DocsPage::After(_) => 0,
+    };
+    let mut url = documents_url(es_url, index, from, size, params)?;
+    params.apply(&mut url);
+    let body = documents_body(query, params, page);
+    let response = send_request(params.timed(client.post(url).json(&body)))?;
+    let payload: SearchResponse = response.json().context("invalid response json")?;
+    let total = payload.hits.total.map(|value| value.value);
+    let shards_failed = payload.shards.map(|shards| shards.failed);
+    let summary = SearchSummary {
+        total,
+        took: payload.took,
+        shards_failed,
+        timed_out: payload.timed_out,
+        pit_id: payload.pit_id,
+        terminated_early: payload.terminated_early,
+    };
+    let docs = payload
+        .hits
+        .hits
+        .into_iter()
+        .map(|hit| DocEntry {
+            index: hit.index,
+            id: hit.id,
+            source: hit.source,
+            version: hit.version,
+            seq_no: hit.seq_no,
+            sort: hit.sort,
+        })
+        .collect();
+    Ok((docs, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_aggregation_reads_buckets_and_stats() {
+        let terms = serde_json::json!({
+            "sum_other_doc_count": 7,
+            "buckets": [
+                { "key": "error", "doc_count": 12 },
+                { "key": 404, "doc_count": 3 }
+            ]
+        });
+        assert_eq!(
+            parse_aggregation(AggKind::Terms, &terms).unwrap(),
+            AggResult::Buckets {
+                buckets: vec![
+                    AggBucket {
+                        key: "error".to_string(),
+                        count: 12
+                    },
+                    AggBucket {
+                        key: "404".to_string(),
+                        count: 3
+                    },
+                ],
+                other: 7,
+            }
+        );
+        let histogram = serde_json::json!({
+            "buckets": [{ "key": 1, "key_as_string": "2024-01-01T00:00:00.000Z", "doc_count": 5 }]
+        });
+        let AggResult::Buckets { buckets, .. } =
+            parse_aggregation(AggKind::DateHistogram, &histogram).unwrap()
+        else {
+            panic!("expected buckets");
+        };
+        assert_eq!(buckets[0].key, "2024-01-01T00:00:00.000Z");
+        let stats =
+            serde_json::json!({ "count": 0, "min": null, "max": null, "avg": null, "sum": 0.0 });
+        assert!(matches!(
+            parse_aggregation(AggKind::Stats, &stats).unwrap(),
+            AggResult::Stats {
+                count: 0,
+                min: None,
+                ..
+            }
+        ));
+        assert!(AggKind::Stats.accepts("long") && !AggKind::Stats.accepts("keyword"));
+        assert_eq!(AggKind::Terms.cycled(-1), AggKind::Stats);
+    }
+
+    #[test]
+    fn parse_percentiles_reads_overall_and_split_rows() {
+        let body = percentiles_body("event.duration", Some("service.name"));
+        assert_eq!(
+            body["split"]["aggs"]["pct"]["percentiles"]["percents"],
+            serde_json::json!([50.0, 90.0, 99.0])
+        );
+        let aggregations = serde_json::json!({
+            "overall": { "values": { "50.0": 12.0, "90.0": 80.5, "99.0": 310.0 } },
+            "count": { "value": 42 },
+            "split": { "buckets": [
+                { "key": "checkout", "doc_count": 30,
+                  "pct": { "values": { "50.0": 15.0, "90.0": 95.0, "99.0": null } } },
+            ] },
+        });
+        assert_eq!(
+            parse_percentiles(&aggregations).unwrap(),
+            [
+                PercentileRow {
+                    key: "(all)".to_string(),
+                    count: 42,
+                    values: vec![Some(12.0), Some(80.5), Some(310.0)],
+                },
+                PercentileRow {
+                    key: "checkout".to_string(),
+                    count: 30,
+                    values: vec![Some(15.0), Some(95.0), None],
+                },
+            ]
+        );
+        assert_eq!(
+            parse_percentiles(&serde_json::json!({}))
+                .unwrap_err()
+                .to_string(),
+            "search response did not contain percentiles"
+        );
+        let rows = parse_percentiles(&serde_json::json!({
+            "overall": { "values": {} },
+            "split": { "buckets": [{ "key": "cart", "doc_count": 0 }] },
+        }))
+        .unwrap();
+        assert_eq!(rows[0].count, 0);
+        assert_eq!(rows[1].key, "cart");
+        assert!(rows[1].values.is_empty());
+    }
+
+    #[test]
+    fn query_body_applies_scope_query_options() {
+        let params = SearchParams {
+            query_options: QueryOptions {
+                default_field: Some("message".to_string()),
+                fields: Vec::new(),
+                lenient: true,
+                operator: Some(QueryOperator::Or),
+                minimum_should_match: Some("2".to_string()),
+            },
+            ..SearchParams::default()
+        };
+        let query = query_body("error", &params);
+        assert_eq!(query["query_string"]["default_field"], "message");
+        assert_eq!(query["query_string"]["lenient"], true);
+        assert_eq!(query["query_string"]["default_operator"], "OR");
+        assert_eq!(query["query_string"]["minimum_should_match"], "2");
+        assert_eq!(query_body("", &params), search_body("")["query"]);
+        let fuzzy = query_body(
+            "conection reset",
+            &SearchParams {
+                fuzzy: true,
+                ..params
+            },
+        );
+        assert_eq!(fuzzy["multi_match"]["fuzziness"], "AUTO");
+        assert_eq!(
+            fuzzy["multi_match"]["fields"],
+            serde_json::json!(["message"])
+        );
+        assert_eq!(fuzzy["multi_match"]["operator"], "OR");
+    }
+
+    #[test]
+    fn query_body_combines_field_filters_in_a_bool_query() {
+        let params = SearchParams {
+            filters: vec![
+                FieldFilter {
+                    field: "service.name".to_string(),
+                    value: serde_json::json!("checkout"),
+                    exclude: false,
+                },
+                FieldFilter {
+                    field: "http.status".to_string(),
+                    value: serde_json::json!(200),
+                    exclude: true,
+                },
+            ],
+            ..SearchParams::default()
+        };
+        assert_eq!(
+            query_body("error", &params),
+            serde_json::json!({ "bool": {
+                "must": [
+                    { "query_string": { "query": "error", "default_operator": "AND" } },
+                    { "term": { "service.name": "checkout" } },
+                ],
+                "must_not": [{ "term": { "http.status": 200 } }],
+            } })
+        );
+        assert_eq!(
+            query_body("error", &SearchParams::default()),
+            serde_json::json!({ "query_string": { "query": "error", "default_operator": "AND" } })
+        );
+        let exclude_only = SearchParams {
+            filters: params.filters[1..].to_vec(),
+            ..SearchParams::default()
+        };
+        assert_eq!(
+            query_body("error", &exclude_only)["bool"]["must"],
+            serde_json::json!([{ "query_string": { "query": "error", "default_operator": "AND" } }])
+        );
+    }
+
+    #[test]
+    fn mapping_fields_walk_objects_and_multi_fields() {
+        let payload = serde_json::json!({
+            "logs-1": { "mappings": { "properties": {
+                "message": {
+                    "type": "text",
+                    "analyzer": "english",
+                    "fields": { "raw": { "type": "keyword" } }
+                },
+                "user": { "properties": { "id": { "type": "keyword" } } }
+            } } },
+            "logs-2": { "mappings": { "properties": {
+                "message": { "type": "text" },
+                "took": { "type": "long" }
+            } } }
+        });
+        let fields = mapping_fields(&payload);
+        let rows: Vec<(&str, usize, &str, bool)> = fields
+            .iter()
+            .map(|field| {
+                (
+                    field.path.as_str(),
+                    field.depth,
+                    field.field_type.as_str(),
+                    field.multi_field,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("message", 0, "text", false),
+                ("message.raw", 1, "keyword", true),
+                ("user", 0, "object", false),
+                ("user.id", 1, "keyword", false),
+                ("took", 0, "long", false),
+            ]
+        );
+        assert_eq!(fields[0].analyzer.as_deref(), Some("english"));
+        let empty = serde_json::json!({
+            "closed-1": {},
+            "new-1": { "mappings": {} },
+            "odd-1": { "mappings": { "properties": [] } },
+        });
+        assert!(mapping_fields(&empty).is_empty());
+        assert!(mapping_fields(&serde_json::json!(null)).is_empty());
+    }
+
+    #[test]
+    fn template_merges_into_request_and_filters_query() {
+        let mut body = serde_json::json!({
+            "query": { "match_all": {} },
+            "_source": { "includes": ["message"] },
+            "version": true
+        });
+        let template = serde_json::json!({
+            "query": { "exists": { "field": "message" } },
+            "_source": { "excludes": ["payload"] },
+            "terminate_after": 10000,
+            "version": false
+        });
+        apply_template(&mut body, &template);
+        assert_eq!(
+            body["query"],
+            serde_json::json!({ "bool": {
+                "must": [{ "match_all": {} }],
+                "filter": [{ "exists": { "field": "message" } }]
+            } })
+        );
+        assert_eq!(body["_source"]["includes"], serde_json::json!(["message"]));
+        assert_eq!(body["_source"]["excludes"], serde_json::json!(["payload"]));
+        assert_eq!(body["terminate_after"], 10000);
+        assert_eq!(body["version"], true);
+        let before = body.clone();
+        apply_template(&mut body, &serde_json::json!(["not", "an", "object"]));
+        apply_template(&mut body, &serde_json::json!({}));
+        assert_eq!(body, before);
+    }
+
+    #[test]
+    fn dsl_query_accepts_bodies_and_bare_clauses() {
+        let term = serde_json::json!({ "term": { "status": 500 } });
+        assert_eq!(
+            parse_dsl_query(r#"{"query":{"term":{"status":500}}}"#).unwrap(),
+            term
+        );
+        assert_eq!(parse_dsl_query(r#"{"term":{"status":500}}"#).unwrap(), term);
+        assert_eq!(
+            parse_dsl_query("  ").unwrap(),
+            serde_json::json!({ "match_all": {} })
+        );
+        assert!(parse_dsl_query(r#"{"term":"#).is_err());
+        assert!(parse_dsl_query("[1]").is_err());
+        assert!(parse_dsl_query(r#"{"query":1}"#).is_err());
+    }
+
+    #[test]
+    fn dsl_query_names_request_keys_it_would_drop() {
+        let err = parse_dsl_query(r#"{"query":{"match_all":{}},"size":5,"sort":["@timestamp"]}"#)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported request keys: size, sort; only \"query\" is used"
+        );
+        let err = parse_dsl_query(r#"{"size":5}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported request keys: size; only \"query\" is used"
+        );
+        assert!(parse_dsl_query(r#"{"aggs":{"n":{"terms":{"field":"x"}}}}"#).is_err());
+        assert_eq!(
+            parse_dsl_query(r#"{"bool":{"filter":[]}}"#).unwrap(),
+            serde_json::json!({ "bool": { "filter": [] } })
+        );
+    }
+
+    #[test]
+    fn sample_query_wraps_query_in_seeded_random_score() {
+        let query = search_body("status:500")["query"].clone();
+        let sampled = sample_query(query.clone(), 42);
+        assert_eq!(sampled["function_score"]["query"], query);
+        assert_eq!(sampled["function_score"]["random_score"]["seed"], 42);
+        assert_eq!(sampled["function_score"]["boost_mode"], "replace");
+    }
+
+    #[test]
+    fn endpoint_url_joins_root_base() {
+        let url = endpoint_url("http://localhost:9200", &["_cluster", "health"]).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:9200/_cluster/health");
+    }
+
+    #[test]
+    fn endpoint_url_handles_trailing_slash() {
+        let url = endpoint_url("http://localhost:9200/", &["_data_stream"]).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:9200/_data_stream");
+    }
+
+    #[test]
+    fn endpoint_url_preserves_path_prefix() {
+        let url =
+            endpoint_url("https://gateway.example.com/es/prod", &["books", "_search"]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://gateway.example.com/es/prod/books/_search"
+        );
+    }
+
+    #[test]
+    fn endpoint_url_preserves_prefix_with_trailing_slash() {
+        let mut url =
+            endpoint_url("https://gateway.example.com/es/prod/", &["_cat", "indices"]).unwrap();
+        url.query_pairs_mut().append_pair("format", "json");
+        assert_eq!(
+            url.as_str(),
+            "https://gateway.example.com/es/prod/_cat/indices?format=json"
+        );
+    }
+
+    #[test]
+    fn endpoint_url_percent_encodes_segments() {
+        let url = endpoint_url("http://localhost:9200", &["weird%name", "_search"]).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:9200/weird%25name/_search");
+        let url = endpoint_url("http://localhost:9200", &["日本", "_search"]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:9200/%E6%97%A5%E6%9C%AC/_search"
+        );
+    }
+
+    #[test]
+    fn resolved_scope_parses_resolve_response() {
+        let resolved: ResolvedScope = serde_json::from_str(
+            r#"{
+                "indices": [
+                    { "name": "logs-a", "attributes": ["open"] },
+                    { "name": ".ds-logs-web-000001", "attributes": ["open"], "data_stream": "logs-web" }
+                ],
+                "aliases": [{ "name": "logs", "indices": ["logs-a"] }],
+                "data_streams": [{ "name": "logs-web", "backing_indices": [".ds-logs-web-000001"], "timestamp_field": "@timestamp" }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(resolved.indices.len(), 2);
+        assert_eq!(resolved.indices[1].data_stream.as_deref(), Some("logs-web"));
+        assert_eq!(resolved.aliases[0].indices, vec!["logs-a"]);
+        assert_eq!(resolved.data_streams[0].backing_indices.len(), 1);
+        assert!(!resolved.is_empty());
+        assert!(ResolvedScope::default().is_empty());
+    }
+
+    #[test]
+    fn search_params_append_only_set_values() {
+        let mut url = search_url("http://localhost:9200", "logs-*", 0, 5).unwrap();
+        SearchParams::default().apply(&mut url);
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:9200/logs-*/_search?from=0&size=5"
+        );
+        SearchParams {
+            max_concurrent_shard_requests: Some(3),
+            ignore_unavailable: true,
+            preference: Some("_local".to_string()),
+            routing: Some("user 1".to_string()),
+            pit: None,
+            time_zone: Some("+09:00".to_string()),
+            sample_seed: None,
+            query_options: QueryOptions::default(),
+            fuzzy: false,
+            template: None,
+            timeout: None,
+            terminate_after: None,
+            dsl: None,
+            sort: None,
+            filters: Vec::new(),
+            request_cache: Some(false),
+        }
+        .apply(&mut url);
+        assert_eq!(
+            url.query(),
+            Some(
+                "from=0&size=5&max_concurrent_shard_requests=3&ignore_unavailable=true\
+                 &preference=_local&routing=user+1&request_cache=false"
+            )
+        );
+    }
+
+    #[test]
+    fn sort_spec_builds_a_sort_clause() {
+        let sort = SortSpec {
+            field: "@timestamp".to_string(),
+            order: SortOrder::default(),
+        };
+        assert_eq!(
+            sort.body(),
+            serde_json::json!([{ "@timestamp": { "order": "desc" } }])
+        );
+        assert_eq!(sort.order.toggled().as_str(), "asc");
+    }
+
+    #[test]
+    fn documents_body_pages_with_search_after_and_a_tiebreaker() {
+        let params = SearchParams::default();
+        let first = documents_body("", &params, &DocsPage::From(0));
+        assert_eq!(first["sort"], serde_json::json!([{ "_score": "desc" }]));
+        assert!(first.get("search_after").is_none());
+        let params = SearchParams {
+            pit: Some(PitRef {
+                id: "pit-1".to_string(),
+                keep_alive: "1m".to_string(),
+            }),
+            sort: Some(SortSpec {
+                field: "@timestamp".to_string(),
+                order: SortOrder::Desc,
+            }),
+            ..SearchParams::default()
+        };
+        let after = serde_json::json!([1700000000000u64, 42]);
+        let next = documents_body("", &params, &DocsPage::After(after.clone()));
+        assert_eq!(
+            next["sort"],
+            serde_json::json!([
+                { "@timestamp": { "order": "desc" } },
+                { "_shard_doc": "asc" }
+            ])
+        );
+        assert_eq!(next["search_after"], after);
+    }
+
+    #[test]
+    fn validate_scope_accepts_patterns() {
+        assert_eq!(validate_scope("books").unwrap(), "books");
+        assert_eq!(
+            validate_scope("logs-*, metrics-*").unwrap(),
+            "logs-*,metrics-*"
+        );
+        assert_eq!(validate_scope("remote:logs-*").unwrap(), "remote:logs-*");
+        assert_eq!(validate_scope("_all").unwrap(), "_all");
+    }
+
+    #[test]
+    fn validate_scope_rejects_malformed_names() {
+        for scope in [
+            "",
+            "my index",
+            "logs,,metrics",
+            "books\r\nX-Injected: 1",
+            "books/_doc",
+            "books?pretty",
+            "books#frag",
+            "..",
+            "_cluster",
+        ] {
+            assert!(
+                validate_scope(scope).is_err(),
+                "{scope:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn excluded_nodes_keep_transient_and_persistent_apart() {
+        let payload = serde_json::json!({
+            "persistent": { EXCLUDE_NAME_SETTING: "node-1, node-2" },
+            "transient": { EXCLUDE_NAME_SETTING: "node-3" },
+        });
+        let excluded = ExcludedNodes::from_settings(&payload);
+        assert_eq!(excluded.persistent, vec!["node-1", "node-2"]);
+        assert_eq!(excluded.transient, vec!["node-3"]);
+        assert!(excluded.contains("node-3"));
+        assert_eq!(
+            ExcludedNodes::from_settings(&serde_json::json!({})),
+            ExcludedNodes::default()
+        );
+    }
+
+    #[test]
+    fn only_connect_failures_count_as_unreachable() {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let refused = send_request(client.get("http://127.0.0.1:9/")).unwrap_err();
+        assert!(is_unreachable(&refused));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let slow = send_request(client.get(url)).unwrap_err();
+        assert!(!is_unreachable(&slow), "{slow:#}");
+    }
+
+    #[test]
+    fn search_timeouts_above_the_client_timeout_extend_the_request() {
+        let client = reqwest::blocking::Client::new();
+        let timeout = |value: Option<&str>| {
+            let params = SearchParams {
+                timeout: value.map(str::to_string),
+                ..SearchParams::default()
+            };
+            let request = params.timed(client.post("http://localhost:9200/_search"));
+            request.build().unwrap().timeout().copied()
+        };
+        assert_eq!(timeout(None), None);
+        assert_eq!(timeout(Some("30s")), Some(Duration::from_secs(32)));
+        assert_eq!(timeout(Some("500ms")), Some(REQUEST_TIMEOUT));
+        assert_eq!(timeout(Some("soon")), None);
+    }
+
+    #[test]
+    fn index_actions_run_forcemerge_as_a_task_with_a_long_timeout() {
+        let client = reqwest::blocking::Client::new();
+        let request = |action| {
+            index_action_request(&client, "http://localhost:9200", "logs-1", action)
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+        let merge = request(IndexAction::ForceMerge);
+        assert_eq!(
+            merge.url().as_str(),
+            "http://localhost:9200/logs-1/_forcemerge?wait_for_completion=false"
+        );
+        let delete = request(IndexAction::Delete);
+        assert_eq!(delete.method(), reqwest::Method::DELETE);
+        assert_eq!(delete.timeout(), Some(&INDEX_ACTION_TIMEOUT));
+        assert!(delete.url().query().is_none());
+    }
+
+    #[test]
+    fn run_index_action_rejects_patterns_before_sending() {
+        let client = reqwest::blocking::Client::new();
+        for index in ["logs-1,logs-2", "logs-*", " ", "_all"] {
+            let err = run_index_action(&client, "http://127.0.0.1:9", index, IndexAction::Delete)
+                .unwrap_err()
+                .to_string();
+            assert!(
+                err.contains("scope") || err.contains("single index"),
+                "{index:?} reached the network: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_warning_header_extracts_quoted_message() {
+        assert_eq!(
+            parse_warning_header(
+                r#"299 Elasticsearch-8.11.0-abc "[types removal] Specifying \"types\" is deprecated." "Mon, 01 Jan 2024 00:00:00 GMT""#
+            ),
+            Some(r#"[types removal] Specifying "types" is deprecated."#.to_string())
+        );
+        assert_eq!(parse_warning_header("299 Elasticsearch-8.11.0"), None);
+    }
+
+    #[test]
+    fn migration_issues_flatten_cluster_and_index_sections() {
+        let payload = serde_json::json!({
+            "cluster_settings": [{ "level": "critical", "message": "setting removed" }],
+            "node_settings": [],
+            "index_settings": {
+                "logs-1": [{ "level": "warning", "message": "old format", "details": "reindex" }]
+            }
+        });
+        let issues = migration_issues(&payload);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].label(), "cluster_settings");
+        assert_eq!(issues[0].level, "critical");
+        assert_eq!(issues[1].label(), "index_settings logs-1");
+        assert_eq!(issues[1].details.as_deref(), Some("reindex"));
+        let sparse = migration_issues(&serde_json::json!({ "ml_settings": [{}], "count": 3 }));
+        assert_eq!(sparse.len(), 1);
+        assert_eq!(
+            (sparse[0].level.as_str(), sparse[0].message.as_str()),
+            ("warning", "-")
+        );
+        assert!(migration_issues(&serde_json::json!([])).is_empty());
+    }
+
+    #[test]
+    fn endpoint_url_rejects_invalid_base() {
+        assert!(endpoint_url("localhost:9200", &["_cluster", "health"]).is_err());
+        assert!(endpoint_url("not a url", &["_cluster", "health"]).is_err());
+    }
+}
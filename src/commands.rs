@@ -0,0 +1,4952 @@
+use std::collections::{BTreeMap, HashSet};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::app::*;
+use crate::client::*;
+use crate::config::*;
+use crate::ecs::*;
+use crate::export::*;
+use crate::format::*;
+use crate::patterns::*;
+use crate::query::*;
+use crate::share::*;
+use crate::state::*;
+use crate::terminal::*;
+use crate::ui::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BenchOptions {
+    pub(crate) runs: usize,
+    pub(crate) warmup: usize,
+    pub(crate) request_cache: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BenchReport {
+    pub(crate) took_ms: Vec<u64>,
+    pub(crate) latency_ms: Vec<u64>,
+    pub(crate) errors: usize,
+    pub(crate) first_error: Option<String>,
+}
+
+pub fn run_command(app: &mut App, input: &str) {
+    let mut words = input.split_whitespace();
+    let Some(name) = words.next() else {
+        return;
+    };
+    let args: Vec<&str> = words.collect();
+    app.usage.record(format!("command:{name}"));
+    let result = match name {
+        "bench" if args == ["cancel"] => cancel_benchmark(app),
+        "bench" => parse_bench_options(&args).and_then(|options| run_benchmark(app, options)),
+        "validate" => run_validate_query(app),
+        "explain" => open_query_translation(app),
+        "terms" => open_terms_lookup(app, &args),
+        "jsonpath" => set_json_path(app, &args),
+        "retention" => update_retention(app, &args),
+        "tsdb" => open_tsdb_overview(app),
+        "slm" => run_slm_command(app, &args),
+        "drain" => run_drain_command(app, &args),
+        "shards" => open_shard_placement(app, None),
+        "master" => open_master_overview(app),
+        "license" => open_license_overview(app),
+        "deprecations" => open_deprecations(app),
+        "upgrade" => open_upgrade_report(app),
+        "reroute" => run_reroute_command(app, &args),
+        "share" => open_share(app),
+        "note" => run_note_command(app, &args),
+        "scope" => open_scope_preview(app, &args),
+        "preference" | "routing" => set_search_param(app, name, &args),
+        "pit" => run_pit_command(app, &args),
+        "sample" => run_sample_command(app, &args),
+        "qs" => run_query_options_command(app, &args),
+        "fuzzy" => set_fuzzy(app, &args),
+        "cluster" => run_cluster_command(app, &args),
+        "agg" => run_agg_command(app, &args),
+        "latency" => open_latency_view(app, &args),
+        "events" => {
+            open_event_timeline(app);
+            Ok(())
+        }
+        "health" => open_health_detail(app),
+        "alias" => run_alias_command(app, &args),
+        "columns" => run_columns_command(app, &args),
+        "sort" => run_sort_command(app, &args),
+        "filter" => run_filter_command(app, &args),
+        "view" => run_view_command(app, &args),
+        "settings" => open_index_settings(app),
+        "limits" => run_limits_command(app, &args),
+        "dedup" => open_dedup_view(app, &args),
+        "patterns" => open_log_patterns(app, &args),
+        "trace" => open_trace_follow(app),
+        "mget" => open_mget_lookup(app, &args),
+        "snapshot" => run_snapshot_command(app, &args),
+        "mask" => {
+            app.unmasked = false;
+            Ok(())
+        }
+        "unmask" => {
+            app.unmasked = true;
+            Ok(())
+        }
+        _ => Err(anyhow::anyhow!("unknown command {name:?}")),
+    };
+    if let Err(err) = result {
+        app.last_error = Some(format!("{name}: {err:#}"));
+    }
+}
+
+pub(crate) fn doc_permalinks(es_url: &str, doc: &DocEntry) -> Result<(String, String)> {
+    let url = endpoint_url(es_url, &[&doc.index, "_doc", &doc.id])?;
+    let curl = format!("curl -s {}", shell_quote(&redact_url(&url)));
+    let deep_link = format!(
+        "index-lens --open {}",
+        shell_quote(&format!("{}:{}", doc.index, doc.id))
+    );
+    Ok((curl, deep_link))
+}
+
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+pub(crate) fn copy_permalink(app: &mut App) -> Result<()> {
+    let doc = app.selected_doc().context("no document selected")?;
+    let (curl, deep_link) = doc_permalinks(&app.es_url, doc)?;
+    let cluster = app
+        .health
+        .as_ref()
+        .map(|health| health.cluster_name.clone())
+        .unwrap_or_else(|| "-".to_string());
+    copy_to_clipboard(&format!("{curl}\n{deep_link}\n"))?;
+    let label_style = Style::default().fg(Color::Gray);
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("cluster: ", label_style),
+            Span::raw(cluster),
+        ]),
+        Line::from(""),
+        Line::from(curl),
+        Line::from(deep_link),
+        Line::from(""),
+        Line::from(Span::styled(
+            "copied to the clipboard (terminals without OSC 52 support ignore this)",
+            label_style,
+        )),
+    ];
+    app.info_panel = Some(InfoPanel::new("Permalink", lines));
+    Ok(())
+}
+
+pub(crate) fn open_share(app: &mut App) -> Result<()> {
+    let outcome = share_query(app)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let (label, target) = match outcome.target {
+        ShareTarget::Link(link) => ("link: ", link),
+        ShareTarget::File(path) => ("file: ", path.display().to_string()),
+    };
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(label, label_style),
+            Span::styled(target, Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("query: ", label_style),
+            Span::raw(if app.query.is_empty() {
+                "-".to_string()
+            } else {
+                app.query.clone()
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled("sample: ", label_style),
+            Span::raw(format!("{} document(s)", outcome.sampled)),
+        ]),
+        Line::from(vec![
+            Span::styled("redacted: ", label_style),
+            Span::styled(
+                format!("{} field(s)", outcome.redacted),
+                Style::default().fg(if outcome.redacted > 0 {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                }),
+            ),
+        ]),
+    ];
+    app.info_panel = Some(InfoPanel::new("Share", lines));
+    Ok(())
+}
+
+pub(crate) fn run_validate_query(app: &mut App) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let validation = fetch_validate_query(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &app.query,
+        &app.search_params(),
+    )?;
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("query: ", label_style),
+            Span::raw(if app.query.is_empty() {
+                "-".to_string()
+            } else {
+                app.query.clone()
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled("valid: ", label_style),
+            Span::styled(
+                validation.valid.to_string(),
+                if validation.valid {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                },
+            ),
+        ]),
+    ];
+    let warnings = match app.query_mode {
+        QueryMode::QueryString => query_cost_warnings(&app.query, app.docs_from),
+        QueryMode::Dsl => Vec::new(),
+    };
+    if !warnings.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("warnings: ", label_style),
+            Span::styled(warnings.join(", "), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+    if let Some(error) = &validation.error {
+        lines.push(Line::from(vec![
+            Span::styled("error: ", label_style),
+            Span::styled(error.clone(), Style::default().fg(Color::Red)),
+        ]));
+    }
+    for explanation in &validation.explanations {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            explanation.index.clone().unwrap_or_else(|| "-".to_string()),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        let text = explanation
+            .explanation
+            .as_deref()
+            .or(explanation.error.as_deref())
+            .unwrap_or("-");
+        lines.push(Line::from(format!("  {text}")));
+    }
+    app.info_panel = Some(InfoPanel::new("Validate (rewrite)", lines));
+    Ok(())
+}
+
+pub(crate) fn open_query_translation(app: &mut App) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let params = app.search_params();
+    let dsl = query_body(&app.query, &params);
+    let validation = fetch_validate_query(&app.client, &app.es_url, &scope, &app.query, &params)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("query_string: ", label_style),
+            Span::raw(if app.query.is_empty() {
+                "-".to_string()
+            } else {
+                app.query.clone()
+            }),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "DSL",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    let pretty = serde_json::to_string_pretty(&dsl).unwrap_or_else(|_| dsl.to_string());
+    lines.extend(pretty.lines().map(|line| Line::from(format!("  {line}"))));
+    if let Some(error) = &validation.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("error: ", label_style),
+            Span::styled(error.clone(), Style::default().fg(Color::Red)),
+        ]));
+    }
+    let mut fields: Vec<String> = Vec::new();
+    for explanation in &validation.explanations {
+        let Some(text) = explanation.explanation.as_deref() else {
+            continue;
+        };
+        for field in lucene_fields(text) {
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+    }
+    if !fields.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(format!("fields searched ({}): ", fields.len()), label_style),
+            Span::styled(fields.join(", "), Style::default().fg(Color::Cyan)),
+        ]));
+    }
+    for explanation in &validation.explanations {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Lucene @ {}", explanation.index.as_deref().unwrap_or("-")),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        let text = explanation
+            .explanation
+            .as_deref()
+            .or(explanation.error.as_deref())
+            .unwrap_or("-");
+        lines.push(Line::from(format!("  {text}")));
+    }
+    app.info_panel = Some(InfoPanel::new("Query translation", lines));
+    Ok(())
+}
+
+pub(crate) fn lucene_fields(explanation: &str) -> Vec<String> {
+    let pattern = Regex::new(r"(?:^|[\s(+\-])([A-Za-z_@][\w.@\-]*):").expect("valid regex");
+    let mut fields: Vec<String> = Vec::new();
+    for capture in pattern.captures_iter(explanation) {
+        let field = capture[1].to_string();
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+    fields
+}
+
+pub(crate) fn set_json_path(app: &mut App, args: &[&str]) -> Result<()> {
+    let (column, rest) = match args.split_first() {
+        Some((&"-c", rest)) => (true, rest),
+        _ => (false, args),
+    };
+    let expr = rest.join(" ");
+    if expr.is_empty() {
+        app.json_path = None;
+        return Ok(());
+    }
+    parse_json_path(&expr)?;
+    app.json_path = Some(JsonPathView { expr, column });
+    if !column {
+        app.show_doc_drawer = true;
+        app.focus = Focus::Results;
+    }
+    Ok(())
+}
+
+pub(crate) fn run_note_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let note = match args {
+        [] => anyhow::bail!("usage: note <text> | note clear"),
+        ["clear"] => None,
+        words => Some(words.join(" ")),
+    };
+    set_note(app, &scope, note)?;
+    open_scope_details(app)
+}
+
+pub(crate) fn set_search_param(app: &mut App, name: &str, args: &[&str]) -> Result<()> {
+    let value = match args {
+        [] => anyhow::bail!("usage: {name} <value> | {name} clear"),
+        ["clear"] => None,
+        [value] => Some(value.to_string()),
+        _ => anyhow::bail!("{name} must be a single value"),
+    };
+    match name {
+        "preference" => app.preference = value,
+        _ => app.routing = value,
+    }
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    Ok(())
+}
+
+pub(crate) fn run_pit_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] | ["open"] => open_pit_session(app, "5m"),
+        ["open", keep_alive] => open_pit_session(app, keep_alive),
+        ["close"] => {
+            close_pit_session(app)?;
+            app.reset_docs_paging();
+            handle_docs_refresh(app);
+            Ok(())
+        }
+        _ => anyhow::bail!("usage: pit [open [keep_alive]] | pit close"),
+    }
+}
+
+pub(crate) fn run_limits_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] => {}
+        ["clear"] => {
+            app.search_timeout = None;
+            app.terminate_after = None;
+        }
+        ["timeout", "clear"] => app.search_timeout = None,
+        ["timeout", value] => {
+            parse_duration_millis(value)
+                .filter(|millis| *millis > 0)
+                .with_context(|| format!("invalid timeout {value:?}, expected e.g. 2s"))?;
+            app.search_timeout = Some(value.to_string());
+        }
+        ["terminate_after", "clear"] => app.terminate_after = None,
+        ["terminate_after", value] => {
+            let limit: u64 = value
+                .parse()
+                .ok()
+                .filter(|limit| *limit > 0)
+                .with_context(|| format!("invalid terminate_after {value:?}"))?;
+            app.terminate_after = Some(limit);
+        }
+        _ => anyhow::bail!(
+            "usage: limits [timeout <duration|clear> | terminate_after <docs|clear> | clear]"
+        ),
+    }
+    if !args.is_empty() {
+        app.reset_docs_paging();
+        handle_docs_refresh(app);
+    }
+    let label_style = Style::default().fg(Color::Gray);
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("timeout: ", label_style),
+            Span::raw(
+                app.search_timeout
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("terminate_after: ", label_style),
+            Span::raw(
+                app.terminate_after
+                    .map_or_else(|| "-".to_string(), |limit| limit.to_string()),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "results cut short by either limit are marked partial",
+            label_style,
+        )),
+        Line::from(Span::styled(
+            ":limits timeout <duration|clear> | terminate_after <docs|clear> | clear",
+            label_style,
+        )),
+    ];
+    app.info_panel = Some(InfoPanel::new("Search limits", lines));
+    Ok(())
+}
+
+pub(crate) fn run_sample_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let seed = now_millis().unsigned_abs();
+    app.sample_seed = match args {
+        [] => app.sample_seed.xor(Some(seed)),
+        ["on"] | ["reseed"] => Some(seed),
+        ["off"] => None,
+        [value] => Some(
+            value
+                .parse()
+                .with_context(|| format!("invalid seed {value:?}"))?,
+        ),
+        _ => anyhow::bail!("usage: sample [on|off|reseed|<seed>]"),
+    };
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    Ok(())
+}
+
+pub(crate) fn set_fuzzy(app: &mut App, args: &[&str]) -> Result<()> {
+    app.fuzzy = match args {
+        [] => !app.fuzzy,
+        ["on"] => true,
+        ["off"] => false,
+        _ => anyhow::bail!("usage: fuzzy [on|off]"),
+    };
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    Ok(())
+}
+
+pub(crate) fn run_query_options_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let mut options = app.query_options();
+    match args {
+        [] => {}
+        ["reset"] => options = QueryOptions::default(),
+        ["default_field", "clear"] => options.default_field = None,
+        ["default_field", field] => options.default_field = Some(field.to_string()),
+        ["fields", "clear"] => options.fields.clear(),
+        ["fields", fields @ ..] if !fields.is_empty() => {
+            options.fields = fields
+                .join(",")
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        ["lenient", "on"] => options.lenient = true,
+        ["lenient", "off"] => options.lenient = false,
+        ["operator", "and" | "AND"] => options.operator = Some(QueryOperator::And),
+        ["operator", "or" | "OR"] => options.operator = Some(QueryOperator::Or),
+        ["operator", "clear"] => options.operator = None,
+        ["msm", "clear"] => options.minimum_should_match = None,
+        ["msm", value] => options.minimum_should_match = Some(value.to_string()),
+        _ => anyhow::bail!(
+            "usage: qs [default_field <field|clear> | fields <f1,f2|clear> | lenient on|off | operator and|or|clear | msm <value|clear> | reset]"
+        ),
+    }
+    if !args.is_empty() {
+        store_query_options(app, &scope, &options)?;
+    }
+    app.info_panel = Some(InfoPanel::new(
+        format!("query_string options: {scope}"),
+        query_options_lines(&options),
+    ));
+    Ok(())
+}
+
+fn store_query_options(app: &mut App, scope: &str, options: &QueryOptions) -> Result<()> {
+    let key = app.note_key(scope);
+    if options.is_default() {
+        app.state.query_options.remove(&key);
+    } else {
+        app.state.query_options.insert(key, options.clone());
+    }
+    save_state(&app.state)?;
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    Ok(())
+}
+
+pub(crate) fn toggle_query_operator(app: &mut App) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let mut options = app.query_options();
+    options.operator = Some(options.operator.unwrap_or_default().toggled());
+    store_query_options(app, &scope, &options)
+}
+
+fn query_options_lines(options: &QueryOptions) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let fields = if options.fields.is_empty() {
+        "-".to_string()
+    } else {
+        options.fields.join(", ")
+    };
+    vec![
+        Line::from(vec![
+            Span::styled("default_field: ", label_style),
+            Span::raw(
+                options
+                    .default_field
+                    .clone()
+                    .unwrap_or_else(|| "index.query.default_field".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("fields: ", label_style),
+            Span::raw(fields),
+        ]),
+        Line::from(vec![
+            Span::styled("lenient: ", label_style),
+            Span::raw(options.lenient.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("default_operator: ", label_style),
+            Span::raw(options.operator.unwrap_or_default().as_str()),
+        ]),
+        Line::from(vec![
+            Span::styled("minimum_should_match: ", label_style),
+            Span::raw(
+                options
+                    .minimum_should_match
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "fields takes precedence over default_field; saved per scope",
+            label_style,
+        )),
+        Line::from(Span::styled(
+            ":qs default_field <f|clear> | fields <f1,f2|clear> | lenient on|off | reset",
+            label_style,
+        )),
+        Line::from(Span::styled(
+            ":qs operator and|or|clear | msm <value|clear>  (O toggles AND/OR)",
+            label_style,
+        )),
+    ]
+}
+
+const SNAPSHOT_DOCS: u64 = 50;
+const SNAPSHOT_DIFF_LIMIT: usize = 20;
+
+pub(crate) fn run_snapshot_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] | ["list"] => {
+            app.info_panel = Some(InfoPanel::new("Snapshots", snapshot_list_lines(app)));
+            Ok(())
+        }
+        ["drop", name] => {
+            app.state
+                .snapshots
+                .remove(*name)
+                .with_context(|| format!("no snapshot named {name:?}"))?;
+            save_state(&app.state)
+        }
+        ["diff", name] => open_snapshot_diff(app, name),
+        ["save", name] | [name] => take_snapshot(app, name),
+        _ => anyhow::bail!("usage: snapshot [save] <name> | diff <name> | drop <name> | list"),
+    }
+}
+
+fn snapshot_target(app: &App, name: &str) -> Result<(String, String)> {
+    if let Some(snapshot) = app.state.snapshots.get(name) {
+        return Ok((snapshot.scope.clone(), snapshot.query.clone()));
+    }
+    if let Some(view) = app.state.saved_views.iter().find(|view| view.name == name) {
+        return Ok((view.scope.clone(), view.query.clone()));
+    }
+    let scope = app.selected_scope_name().context("no scope selected")?;
+    Ok((scope.to_string(), app.query.clone()))
+}
+
+fn run_snapshot(app: &App, scope: &str, query: &str) -> Result<ResultSnapshot> {
+    let params = SearchParams {
+        pit: None,
+        ..app.search_params()
+    };
+    let (docs, summary) = fetch_documents(
+        &app.client,
+        &app.es_url,
+        scope,
+        &DocsPage::From(0),
+        SNAPSHOT_DOCS,
+        query,
+        &params,
+    )?;
+    Ok(ResultSnapshot {
+        scope: scope.to_string(),
+        query: query.to_string(),
+        taken_millis: now_millis(),
+        total: summary.total,
+        docs: docs
+            .iter()
+            .map(|doc| SnapshotDoc {
+                index: doc.index.clone(),
+                id: doc.id.clone(),
+                version: doc.version,
+                digest: source_digest(&doc.source),
+            })
+            .collect(),
+    })
+}
+
+fn take_snapshot(app: &mut App, name: &str) -> Result<()> {
+    let (scope, query) = match app.state.saved_views.iter().find(|view| view.name == name) {
+        Some(view) => (view.scope.clone(), view.query.clone()),
+        None => {
+            let scope = app.selected_scope_name().context("no scope selected")?;
+            (scope.to_string(), app.query.clone())
+        }
+    };
+    let snapshot = run_snapshot(app, &scope, &query)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let lines = vec![
+        Line::from(vec![Span::styled("scope: ", label_style), Span::raw(scope)]),
+        Line::from(vec![
+            Span::styled("query: ", label_style),
+            Span::raw(display_query(&query)),
+        ]),
+        Line::from(vec![
+            Span::styled("hits: ", label_style),
+            Span::raw(display_total(snapshot.total)),
+        ]),
+        Line::from(vec![
+            Span::styled("stored: ", label_style),
+            Span::raw(format!("{} docs", snapshot.docs.len())),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("compare later with :snapshot diff {name}"),
+            label_style,
+        )),
+    ];
+    app.state.snapshots.insert(name.to_string(), snapshot);
+    save_state(&app.state)?;
+    app.info_panel = Some(InfoPanel::new(format!("Snapshot {name}"), lines));
+    Ok(())
+}
+
+fn open_snapshot_diff(app: &mut App, name: &str) -> Result<()> {
+    let before = app
+        .state
+        .snapshots
+        .get(name)
+        .cloned()
+        .with_context(|| format!("no snapshot named {name:?}"))?;
+    let (scope, query) = snapshot_target(app, name)?;
+    let after = run_snapshot(app, &scope, &query)?;
+    app.info_panel = Some(InfoPanel::new(
+        format!("Snapshot {name} vs now"),
+        snapshot_diff_lines(&before, &after, &app.time_zone),
+    ));
+    Ok(())
+}
+
+fn display_query(query: &str) -> String {
+    if query.trim().is_empty() {
+        "*".to_string()
+    } else {
+        query.to_string()
+    }
+}
+
+fn display_total(total: Option<u64>) -> String {
+    total.map_or_else(|| "?".to_string(), |total| total.to_string())
+}
+
+pub(crate) fn snapshot_diff_lines(
+    before: &ResultSnapshot,
+    after: &ResultSnapshot,
+    time_zone: &TimeZone,
+) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let diff = before.diff(after);
+    let delta = match (before.total, after.total) {
+        (Some(before), Some(after)) => format!(" ({:+})", after as i64 - before as i64),
+        _ => String::new(),
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("scope: ", label_style),
+            Span::raw(before.scope.clone()),
+            Span::styled("  query: ", label_style),
+            Span::raw(display_query(&before.query)),
+        ]),
+        Line::from(vec![
+            Span::styled("taken: ", label_style),
+            Span::raw(time_zone.format(before.taken_millis)),
+        ]),
+        Line::from(vec![
+            Span::styled("hits: ", label_style),
+            Span::raw(format!(
+                "{} -> {}{delta}",
+                display_total(before.total),
+                display_total(after.total)
+            )),
+        ]),
+        Line::from(""),
+    ];
+    if diff.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("first {} docs unchanged", after.docs.len()),
+            Style::default().fg(Color::Green),
+        )));
+        return lines;
+    }
+    for (title, ids, color) in [
+        ("added", &diff.added, Color::Green),
+        ("removed", &diff.removed, Color::Red),
+        ("changed", &diff.changed, Color::Yellow),
+    ] {
+        if ids.is_empty() {
+            continue;
+        }
+        lines.push(Line::from(Span::styled(
+            format!("{title} ({})", ids.len()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        )));
+        for id in ids.iter().take(SNAPSHOT_DIFF_LIMIT) {
+            lines.push(Line::from(format!("  {id}")));
+        }
+        if ids.len() > SNAPSHOT_DIFF_LIMIT {
+            lines.push(Line::from(Span::styled(
+                format!("  ... {} more", ids.len() - SNAPSHOT_DIFF_LIMIT),
+                label_style,
+            )));
+        }
+    }
+    lines
+}
+
+fn snapshot_list_lines(app: &App) -> Vec<Line<'static>> {
+    if app.state.snapshots.is_empty() {
+        return vec![Line::from("no snapshots; take one with :snapshot <name>")];
+    }
+    let label_style = Style::default().fg(Color::Gray);
+    app.state
+        .snapshots
+        .iter()
+        .map(|(name, snapshot)| {
+            Line::from(vec![
+                Span::styled(name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    format!("  {}  ", app.time_zone.format(snapshot.taken_millis)),
+                    label_style,
+                ),
+                Span::raw(format!(
+                    "{} {} ({} hits)",
+                    snapshot.scope,
+                    display_query(&snapshot.query),
+                    display_total(snapshot.total)
+                )),
+            ])
+        })
+        .collect()
+}
+
+const DEDUP_DEFAULT_FIELDS: [&str; 1] = ["message"];
+const DEDUP_VALUE_WIDTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DedupGroup {
+    pub(crate) values: Vec<String>,
+    pub(crate) count: usize,
+}
+
+pub(crate) fn dedup_groups<'a>(
+    sources: impl IntoIterator<Item = &'a Value>,
+    fields: &[String],
+) -> Vec<DedupGroup> {
+    let mut groups: Vec<DedupGroup> = Vec::new();
+    for source in sources {
+        let values: Vec<String> = fields
+            .iter()
+            .map(|field| ecs_text(source, field).unwrap_or_else(|| "-".to_string()))
+            .collect();
+        match groups.iter_mut().find(|group| group.values == values) {
+            Some(group) => group.count += 1,
+            None => groups.push(DedupGroup { values, count: 1 }),
+        }
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.count));
+    groups
+}
+
+pub(crate) fn open_dedup_view(app: &mut App, args: &[&str]) -> Result<()> {
+    let fields: Vec<String> = if args.is_empty() {
+        DEDUP_DEFAULT_FIELDS
+            .iter()
+            .map(|field| field.to_string())
+            .collect()
+    } else {
+        args.join(",")
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let docs = app.visible_docs();
+    if docs.is_empty() {
+        anyhow::bail!("no loaded documents");
+    }
+    let sources: Vec<Value> = docs
+        .iter()
+        .map(|doc| app.display_source(doc).into_owned())
+        .collect();
+    let groups = dedup_groups(&sources, &fields);
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("fingerprint: ", label_style),
+            Span::raw(fields.join(" + ")),
+        ]),
+        Line::from(vec![
+            Span::styled("unique: ", label_style),
+            Span::raw(format!("{} of {} loaded docs", groups.len(), docs.len())),
+        ]),
+        Line::from(""),
+    ];
+    for group in &groups {
+        let text = group.values.join(" | ");
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:>5}x ", group.count),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(truncate_string(&text, DEDUP_VALUE_WIDTH)),
+        ]));
+    }
+    app.info_panel = Some(InfoPanel::new("Dedup", lines));
+    Ok(())
+}
+
+const PATTERN_DEFAULT_FIELD: &str = "message";
+const PATTERN_LIMIT: usize = 50;
+
+pub(crate) fn open_log_patterns(app: &mut App, args: &[&str]) -> Result<()> {
+    let field = match args {
+        [] => PATTERN_DEFAULT_FIELD,
+        [field] => field,
+        _ => anyhow::bail!("usage: patterns [field]"),
+    };
+    let messages: Vec<String> = app
+        .visible_docs()
+        .iter()
+        .filter_map(|doc| ecs_text(&app.display_source(doc), field))
+        .collect();
+    if messages.is_empty() {
+        anyhow::bail!("no loaded documents have {field}");
+    }
+    let patterns = cluster_messages(messages.iter().map(String::as_str));
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("field: ", label_style),
+            Span::raw(field.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("patterns: ", label_style),
+            Span::raw(format!(
+                "{} from {} messages",
+                patterns.len(),
+                messages.len()
+            )),
+        ]),
+        Line::from(""),
+    ];
+    for pattern in patterns.iter().take(PATTERN_LIMIT) {
+        let mut spans = vec![Span::styled(
+            format!("{:>5}x ", pattern.count),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )];
+        for (index, token) in pattern.tokens.iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::raw(" "));
+            }
+            spans.push(if token == WILDCARD {
+                Span::styled(token.clone(), Style::default().fg(Color::Yellow))
+            } else {
+                Span::raw(token.clone())
+            });
+        }
+        lines.push(Line::from(spans));
+    }
+    if patterns.len() > PATTERN_LIMIT {
+        lines.push(Line::from(Span::styled(
+            format!("{} more patterns", patterns.len() - PATTERN_LIMIT),
+            label_style,
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Log patterns", lines));
+    Ok(())
+}
+
+pub(crate) fn open_mget_lookup(app: &mut App, args: &[&str]) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let ids = match args {
+        [] => anyhow::bail!("usage: mget <id> [id ...] | mget @<file>"),
+        [path] if path.starts_with('@') => {
+            let path = &path[1..];
+            let text =
+                std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+            parse_id_list(&text)
+        }
+        words => parse_id_list(&words.join(" ")),
+    };
+    if ids.is_empty() {
+        anyhow::bail!("no ids given");
+    }
+    let results = fetch_mget(&app.client, &app.es_url, &scope, &ids)?;
+    app.info_panel = Some(InfoPanel::new(
+        format!("Lookup in {scope}"),
+        mget_lines(&results),
+    ));
+    Ok(())
+}
+
+pub(crate) fn parse_id_list(text: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in text.split(|ch: char| ch == ',' || ch.is_whitespace()) {
+        let id = id.trim_matches(['"', '\'']);
+        if !id.is_empty() && !ids.iter().any(|seen| seen == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+pub(crate) fn mget_lines(results: &[(String, MgetResult)]) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let found = results
+        .iter()
+        .filter(|(_, result)| matches!(result, MgetResult::Found(_)))
+        .count();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{found} of {} found", results.len()),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for (id, result) in results {
+        let (status, style, detail) = match result {
+            MgetResult::Found(doc) => (
+                "found  ",
+                Style::default().fg(Color::Green),
+                match doc.version {
+                    Some(version) => format!("{} v{version}", doc.index),
+                    None => doc.index.clone(),
+                },
+            ),
+            MgetResult::Missing => ("missing", Style::default().fg(Color::Yellow), String::new()),
+            MgetResult::Failed(reason) => {
+                ("error  ", Style::default().fg(Color::Red), reason.clone())
+            }
+        };
+        lines.push(Line::from(vec![
+            Span::styled(status, style),
+            Span::raw(format!(" {id} ")),
+            Span::styled(detail, label_style),
+        ]));
+    }
+    lines
+}
+
+pub(crate) fn open_trace_follow(app: &mut App) -> Result<()> {
+    let doc = app.selected_doc().context("no document selected")?;
+    let trace_id = ecs_text(&doc.source, "trace.id").context("document has no trace.id")?;
+    let origin = (doc.index.clone(), doc.id.clone());
+    let indices = app.config.trace.indices.join(",");
+    let docs = fetch_trace_documents(
+        &app.client,
+        &app.es_url,
+        &indices,
+        &trace_id,
+        app.config.trace.size,
+    )?;
+    app.info_panel = Some(InfoPanel::new(
+        format!("Trace {trace_id}"),
+        trace_lines(app, &docs, &origin),
+    ));
+    Ok(())
+}
+
+pub(crate) fn trace_lines(
+    app: &App,
+    docs: &[DocEntry],
+    origin: &(String, String),
+) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let indices: HashSet<&str> = docs.iter().map(|doc| doc.index.as_str()).collect();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!(
+                "{} correlated document(s) across {} index(es), oldest first",
+                docs.len(),
+                indices.len()
+            ),
+            label_style,
+        )),
+        Line::from(""),
+    ];
+    for doc in docs {
+        let source = app.display_source(doc);
+        let time = ecs_timestamp_millis(&source)
+            .map(|millis| app.time_zone.format(millis))
+            .unwrap_or_else(|| "-".to_string());
+        let service = ecs_text(&source, "service.name").unwrap_or_else(|| "-".to_string());
+        let span = ecs_text(&source, "span.id")
+            .map(|id| format!("span {id}"))
+            .unwrap_or_default();
+        let summary = ["span.name", "transaction.name", "message"]
+            .iter()
+            .find_map(|field| ecs_text(&source, field))
+            .unwrap_or_default();
+        let marker = if (&doc.index, &doc.id) == (&origin.0, &origin.1) {
+            "> "
+        } else {
+            "  "
+        };
+        let level = ecs_text(&source, "log.level").unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{time} "), label_style),
+            Span::styled(
+                format!("{service} "),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("{level} "), log_level_style(&level)),
+            Span::raw(truncate_string(
+                summary.lines().next().unwrap_or_default(),
+                120,
+            )),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("    {} {} {span}", doc.index, doc.id),
+            label_style,
+        )));
+    }
+    lines
+}
+
+pub(crate) fn open_scope_details(app: &mut App) -> Result<()> {
+    match app.scope_kind {
+        ScopeKind::Indices => open_index_details(app),
+        ScopeKind::Aliases => open_alias_details(app),
+        ScopeKind::DataStreams => open_lifecycle_details(app),
+    }
+}
+
+pub(crate) fn note_lines(app: &App, scope: &str) -> Vec<Line<'static>> {
+    let Some(note) = app.note_for(scope) else {
+        return Vec::new();
+    };
+    let mut lines: Vec<Line<'static>> = note
+        .lines()
+        .map(|line| {
+            Line::from(vec![
+                Span::styled("note: ", Style::default().fg(Color::Cyan)),
+                Span::raw(line.to_string()),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines
+}
+
+pub(crate) fn open_index_details(app: &mut App) -> Result<()> {
+    let name = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no index selected")?;
+    let entry = app
+        .indices
+        .iter()
+        .find(|entry| entry.name == name)
+        .context("index not found")?;
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = note_lines(app, &name);
+    let fields = [
+        ("health", entry.health.clone()),
+        (
+            "docs",
+            entry.docs_count.clone().unwrap_or_else(|| "-".to_string()),
+        ),
+        (
+            "size",
+            entry.store_size.clone().unwrap_or_else(|| "-".to_string()),
+        ),
+    ];
+    for (label, value) in fields {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{label}: "), label_style),
+            Span::raw(value),
+        ]));
+    }
+    for (key, value) in app.index_tags.get(&name).into_iter().flatten() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{key}: "), label_style),
+            Span::styled(value.clone(), Style::default().fg(Color::Magenta)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "annotate with :note <text>, remove with :note clear",
+        label_style,
+    )));
+    app.info_panel = Some(InfoPanel::new(format!("Index {name}"), lines));
+    Ok(())
+}
+
+pub(crate) fn open_alias_details(app: &mut App) -> Result<()> {
+    let alias = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no alias selected")?;
+    let details = fetch_alias_details(&app.client, &app.es_url, &alias)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = note_lines(app, &alias);
+    for (index, detail) in &details {
+        let mut header = vec![Span::styled(
+            index.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )];
+        if detail.is_write_index == Some(true) {
+            header.push(Span::styled(
+                "  write index",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        lines.push(Line::from(header));
+        lines.push(Line::from(vec![
+            Span::styled("  index_routing: ", label_style),
+            Span::raw(
+                detail
+                    .index_routing
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("  search_routing: ", label_style),
+            Span::raw(
+                detail
+                    .search_routing
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]));
+        match &detail.filter {
+            Some(filter) => {
+                lines.push(Line::from(Span::styled(
+                    "  filter:",
+                    Style::default().fg(Color::Magenta),
+                )));
+                for line in json_lines_pretty(filter) {
+                    lines.push(Line::from(format!("    {line}")));
+                }
+            }
+            None => lines.push(Line::from(vec![
+                Span::styled("  filter: ", label_style),
+                Span::raw("-"),
+            ])),
+        }
+        lines.push(Line::from(""));
+    }
+    if details.is_empty() {
+        lines.push(Line::from("No targets"));
+    }
+    app.info_panel = Some(InfoPanel::new(format!("Alias {alias}"), lines));
+    Ok(())
+}
+
+pub(crate) fn open_lifecycle_details(app: &mut App) -> Result<()> {
+    let name = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no data stream selected")?;
+    let lifecycles = fetch_lifecycle(&app.client, &app.es_url, &name)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = note_lines(app, &name);
+    for entry in &lifecycles {
+        lines.push(Line::from(Span::styled(
+            entry.name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        let Some(lifecycle) = &entry.lifecycle else {
+            lines.push(Line::from("  no data stream lifecycle (ILM or unmanaged)"));
+            continue;
+        };
+        let field = |key: &str| {
+            lifecycle
+                .get(key)
+                .map(json_value_compact)
+                .unwrap_or_else(|| "-".to_string())
+        };
+        for (label, key) in [
+            ("enabled", "enabled"),
+            ("data_retention", "data_retention"),
+            ("effective_retention", "effective_retention"),
+            ("retention_source", "retention_determined_by"),
+        ] {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {label}: "), label_style),
+                Span::raw(field(key)),
+            ]));
+        }
+        match lifecycle.get("rollover").and_then(Value::as_object) {
+            Some(rollover) => {
+                let conditions: Vec<String> = rollover
+                    .iter()
+                    .map(|(key, value)| format!("{key}={}", json_value_compact(value)))
+                    .collect();
+                lines.push(Line::from(vec![
+                    Span::styled("  rollover: ", label_style),
+                    Span::raw(conditions.join(", ")),
+                ]));
+            }
+            None => lines.push(Line::from(vec![
+                Span::styled("  rollover: ", label_style),
+                Span::raw("-"),
+            ])),
+        }
+        match lifecycle.get("downsampling").and_then(Value::as_array) {
+            Some(rounds) if !rounds.is_empty() => {
+                lines.push(Line::from(Span::styled("  downsampling:", label_style)));
+                for round in rounds {
+                    let after = round.get("after").map(json_value_compact);
+                    let interval = round.get("fixed_interval").map(json_value_compact);
+                    lines.push(Line::from(format!(
+                        "    after {} -> {}",
+                        after.unwrap_or_else(|| "-".to_string()),
+                        interval.unwrap_or_else(|| "-".to_string())
+                    )));
+                }
+            }
+            _ => lines.push(Line::from(vec![
+                Span::styled("  downsampling: ", label_style),
+                Span::raw("-"),
+            ])),
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "edit retention with :retention <duration> (e.g. 30d)",
+        label_style,
+    )));
+    app.info_panel = Some(InfoPanel::new(format!("Lifecycle {name}"), lines));
+    Ok(())
+}
+
+pub(crate) fn update_retention(app: &mut App, args: &[&str]) -> Result<()> {
+    if app.scope_kind != ScopeKind::DataStreams {
+        anyhow::bail!("select a data stream first");
+    }
+    let name = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no data stream selected")?;
+    let [retention] = args else {
+        anyhow::bail!("usage: retention <duration>");
+    };
+    let valid = Regex::new(r"^\d+(d|h|m|s|ms|micros|nanos)$").expect("valid duration regex");
+    if !valid.is_match(retention) {
+        anyhow::bail!("invalid duration {retention:?}, expected e.g. 30d or 12h");
+    }
+    put_lifecycle_retention(&app.client, &app.es_url, &name, retention)?;
+    open_lifecycle_details(app)
+}
+
+pub(crate) fn run_slm_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] => open_slm_overview(app, None),
+        ["run", policy] => {
+            let snapshot = execute_slm_policy(&app.client, &app.es_url, policy)?;
+            open_slm_overview(
+                app,
+                Some(format!("started {snapshot} from policy {policy}")),
+            )
+        }
+        _ => anyhow::bail!("usage: slm [run <policy>]"),
+    }
+}
+
+pub(crate) fn open_slm_overview(app: &mut App, notice: Option<String>) -> Result<()> {
+    let tz = app.time_zone;
+    let policies = fetch_slm_policies(&app.client, &app.es_url)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = Vec::new();
+    if let Some(notice) = notice {
+        lines.push(Line::from(Span::styled(
+            notice,
+            Style::default().fg(Color::Green),
+        )));
+        lines.push(Line::from(""));
+    }
+    let invocation = |entry: &Option<SlmInvocation>| match entry {
+        Some(entry) => format!(
+            "{} at {}",
+            entry.snapshot_name.as_deref().unwrap_or("-"),
+            entry
+                .time
+                .map_or("-".to_string(), |millis| tz.format(millis))
+        ),
+        None => "-".to_string(),
+    };
+    for (id, entry) in &policies {
+        let mut title = vec![Span::styled(
+            id.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )];
+        if entry.in_progress.is_some() {
+            title.push(Span::styled(" running", Style::default().fg(Color::Yellow)));
+        }
+        lines.push(Line::from(title));
+        let fields = [
+            (
+                "schedule",
+                entry
+                    .policy
+                    .schedule
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            (
+                "repository",
+                entry
+                    .policy
+                    .repository
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            (
+                "snapshot name",
+                entry.policy.name.clone().unwrap_or_else(|| "-".to_string()),
+            ),
+            ("last success", invocation(&entry.last_success)),
+            (
+                "next run",
+                entry
+                    .next_execution_millis
+                    .map_or("-".to_string(), |millis| tz.format(millis)),
+            ),
+        ];
+        for (label, value) in fields {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {label}: "), label_style),
+                Span::raw(value),
+            ]));
+        }
+        let failed_last = match (&entry.last_failure, &entry.last_success) {
+            (Some(failure), Some(success)) => failure.time > success.time,
+            (Some(_), None) => true,
+            _ => false,
+        };
+        let failure_style = if failed_last {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  last failure: ", label_style),
+            Span::styled(invocation(&entry.last_failure), failure_style),
+        ]));
+        if failed_last
+            && let Some(details) = entry.last_failure.as_ref().and_then(|f| f.details.as_ref())
+        {
+            lines.push(Line::from(Span::styled(
+                format!("    {details}"),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+    if policies.is_empty() {
+        lines.push(Line::from("No SLM policies"));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "run a policy now with :slm run <policy>",
+            label_style,
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Snapshot lifecycle policies", lines));
+    Ok(())
+}
+
+pub(crate) fn run_drain_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let (action, node) = match args {
+        [] => return open_drain_overview(app),
+        [action @ ("add" | "remove"), node] => (*action, *node),
+        _ => anyhow::bail!("usage: drain [add|remove <node>]"),
+    };
+    let excluded = fetch_excluded_nodes(&app.client, &app.es_url)?;
+    let nodes = if action == "add" && excluded.transient.is_empty() {
+        fetch_node_names(&app.client, &app.es_url)?
+    } else {
+        Vec::new()
+    };
+    let excluded = drain_exclusion(&excluded, action, node, &nodes)?;
+    put_excluded_nodes(&app.client, &app.es_url, &excluded)?;
+    open_drain_overview(app)
+}
+
+fn drain_exclusion(
+    excluded: &ExcludedNodes,
+    action: &str,
+    node: &str,
+    nodes: &[String],
+) -> Result<Vec<String>> {
+    if !excluded.transient.is_empty() {
+        anyhow::bail!(
+            "transient {EXCLUDE_NAME_SETTING} is set to {}, clear it before using :drain",
+            excluded.transient.join(",")
+        );
+    }
+    let mut excluded = excluded.persistent.clone();
+    if action == "add" {
+        if !nodes.iter().any(|name| name == node) {
+            anyhow::bail!("unknown node {node:?}, known nodes: {}", nodes.join(", "));
+        }
+        if excluded.iter().any(|name| name == node) {
+            anyhow::bail!("{node} is already excluded");
+        }
+        excluded.push(node.to_string());
+    } else {
+        let before = excluded.len();
+        excluded.retain(|name| name != node);
+        if excluded.len() == before {
+            anyhow::bail!("{node} is not excluded");
+        }
+    }
+    Ok(excluded)
+}
+
+pub(crate) fn open_drain_overview(app: &mut App) -> Result<()> {
+    let excluded = fetch_excluded_nodes(&app.client, &app.es_url)?;
+    let (allocation, shards) = fetch_node_shard_counts(&app.client, &app.es_url)?;
+    let lines = drain_overview_lines(&excluded, &allocation, &shards);
+    app.info_panel = Some(InfoPanel::new("Node allocation exclusion", lines));
+    Ok(())
+}
+
+fn drain_overview_lines(
+    excluded: &ExcludedNodes,
+    allocation: &[AllocationRow],
+    shards: &[ShardRow],
+) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{EXCLUDE_NAME_SETTING}: "), label_style),
+        Span::raw(if excluded.persistent.is_empty() {
+            "-".to_string()
+        } else {
+            excluded.persistent.join(",")
+        }),
+    ])];
+    if !excluded.transient.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("transient: ", label_style),
+            Span::styled(
+                format!(
+                    "{} (set outside :drain, clear it before draining)",
+                    excluded.transient.join(",")
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+    for row in allocation {
+        let Some(node) = row.node.as_deref() else {
+            continue;
+        };
+        let relocating = shards
+            .iter()
+            .filter(|shard| {
+                shard.state.as_deref() == Some("RELOCATING")
+                    && shard
+                        .node
+                        .as_deref()
+                        .is_some_and(|name| name.split_whitespace().next() == Some(node))
+            })
+            .count();
+        let is_excluded = excluded.contains(node);
+        let mut spans = vec![
+            Span::styled(
+                format!("{node:<24}"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " shards={} relocating={relocating}",
+                row.shards.as_deref().unwrap_or("-")
+            )),
+        ];
+        if is_excluded {
+            let (label, color) = match row.shards.as_deref() {
+                Some("0") => (" drained", Color::Green),
+                _ => (" draining", Color::Yellow),
+            };
+            spans.push(Span::styled(label, Style::default().fg(color)));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "exclude with :drain add <node>, restore with :drain remove <node>",
+        label_style,
+    )));
+    lines
+}
+
+pub(crate) fn selected_index_name(app: &App) -> Result<String> {
+    if app.scope_kind != ScopeKind::Indices {
+        anyhow::bail!("select an index first");
+    }
+    app.selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no index selected")
+}
+
+pub(crate) fn open_shard_placement(app: &mut App, notice: Option<Line<'static>>) -> Result<()> {
+    let index = selected_index_name(app)?;
+    let shards = fetch_shard_placement(&app.client, &app.es_url, &index)?;
+    let raw = app.config.display.raw_numbers;
+    let mut lines = Vec::new();
+    if let Some(notice) = notice {
+        lines.push(notice);
+        lines.push(Line::from(""));
+    }
+    for shard in &shards {
+        let state_color = match shard.state.as_str() {
+            "STARTED" => Color::Green,
+            "RELOCATING" | "INITIALIZING" => Color::Yellow,
+            _ => Color::Red,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:>3} {} ", shard.shard, shard.prirep),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{:<12}", shard.state),
+                Style::default().fg(state_color),
+            ),
+            Span::raw(format!(
+                " {:<32} docs={} size={}",
+                shard.node.as_deref().unwrap_or("-"),
+                format_count_str(shard.docs.as_deref(), raw),
+                format_bytes_str(shard.store.as_deref(), raw)
+            )),
+        ]));
+    }
+    if shards.is_empty() {
+        lines.push(Line::from("No shards"));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "dry-run with :reroute move <shard> <from> <to> or :reroute cancel <shard> <node>",
+        Style::default().fg(Color::Gray),
+    )));
+    app.info_panel = Some(InfoPanel::new(format!("Shards {index}"), lines));
+    Ok(())
+}
+
+pub(crate) fn open_quit_confirm(app: &mut App) {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} operation(s) still running", app.jobs.len()),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for job in &app.jobs {
+        let kind = match &job.task_id {
+            Some(task_id) => format!("task {task_id}"),
+            None => "local, stops on exit".to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                job.label.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  {kind}, running {}s", job.started.elapsed().as_secs()),
+                label_style,
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "c cancel tasks and quit | d detach and quit (server tasks keep running) | Esc stay",
+        label_style,
+    )));
+    app.info_panel = Some(InfoPanel::new("Quit", lines));
+    app.prompt = Some(Prompt::Quit);
+}
+
+pub(crate) fn open_reconnect_confirm(app: &mut App, cluster: ClusterConfig) {
+    let label_style = Style::default().fg(Color::Gray);
+    let current = &app.config.cluster;
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "connection settings changed in the config file",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    let changes = [
+        ("url", current.url.clone(), cluster.url.clone()),
+        (
+            "auth",
+            format!("{:?}", current.auth),
+            format!("{:?}", cluster.auth),
+        ),
+        (
+            "insecure",
+            current.insecure.to_string(),
+            cluster.insecure.to_string(),
+        ),
+        (
+            "ca_cert",
+            format!("{:?}", current.ca_cert),
+            format!("{:?}", cluster.ca_cert),
+        ),
+    ];
+    for (label, before, after) in changes {
+        if before != after {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label}: "), label_style),
+                Span::raw(format!("{before} -> {after}")),
+            ]));
+        }
+    }
+    if lines.len() == 2 {
+        lines.push(Line::from(Span::styled("credentials changed", label_style)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "y reconnect | n keep the current connection",
+        label_style,
+    )));
+    app.info_panel = Some(InfoPanel::new("Reconnect", lines));
+    app.prompt = Some(Prompt::Reconnect(cluster));
+}
+
+const AGG_BAR_WIDTH: usize = 30;
+const AGG_KEY_WIDTH: usize = 28;
+const AGG_SCROLL_MARGIN: usize = 8;
+
+pub(crate) fn aggregation_fields(app: &App, kind: AggKind) -> Vec<String> {
+    app.mapping
+        .iter()
+        .flat_map(|view| &view.fields)
+        .filter(|field| kind.accepts(&field.field_type))
+        .map(|field| field.path.clone())
+        .collect()
+}
+
+pub(crate) fn open_aggregation_builder(
+    app: &mut App,
+    kind: AggKind,
+    selected: usize,
+) -> Result<()> {
+    refresh_mapping(app)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let fields = aggregation_fields(app, kind);
+    let selected = selected.min(fields.len().saturating_sub(1));
+    let mut kinds = vec![Span::styled("type: ", label_style)];
+    for candidate in AggKind::ALL {
+        kinds.push(if candidate == kind {
+            Span::styled(
+                format!("[{}] ", candidate.as_str()),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled(format!(" {}  ", candidate.as_str()), label_style)
+        });
+    }
+    let mut lines = vec![Line::from(kinds), Line::from("")];
+    if fields.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("no fields in the mapping support {}", kind.as_str()),
+            label_style,
+        )));
+    }
+    for (index, field) in fields.iter().enumerate() {
+        lines.push(if index == selected {
+            Line::from(Span::styled(
+                format!("> {field}"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Line::from(format!("  {field}"))
+        });
+    }
+    let mut panel = InfoPanel::new("Aggregate", lines);
+    panel.scroll = (selected + 2).saturating_sub(AGG_SCROLL_MARGIN) as u16;
+    app.info_panel = Some(panel);
+    app.prompt = Some(Prompt::BuildAggregation {
+        kind,
+        field: selected,
+    });
+    Ok(())
+}
+
+pub(crate) fn run_aggregation(app: &mut App, request: &AggRequest) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let result = fetch_aggregation(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &app.query,
+        &app.search_params(),
+        request,
+    )?;
+    let title = format!("{} {}", request.kind.as_str(), request.field);
+    app.info_panel = Some(InfoPanel::new(title, aggregation_lines(request, &result)));
+    Ok(())
+}
+
+const ANOMALY_WINDOW: usize = 6;
+const ANOMALY_MIN_HISTORY: usize = 3;
+const ANOMALY_THRESHOLD: f64 = 3.0;
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Anomaly {
+    Spike,
+    Drop,
+}
+
+impl Anomaly {
+    fn style(self) -> Style {
+        match self {
+            Anomaly::Spike => Style::default().fg(Color::Red),
+            Anomaly::Drop => Style::default().fg(Color::Blue),
+        }
+    }
+}
+
+pub(crate) fn bucket_anomalies(counts: &[u64]) -> Vec<Option<Anomaly>> {
+    let mut history: Vec<u64> = Vec::new();
+    counts
+        .iter()
+        .map(|&count| {
+            let window = &history[history.len().saturating_sub(ANOMALY_WINDOW)..];
+            let anomaly = if window.len() < ANOMALY_MIN_HISTORY {
+                None
+            } else {
+                let len = window.len() as f64;
+                let mean = window.iter().sum::<u64>() as f64 / len;
+                let variance = window
+                    .iter()
+                    .map(|&value| (value as f64 - mean).powi(2))
+                    .sum::<f64>()
+                    / len;
+                let spread = variance.sqrt().max(mean.sqrt()).max(1.0);
+                let deviation = (count as f64 - mean) / spread;
+                if deviation > ANOMALY_THRESHOLD {
+                    Some(Anomaly::Spike)
+                } else if deviation < -ANOMALY_THRESHOLD {
+                    Some(Anomaly::Drop)
+                } else {
+                    None
+                }
+            };
+            if anomaly.is_none() {
+                history.push(count);
+            }
+            anomaly
+        })
+        .collect()
+}
+
+pub(crate) fn aggregation_lines(request: &AggRequest, result: &AggResult) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = Vec::new();
+    let histogram = request.kind == AggKind::DateHistogram;
+    if histogram {
+        lines.push(Line::from(vec![
+            Span::styled("interval: ", label_style),
+            Span::raw(request.interval.clone()),
+        ]));
+    }
+    match result {
+        AggResult::Buckets { buckets, other } => {
+            if buckets.is_empty() {
+                lines.push(Line::from(Span::styled("no buckets", label_style)));
+            }
+            let max = buckets.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+            let counts: Vec<u64> = buckets.iter().map(|bucket| bucket.count).collect();
+            let anomalies = if histogram {
+                bucket_anomalies(&counts)
+            } else {
+                vec![None; counts.len()]
+            };
+            if histogram && !buckets.is_empty() {
+                let mut spark = vec![Span::styled("trend:    ", label_style)];
+                for (count, anomaly) in counts.iter().zip(&anomalies) {
+                    let level = (*count as usize * (SPARK_LEVELS.len() - 1))
+                        .checked_div(max as usize)
+                        .unwrap_or(0);
+                    spark.push(Span::styled(
+                        SPARK_LEVELS[level].to_string(),
+                        anomaly.map_or(Style::default().fg(Color::Cyan), Anomaly::style),
+                    ));
+                }
+                lines.push(Line::from(spark));
+                let flagged = anomalies.iter().flatten().count();
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "{flagged} anomalies (beyond {ANOMALY_THRESHOLD}x spread of the previous {ANOMALY_WINDOW} buckets)"
+                    ),
+                    label_style,
+                )));
+            }
+            if histogram {
+                lines.push(Line::from(""));
+            }
+            for (bucket, anomaly) in buckets.iter().zip(&anomalies) {
+                let width = (bucket.count as usize * AGG_BAR_WIDTH)
+                    .checked_div(max as usize)
+                    .unwrap_or(0)
+                    .max(usize::from(bucket.count > 0));
+                let mut spans = vec![
+                    Span::raw(format!(
+                        "{:<width$} ",
+                        truncate_string(&bucket.key, AGG_KEY_WIDTH),
+                        width = AGG_KEY_WIDTH
+                    )),
+                    Span::styled(format!("{:>8} ", bucket.count), label_style),
+                    Span::styled(
+                        "█".repeat(width),
+                        anomaly.map_or(Style::default().fg(Color::Cyan), Anomaly::style),
+                    ),
+                ];
+                match anomaly {
+                    Some(Anomaly::Spike) => {
+                        spans.push(Span::styled(" ▲ spike", Anomaly::Spike.style()))
+                    }
+                    Some(Anomaly::Drop) => {
+                        spans.push(Span::styled(" ▼ drop", Anomaly::Drop.style()))
+                    }
+                    None => {}
+                }
+                lines.push(Line::from(spans));
+            }
+            if *other > 0 {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("{other} docs in other buckets"),
+                    label_style,
+                )));
+            }
+        }
+        AggResult::Stats {
+            count,
+            min,
+            max,
+            avg,
+            sum,
+        } => {
+            let number =
+                |value: &Option<f64>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+            for (label, value) in [
+                ("count", count.to_string()),
+                ("min", number(min)),
+                ("max", number(max)),
+                ("avg", number(avg)),
+                ("sum", sum.to_string()),
+            ] {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{label:<6} "), label_style),
+                    Span::raw(value),
+                ]));
+            }
+        }
+    }
+    lines
+}
+
+pub(crate) fn run_agg_command(app: &mut App, args: &[&str]) -> Result<()> {
+    const USAGE: &str = "usage: agg [terms|date_histogram|stats] [field] [interval]";
+    let kind = match args.first() {
+        Some(kind) => AggKind::parse(kind).context(USAGE)?,
+        None => AggKind::Terms,
+    };
+    match args {
+        [] | [_] => open_aggregation_builder(app, kind, 0),
+        [_, field] => run_aggregation(
+            app,
+            &AggRequest {
+                kind,
+                field: field.to_string(),
+                interval: AGG_DEFAULT_INTERVAL.to_string(),
+            },
+        ),
+        [_, field, interval] if kind == AggKind::DateHistogram => run_aggregation(
+            app,
+            &AggRequest {
+                kind,
+                field: field.to_string(),
+                interval: interval.to_string(),
+            },
+        ),
+        _ => anyhow::bail!(USAGE),
+    }
+}
+
+const LATENCY_HINTS: [&str; 5] = ["duration", "latency", "took", "elapsed", "response_time"];
+
+pub(crate) fn latency_fields(app: &App) -> Vec<String> {
+    aggregation_fields(app, AggKind::Stats)
+        .into_iter()
+        .filter(|path| {
+            let path = path.to_ascii_lowercase();
+            LATENCY_HINTS.iter().any(|hint| path.contains(hint))
+        })
+        .collect()
+}
+
+pub(crate) fn open_latency_view(app: &mut App, args: &[&str]) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let (field, split) = match args {
+        [] => {
+            refresh_mapping(app)?;
+            let field = latency_fields(app)
+                .into_iter()
+                .next()
+                .context("no duration field found; usage: latency [field] [split-field]")?;
+            (field, None)
+        }
+        [field] => (field.to_string(), None),
+        [field, split] => (field.to_string(), Some(split.to_string())),
+        _ => anyhow::bail!("usage: latency [field] [split-field]"),
+    };
+    let rows = fetch_percentiles(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &app.query,
+        &app.search_params(),
+        &field,
+        split.as_deref(),
+    )?;
+    let title = match &split {
+        Some(split) => format!("Latency {field} by {split}"),
+        None => format!("Latency {field}"),
+    };
+    app.info_panel = Some(InfoPanel::new(title, percentile_lines(&rows)));
+    Ok(())
+}
+
+pub(crate) fn percentile_lines(rows: &[PercentileRow]) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let number = |value: Option<f64>| match value {
+        Some(value) if value.fract() == 0.0 => format!("{value:.0}"),
+        Some(value) => format!("{value:.2}"),
+        None => "-".to_string(),
+    };
+    let mut header = format!("{:<width$} {:>8}", "key", "count", width = AGG_KEY_WIDTH);
+    for percent in PERCENTS {
+        header.push_str(&format!(" {:>10}", format!("p{percent}")));
+    }
+    let mut lines = vec![Line::from(Span::styled(header, label_style))];
+    let slowest = rows
+        .iter()
+        .filter_map(|row| row.values.last().copied().flatten())
+        .fold(0.0, f64::max);
+    for (index, row) in rows.iter().enumerate() {
+        let mut spans = vec![
+            Span::raw(format!(
+                "{:<width$} ",
+                truncate_string(&row.key, AGG_KEY_WIDTH),
+                width = AGG_KEY_WIDTH
+            )),
+            Span::styled(format!("{:>8}", row.count), label_style),
+        ];
+        for value in &row.values {
+            spans.push(Span::raw(format!(" {:>10}", number(*value))));
+        }
+        let tail = row.values.last().copied().flatten().unwrap_or(0.0);
+        let width = if slowest > 0.0 {
+            ((tail / slowest) * AGG_BAR_WIDTH as f64 / 2.0).round() as usize
+        } else {
+            0
+        };
+        spans.push(Span::styled(
+            format!(" {}", "█".repeat(width)),
+            Style::default().fg(Color::Magenta),
+        ));
+        lines.push(Line::from(spans));
+        if index == 0 && rows.len() > 1 {
+            lines.push(Line::from(""));
+        }
+    }
+    lines
+}
+
+pub(crate) fn confirm_aggregation(app: &mut App, kind: AggKind, selected: usize) -> Result<()> {
+    let field = aggregation_fields(app, kind)
+        .into_iter()
+        .nth(selected)
+        .context("no field selected")?;
+    run_aggregation(
+        app,
+        &AggRequest {
+            kind,
+            field,
+            interval: AGG_DEFAULT_INTERVAL.to_string(),
+        },
+    )
+}
+
+pub(crate) fn column_fields(app: &App) -> Vec<String> {
+    app.mapping
+        .iter()
+        .flat_map(|view| &view.fields)
+        .filter(|field| !matches!(field.field_type.as_str(), "object" | "nested"))
+        .map(|field| field.path.clone())
+        .collect()
+}
+
+pub(crate) fn open_column_picker(app: &mut App, selected: usize) -> Result<()> {
+    refresh_mapping(app)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let fields = column_fields(app);
+    let selected = selected.min(fields.len().saturating_sub(1));
+    let columns = app.result_columns();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Space toggles a column | Enter closes",
+            label_style,
+        )),
+        Line::from(""),
+    ];
+    if fields.is_empty() {
+        lines.push(Line::from(Span::styled("no mapped fields", label_style)));
+    }
+    for (index, field) in fields.iter().enumerate() {
+        let marker = if index == selected { ">" } else { " " };
+        let check = if columns.contains(field) {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let style = if index == selected {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{marker} {check} {field}"),
+            style,
+        )));
+    }
+    let mut panel = InfoPanel::new("Columns", lines);
+    panel.scroll = (selected + 2).saturating_sub(AGG_SCROLL_MARGIN) as u16;
+    app.info_panel = Some(panel);
+    app.prompt = Some(Prompt::PickColumns(selected));
+    Ok(())
+}
+
+fn store_columns(app: &mut App, scope: &str, columns: Vec<String>) -> Result<()> {
+    let key = app.note_key(scope);
+    if columns.is_empty() {
+        app.state.columns.remove(&key);
+    } else {
+        app.state.columns.insert(key, columns);
+    }
+    save_state(&app.state)
+}
+
+pub(crate) fn toggle_column(app: &mut App, selected: usize) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let field = column_fields(app)
+        .into_iter()
+        .nth(selected)
+        .context("no field selected")?;
+    let mut columns = app.result_columns().to_vec();
+    match columns.iter().position(|column| *column == field) {
+        Some(position) => {
+            columns.remove(position);
+        }
+        None => columns.push(field),
+    }
+    store_columns(app, &scope, columns)?;
+    open_column_picker(app, selected)
+}
+
+pub(crate) fn run_columns_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    match args {
+        [] => open_column_picker(app, 0),
+        ["clear"] => store_columns(app, &scope, Vec::new()),
+        fields => {
+            let columns = fields
+                .join(",")
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(str::to_string)
+                .collect();
+            store_columns(app, &scope, columns)
+        }
+    }
+}
+
+pub(crate) fn open_cell_popup(app: &mut App) -> Result<()> {
+    let doc = app.selected_doc().context("no document selected")?;
+    let source = app.display_source(doc);
+    let (title, value) = match app.selected_column() {
+        Some(column) => (
+            format!("{} {column}", doc.id),
+            ecs_field(&source, column).cloned().unwrap_or(Value::Null),
+        ),
+        None => (format!("{} _source", doc.id), source.into_owned()),
+    };
+    let text = match &value {
+        Value::String(text) => text.clone(),
+        Value::Null => "(missing)".to_string(),
+        value => serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()),
+    };
+    let lines = text
+        .lines()
+        .map(|line| Line::from(line.to_string()))
+        .collect();
+    let mut panel = InfoPanel::new(title, lines);
+    panel.wrap = true;
+    app.info_panel = Some(panel);
+    Ok(())
+}
+
+pub(crate) fn sort_fields(app: &App) -> Vec<String> {
+    app.mapping
+        .iter()
+        .flat_map(|view| &view.fields)
+        .filter(|field| AggKind::Terms.accepts(&field.field_type))
+        .map(|field| field.path.clone())
+        .collect()
+}
+
+pub(crate) fn open_sort_picker(app: &mut App, selected: usize, order: SortOrder) -> Result<()> {
+    refresh_mapping(app)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let fields = sort_fields(app);
+    let selected = selected.min(fields.len());
+    let mut header = vec![Span::styled("order: ", label_style)];
+    for candidate in [SortOrder::Desc, SortOrder::Asc] {
+        header.push(if candidate == order {
+            Span::styled(
+                format!("[{}] ", candidate.as_str()),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled(format!(" {}  ", candidate.as_str()), label_style)
+        });
+    }
+    let mut lines = vec![Line::from(header), Line::from("")];
+    let entries = std::iter::once("(default order)".to_string()).chain(fields);
+    for (index, field) in entries.enumerate() {
+        let active = match &app.sort {
+            Some(sort) => sort.field == field,
+            None => index == 0,
+        };
+        let marker = if index == selected { ">" } else { " " };
+        let style = if index == selected {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{marker} {field}"), style),
+            Span::styled(
+                if active { "  (active)" } else { "" },
+                Style::default().fg(Color::Green),
+            ),
+        ]));
+    }
+    let mut panel = InfoPanel::new("Sort", lines);
+    panel.scroll = (selected + 2).saturating_sub(AGG_SCROLL_MARGIN) as u16;
+    app.info_panel = Some(panel);
+    app.prompt = Some(Prompt::PickSort {
+        field: selected,
+        order,
+    });
+    Ok(())
+}
+
+pub(crate) fn filter_field_path(path: &str) -> String {
+    let mut field = String::new();
+    let mut in_index = false;
+    for ch in path.chars() {
+        match ch {
+            '[' => in_index = true,
+            ']' => in_index = false,
+            _ if !in_index => field.push(ch),
+            _ => {}
+        }
+    }
+    field
+}
+
+pub(crate) fn move_drawer_field(app: &mut App, delta: isize) {
+    if app.doc_view_mode != DocViewMode::Flatten {
+        return;
+    }
+    let Some(doc) = app.selected_doc() else {
+        return;
+    };
+    let count = json_lines_flatten(&app.display_source(doc)).len();
+    app.drawer_field = app
+        .drawer_field
+        .min(count.saturating_sub(1))
+        .saturating_add_signed(delta)
+        .min(count.saturating_sub(1));
+}
+
+pub(crate) fn add_field_filter(app: &mut App, exclude: bool) -> Result<()> {
+    if app.doc_view_mode != DocViewMode::Flatten {
+        anyhow::bail!("switch the drawer to Flatten view (v) to pick a field");
+    }
+    let filter = {
+        let doc = app.selected_doc().context("no document selected")?;
+        let source = app.display_source(doc);
+        let mut leaves = Vec::new();
+        flatten_json_leaves(&source, None, &mut leaves);
+        let (path, value) = leaves
+            .get(app.drawer_field.min(leaves.len().saturating_sub(1)))
+            .context("no field selected")?;
+        if matches!(value, Value::Null | Value::Object(_) | Value::Array(_)) {
+            anyhow::bail!("{path} has no single value to filter on");
+        }
+        FieldFilter {
+            field: filter_field_path(path),
+            value: (*value).clone(),
+            exclude,
+        }
+    };
+    match app.filters.iter().position(|existing| *existing == filter) {
+        Some(index) => {
+            app.filters.remove(index);
+        }
+        None => {
+            app.filters.retain(|existing| {
+                existing.field != filter.field || existing.value != filter.value
+            });
+            app.filters.push(filter);
+        }
+    }
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+    Ok(())
+}
+
+pub(crate) fn run_filter_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] | ["clear"] => {
+            app.filters.clear();
+            app.reset_docs_paging();
+            handle_docs_refresh(app);
+            Ok(())
+        }
+        _ => anyhow::bail!("usage: :filter [clear]"),
+    }
+}
+
+pub(crate) fn set_sort(app: &mut App, sort: Option<SortSpec>) {
+    app.sort = sort;
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+}
+
+pub(crate) fn confirm_sort(app: &mut App, selected: usize, order: SortOrder) -> Result<()> {
+    let sort = match selected.checked_sub(1) {
+        None => None,
+        Some(index) => {
+            let field = sort_fields(app)
+                .into_iter()
+                .nth(index)
+                .context("no field selected")?;
+            Some(SortSpec { field, order })
+        }
+    };
+    set_sort(app, sort);
+    Ok(())
+}
+
+pub(crate) fn run_sort_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let order = |value: &str| match value {
+        "asc" => Ok(SortOrder::Asc),
+        "desc" => Ok(SortOrder::Desc),
+        _ => anyhow::bail!("usage: sort [<field> [asc|desc] | clear]"),
+    };
+    match args {
+        [] => {
+            let order = app.sort.as_ref().map(|sort| sort.order).unwrap_or_default();
+            open_sort_picker(app, 0, order)
+        }
+        ["clear"] => {
+            set_sort(app, None);
+            Ok(())
+        }
+        [field] | [field, _] => {
+            let order = args.get(1).map(|value| order(value)).transpose()?;
+            set_sort(
+                app,
+                Some(SortSpec {
+                    field: field.to_string(),
+                    order: order.unwrap_or_default(),
+                }),
+            );
+            Ok(())
+        }
+        _ => anyhow::bail!("usage: sort [<field> [asc|desc] | clear]"),
+    }
+}
+
+pub(crate) fn toggle_favorite(app: &mut App) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let key = app.note_key(&scope);
+    match app
+        .state
+        .favorites
+        .iter()
+        .position(|favorite| *favorite == key)
+    {
+        Some(position) => {
+            app.state.favorites.remove(position);
+        }
+        None => app.state.favorites.push(key),
+    }
+    save_state(&app.state)
+}
+
+pub(crate) fn open_favorite_picker(app: &mut App, selected: usize) {
+    let favorites = app.favorites();
+    let selected = selected.min(favorites.len().saturating_sub(1));
+    let mut lines: Vec<Line<'static>> = favorites
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            if index == selected {
+                Line::from(Span::styled(
+                    format!("> {name}"),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Line::from(format!("  {name}"))
+            }
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "no favorites yet (* stars the selected scope)",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Favorites", lines));
+    app.prompt = Some(Prompt::PickFavorite(selected));
+}
+
+pub(crate) fn select_favorite(app: &mut App, selected: usize) -> Result<()> {
+    let name = app
+        .favorites()
+        .get(selected)
+        .map(|name| name.to_string())
+        .context("no favorite selected")?;
+    let kind = scope_kind_of(app, &name)
+        .with_context(|| format!("{name} no longer exists on this cluster"))?;
+    app.record_nav();
+    app.set_scope_kind(kind);
+    app.select_scope_by_name(&name);
+    app.reset_docs_paging();
+    handle_scope_change(app);
+    Ok(())
+}
+
+pub(crate) fn save_view(app: &mut App, name: &str) -> Result<()> {
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let view = SavedView {
+        name: name.to_string(),
+        columns: app.result_columns().to_vec(),
+        scope,
+        query: app.query.clone(),
+        dsl: app.query_mode == QueryMode::Dsl,
+        sort: app.sort.clone(),
+    };
+    match app
+        .state
+        .saved_views
+        .iter_mut()
+        .find(|saved| saved.name == name)
+    {
+        Some(saved) => *saved = view,
+        None => app.state.saved_views.push(view),
+    }
+    save_state(&app.state)
+}
+
+pub(crate) fn delete_view(app: &mut App, name: &str) -> Result<()> {
+    let position = app
+        .state
+        .saved_views
+        .iter()
+        .position(|view| view.name == name)
+        .with_context(|| format!("no saved view named {name:?}"))?;
+    app.state.saved_views.remove(position);
+    save_state(&app.state)
+}
+
+pub(crate) fn load_view(app: &mut App, name: &str) -> Result<()> {
+    let view = app
+        .state
+        .saved_views
+        .iter()
+        .find(|view| view.name == name)
+        .cloned()
+        .with_context(|| format!("no saved view named {name:?}"))?;
+    app.record_nav();
+    match scope_kind_of(app, &view.scope) {
+        Some(kind) => {
+            app.set_scope_kind(kind);
+            app.select_scope_by_name(&view.scope);
+        }
+        None => app.scope_pattern = Some(view.scope.clone()),
+    }
+    app.query_mode = if view.dsl {
+        QueryMode::Dsl
+    } else {
+        QueryMode::QueryString
+    };
+    app.query = view.query;
+    app.saved_query_string.clear();
+    app.sort = view.sort;
+    if !view.columns.is_empty() {
+        store_columns(app, &view.scope, view.columns)?;
+    }
+    app.reset_docs_paging();
+    handle_scope_change(app);
+    Ok(())
+}
+
+pub(crate) fn open_view_picker(app: &mut App, selected: usize) {
+    let label_style = Style::default().fg(Color::Gray);
+    let views = &app.state.saved_views;
+    let selected = selected.min(views.len().saturating_sub(1));
+    let mut lines: Vec<Line<'static>> = views
+        .iter()
+        .enumerate()
+        .map(|(index, view)| {
+            let style = if index == selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let marker = if index == selected { "> " } else { "  " };
+            Line::from(vec![
+                Span::styled(format!("{marker}{:<16}", view.name), style),
+                Span::styled(
+                    format!(" {}  {}", view.scope, display_query(&view.query)),
+                    label_style,
+                ),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "no saved views yet (:view save <name>)",
+            label_style,
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Saved views", lines));
+    app.prompt = Some(Prompt::PickView(selected));
+}
+
+pub(crate) fn run_view_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] => {
+            open_view_picker(app, 0);
+            Ok(())
+        }
+        ["save", name] => save_view(app, name),
+        ["load", name] => load_view(app, name),
+        ["delete", name] => delete_view(app, name),
+        _ => anyhow::bail!("usage: view [save|load|delete <name>]"),
+    }
+}
+
+fn selected_doc_blobs(app: &App) -> Result<(String, Vec<(String, FieldBlob)>)> {
+    let doc = app.selected_doc().context("no document selected")?;
+    Ok((
+        doc.id.clone(),
+        collect_field_blobs(&app.display_source(doc)),
+    ))
+}
+
+pub(crate) fn export_dir() -> Result<std::path::PathBuf> {
+    dirs::download_dir()
+        .or_else(|| std::env::current_dir().ok())
+        .context("no download directory")
+}
+
+pub(crate) fn default_export_path(app: &App) -> Result<String> {
+    let scope = app.selected_scope_name().context("no scope selected")?;
+    let name: String = scope
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let file = export_dir()?.join(format!("{name}-{}.ndjson", now_millis() / 1000));
+    Ok(file.display().to_string())
+}
+
+pub(crate) fn start_export(app: &mut App, path: &str) -> Result<()> {
+    let format =
+        ExportFormat::from_path(path).context("use a .ndjson, .jsonl, .csv or .json file name")?;
+    let index = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let redactor = (!app.unmasked && !app.redactor.is_empty()).then(|| app.redactor.clone());
+    let progress = spawn_export(ExportRequest {
+        client: app.client.clone(),
+        es_url: app.es_url.clone(),
+        index,
+        query: app.query.clone(),
+        params: app.search_params(),
+        columns: app.result_columns().to_vec(),
+        redactor,
+        format,
+        path: std::path::PathBuf::from(path),
+    });
+    let label = std::path::Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    app.start_job(format!("export {label}"), None);
+    if let Some(job) = app.jobs.last_mut() {
+        job.progress = Some(progress);
+    }
+    Ok(())
+}
+
+pub(crate) fn toggle_mark(app: &mut App) {
+    let Some(doc) = app.selected_doc().cloned() else {
+        return;
+    };
+    let key = (doc.index.clone(), doc.id.clone());
+    if app.basket.remove(&key).is_none() {
+        app.basket.insert(key, doc);
+    }
+    let next = app.docs_state.selected().map_or(0, |row| row + 1);
+    if next < app.visible_docs().len() {
+        app.docs_state.select(Some(next));
+    }
+}
+
+const MARKED_EXPORTS: [(&str, bool, bool); 4] = [
+    ("copy as NDJSON", false, false),
+    ("copy as JSON array", false, true),
+    ("save as NDJSON", true, false),
+    ("save as JSON array", true, true),
+];
+
+pub(crate) fn marked_export_text(app: &App, pretty: bool) -> Result<String> {
+    let docs: Vec<Value> = app
+        .marked_documents()
+        .into_iter()
+        .map(|doc| {
+            serde_json::json!({
+                "_index": doc.index,
+                "_id": doc.id,
+                "_source": app.display_source(doc),
+            })
+        })
+        .collect();
+    if docs.is_empty() {
+        anyhow::bail!("the basket is empty (Space marks a row)");
+    }
+    if pretty {
+        return Ok(format!("{}\n", serde_json::to_string_pretty(&docs)?));
+    }
+    let mut text = String::new();
+    for doc in &docs {
+        text.push_str(&serde_json::to_string(doc)?);
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+pub(crate) fn open_marked_export(app: &mut App, selected: usize) -> Result<()> {
+    let count = app.marked_documents().len();
+    if count == 0 {
+        anyhow::bail!("the basket is empty (Space marks a row)");
+    }
+    let selected = selected.min(MARKED_EXPORTS.len() - 1);
+    let lines = MARKED_EXPORTS
+        .iter()
+        .enumerate()
+        .map(|(index, (label, _, _))| {
+            if index == selected {
+                Line::from(Span::styled(
+                    format!("> {label}"),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Line::from(format!("  {label}"))
+            }
+        })
+        .collect();
+    app.info_panel = Some(InfoPanel::new(format!("Export {count} marked"), lines));
+    app.prompt = Some(Prompt::ExportMarked(selected));
+    Ok(())
+}
+
+pub(crate) fn export_marked(app: &mut App, selected: usize) -> Result<()> {
+    let (_, save, pretty) = MARKED_EXPORTS
+        .get(selected)
+        .copied()
+        .context("no export selected")?;
+    let text = marked_export_text(app, pretty)?;
+    let count = app.marked_documents().len();
+    let label_style = Style::default().fg(Color::Gray);
+    let target = if save {
+        let extension = if pretty { "json" } else { "ndjson" };
+        let file = export_dir()?.join(format!("marked-{}.{extension}", now_millis() / 1000));
+        std::fs::write(&file, &text)
+            .with_context(|| format!("failed to write {}", file.display()))?;
+        Line::from(vec![
+            Span::styled("saved: ", label_style),
+            Span::raw(file.display().to_string()),
+        ])
+    } else {
+        copy_to_clipboard(&text)?;
+        Line::from(Span::styled(
+            "copied to the clipboard (terminals without OSC 52 support ignore this)",
+            label_style,
+        ))
+    };
+    app.info_panel = Some(InfoPanel::new(
+        "Export",
+        vec![
+            Line::from(format!("{count} marked documents, {} bytes", text.len())),
+            target,
+        ],
+    ));
+    Ok(())
+}
+
+const BASKET_DIFF_LIMIT: usize = 40;
+
+pub(crate) fn open_basket(app: &mut App, selected: usize) -> Result<()> {
+    if app.basket.is_empty() {
+        anyhow::bail!("the basket is empty (Space marks a row)");
+    }
+    let selected = selected.min(app.basket.len() - 1);
+    let label_style = Style::default().fg(Color::Gray);
+    let lines: Vec<Line> = app
+        .marked_documents()
+        .into_iter()
+        .enumerate()
+        .map(|(index, doc)| {
+            let marker = if index == selected { "> " } else { "  " };
+            let style = if index == selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{marker}{}/{}", doc.index, doc.id), style),
+                Span::styled(format!("  {}", doc_summary(app, doc, 60)), label_style),
+            ])
+        })
+        .collect();
+    let mut panel = InfoPanel::new(format!("Basket ({})", lines.len()), lines);
+    panel.scroll = (selected + 2).saturating_sub(AGG_SCROLL_MARGIN) as u16;
+    app.info_panel = Some(panel);
+    app.prompt = Some(Prompt::PickBasket(selected));
+    Ok(())
+}
+
+fn basket_doc(app: &App, selected: usize) -> Result<DocEntry> {
+    app.marked_documents()
+        .get(selected)
+        .map(|doc| (*doc).clone())
+        .context("no document selected")
+}
+
+pub(crate) fn open_basket_doc(app: &mut App, selected: usize) -> Result<()> {
+    let doc = basket_doc(app, selected)?;
+    let lines = json_lines_expanded(&app.display_source(&doc))
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    app.info_panel = Some(InfoPanel::new(format!("{}/{}", doc.index, doc.id), lines));
+    Ok(())
+}
+
+pub(crate) fn remove_basket_doc(app: &mut App, selected: usize) -> Result<()> {
+    let doc = basket_doc(app, selected)?;
+    app.basket.remove(&(doc.index, doc.id));
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct FieldChange {
+    pub(crate) path: String,
+    pub(crate) before: Option<String>,
+    pub(crate) after: Option<String>,
+}
+
+pub(crate) fn doc_field_diff(before: &Value, after: &Value) -> (usize, Vec<FieldChange>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    flatten_fields(before, None, &mut left);
+    flatten_fields(after, None, &mut right);
+    let left: BTreeMap<String, String> = left.into_iter().collect();
+    let mut right: BTreeMap<String, String> = right.into_iter().collect();
+    let mut same = 0;
+    let mut changed = Vec::new();
+    for (path, value) in left {
+        match right.remove(&path) {
+            Some(other) if other == value => same += 1,
+            other => changed.push(FieldChange {
+                path,
+                before: Some(value),
+                after: other,
+            }),
+        }
+    }
+    changed.extend(right.into_iter().map(|(path, value)| FieldChange {
+        path,
+        before: None,
+        after: Some(value),
+    }));
+    changed.sort_by(|a, b| a.path.cmp(&b.path));
+    (same, changed)
+}
+
+pub(crate) fn open_basket_diff(app: &mut App, selected: usize) -> Result<()> {
+    let count = app.basket.len();
+    if count < 2 {
+        anyhow::bail!("mark at least two documents to compare");
+    }
+    let before = basket_doc(app, selected)?;
+    let after = basket_doc(app, (selected + 1) % count)?;
+    let (same, changed) = doc_field_diff(&app.display_source(&before), &app.display_source(&after));
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("- ", Style::default().fg(Color::Red)),
+            Span::raw(format!("{}/{}", before.index, before.id)),
+        ]),
+        Line::from(vec![
+            Span::styled("+ ", Style::default().fg(Color::Green)),
+            Span::raw(format!("{}/{}", after.index, after.id)),
+        ]),
+        Line::from(Span::styled(
+            format!("{same} fields equal, {} differ", changed.len()),
+            label_style,
+        )),
+        Line::from(""),
+    ];
+    for change in changed.iter().take(BASKET_DIFF_LIMIT) {
+        lines.push(Line::from(Span::styled(
+            change.path.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        if let Some(old) = &change.before {
+            lines.push(Line::from(Span::styled(
+                format!("  - {old}"),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        if let Some(new) = &change.after {
+            lines.push(Line::from(Span::styled(
+                format!("  + {new}"),
+                Style::default().fg(Color::Green),
+            )));
+        }
+    }
+    if changed.len() > BASKET_DIFF_LIMIT {
+        lines.push(Line::from(Span::styled(
+            format!("... {} more", changed.len() - BASKET_DIFF_LIMIT),
+            label_style,
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Basket diff", lines));
+    Ok(())
+}
+
+pub(crate) fn open_blob_picker(app: &mut App, selected: usize) -> Result<()> {
+    let (_, blobs) = selected_doc_blobs(app)?;
+    if blobs.is_empty() {
+        anyhow::bail!("no links or encoded bytes in this document");
+    }
+    let label_style = Style::default().fg(Color::Gray);
+    let selected = selected.min(blobs.len() - 1);
+    let raw = app.config.display.raw_numbers;
+    let lines = blobs
+        .iter()
+        .enumerate()
+        .map(|(index, (path, blob))| {
+            let marker = if index == selected { "> " } else { "  " };
+            let style = if index == selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{marker}{path}"), style),
+                Span::styled(format!("  {}", blob.hint(raw)), label_style),
+            ])
+        })
+        .collect();
+    app.info_panel = Some(InfoPanel::new("Links and bytes", lines));
+    app.prompt = Some(Prompt::PickBlob(selected));
+    Ok(())
+}
+
+pub(crate) fn open_selected_blob(app: &mut App, selected: usize) -> Result<()> {
+    let (id, blobs) = selected_doc_blobs(app)?;
+    let (path, blob) = blobs.get(selected).context("no field selected")?;
+    let label_style = Style::default().fg(Color::Gray);
+    let lines = match blob {
+        FieldBlob::Url(url) => {
+            open_url(url)?;
+            vec![
+                Line::from(url.clone()),
+                Line::from(""),
+                Line::from(Span::styled("opened in the browser", label_style)),
+            ]
+        }
+        FieldBlob::Bytes { data, .. } => {
+            let dir = export_dir()?;
+            let name: String = format!("{id}-{path}")
+                .chars()
+                .map(|ch| {
+                    if ch.is_ascii_alphanumeric() || ch == '-' {
+                        ch
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let file = dir.join(format!("{name}.{}", blob.extension()));
+            std::fs::write(&file, data)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+            vec![
+                Line::from(vec![
+                    Span::styled("saved: ", label_style),
+                    Span::raw(file.display().to_string()),
+                ]),
+                Line::from(vec![
+                    Span::styled("size: ", label_style),
+                    Span::raw(blob.hint(app.config.display.raw_numbers)),
+                ]),
+            ]
+        }
+    };
+    app.info_panel = Some(InfoPanel::new(path.clone(), lines));
+    Ok(())
+}
+
+pub(crate) fn move_drawer_match(app: &mut App, delta: isize) {
+    let Some(doc) = app.selected_doc() else {
+        return;
+    };
+    let lines = drawer_body_lines(app, &app.display_source(doc));
+    let count = drawer_match_lines(&lines, &app.drawer_search).len();
+    if count > 0 {
+        let current = (app.drawer_match % count) as isize;
+        app.drawer_match = (current + delta).rem_euclid(count as isize) as usize;
+    }
+}
+
+pub(crate) fn open_cluster_picker(app: &mut App, selected: usize) {
+    let label_style = Style::default().fg(Color::Gray);
+    let profiles = cluster_profiles(app);
+    let selected = selected.min(profiles.len().saturating_sub(1));
+    let mut lines = Vec::new();
+    for (index, (name, cluster)) in profiles.iter().enumerate() {
+        let active = *name == app.cluster_name;
+        let marker = if index == selected { "> " } else { "  " };
+        let style = if index == selected {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(
+                format!("{:<12}", name.as_deref().unwrap_or("default")),
+                style,
+            ),
+            Span::styled(format!(" {}", cluster.url), label_style),
+            Span::styled(
+                if active { "  (active)" } else { "" },
+                Style::default().fg(Color::Green),
+            ),
+        ]));
+    }
+    if profiles.len() == 1 {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "add named profiles under [clusters.<name>] in the config file",
+            label_style,
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Clusters", lines));
+    app.prompt = Some(Prompt::PickCluster(selected));
+}
+
+pub(crate) fn run_cluster_command(app: &mut App, args: &[&str]) -> Result<()> {
+    match args {
+        [] => {
+            let current = cluster_profiles(app)
+                .iter()
+                .position(|(name, _)| *name == app.cluster_name)
+                .unwrap_or(0);
+            open_cluster_picker(app, current);
+            Ok(())
+        }
+        ["default"] => switch_cluster(app, None),
+        [name] => switch_cluster(app, Some(name.to_string())),
+        _ => anyhow::bail!("usage: cluster [name|default]"),
+    }
+}
+
+pub(crate) fn open_scope_preview(app: &mut App, args: &[&str]) -> Result<()> {
+    let [pattern] = args else {
+        anyhow::bail!("usage: scope <name|pattern>");
+    };
+    if !is_scope_pattern(pattern)
+        && let Some(kind) = scope_kind_of(app, pattern)
+    {
+        app.record_nav();
+        app.set_scope_kind(kind);
+        app.select_scope_by_name(pattern);
+        app.reset_docs_paging();
+        handle_scope_change(app);
+        return Ok(());
+    }
+    let resolved = resolve_scope(&app.client, &app.es_url, pattern)?;
+    if resolved.is_empty() {
+        anyhow::bail!("{pattern:?} does not match any index, alias or data stream");
+    }
+    let mut lines = resolved_scope_lines(&resolved);
+    lines.extend(shard_guard_lines(
+        fetch_search_shard_count(&app.client, &app.es_url, pattern),
+        &app.config.search,
+    ));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "y search this pattern | n cancel",
+        Style::default().fg(Color::Gray),
+    )));
+    app.info_panel = Some(InfoPanel::new(format!("Resolve {pattern}"), lines));
+    app.prompt = Some(Prompt::OpenScope(pattern.to_string()));
+    Ok(())
+}
+
+const RESOLVE_PREVIEW_LIMIT: usize = 20;
+
+pub(crate) fn resolved_scope_lines(resolved: &ResolvedScope) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let heading_style = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+    let sections: [(&str, Vec<String>); 3] = [
+        (
+            "data streams",
+            resolved
+                .data_streams
+                .iter()
+                .map(|stream| {
+                    format!(
+                        "{} ({} backing indices)",
+                        stream.name,
+                        stream.backing_indices.len()
+                    )
+                })
+                .collect(),
+        ),
+        (
+            "aliases",
+            resolved
+                .aliases
+                .iter()
+                .map(|alias| format!("{} -> {}", alias.name, alias.indices.join(", ")))
+                .collect(),
+        ),
+        (
+            "indices",
+            resolved
+                .indices
+                .iter()
+                .map(|index| match &index.data_stream {
+                    Some(stream) => format!("{} (data stream {stream})", index.name),
+                    None => index.name.clone(),
+                })
+                .collect(),
+        ),
+    ];
+    for (label, names) in sections {
+        if names.is_empty() {
+            continue;
+        }
+        lines.push(Line::from(Span::styled(
+            format!("{} {label}", names.len()),
+            heading_style,
+        )));
+        for name in names.iter().take(RESOLVE_PREVIEW_LIMIT) {
+            lines.push(Line::from(format!("  {name}")));
+        }
+        if names.len() > RESOLVE_PREVIEW_LIMIT {
+            lines.push(Line::from(Span::styled(
+                format!("  ... {} more", names.len() - RESOLVE_PREVIEW_LIMIT),
+                label_style,
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+    lines
+}
+
+pub(crate) fn shard_guard_lines(
+    shards: Result<usize>,
+    search: &SearchConfig,
+) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let shards = match shards {
+        Ok(shards) => shards,
+        Err(err) => {
+            return vec![Line::from(Span::styled(
+                format!("shard count unavailable: {err:#}"),
+                label_style,
+            ))];
+        }
+    };
+    let mut lines = Vec::new();
+    if shards > search.shard_warn_threshold {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "search will touch {shards} shards (warn threshold {})",
+                search.shard_warn_threshold
+            ),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )));
+    } else {
+        lines.push(Line::from(format!("search will touch {shards} shards")));
+    }
+    let mut params = Vec::new();
+    if let Some(limit) = search.max_concurrent_shard_requests {
+        params.push(format!("max_concurrent_shard_requests={limit}"));
+    }
+    if search.ignore_unavailable {
+        params.push("ignore_unavailable=true".to_string());
+    }
+    if !params.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("with {}", params.join(" ")),
+            label_style,
+        )));
+    }
+    lines
+}
+
+pub(crate) fn run_reroute_command(app: &mut App, args: &[&str]) -> Result<()> {
+    if let ["apply"] = args {
+        let command = app
+            .pending_reroute
+            .take()
+            .context("nothing to apply, run a dry-run first")?;
+        post_reroute(&app.client, &app.es_url, &command, false)?;
+        let notice = Line::from(Span::styled(
+            "reroute submitted",
+            Style::default().fg(Color::Green),
+        ));
+        return open_shard_placement(app, Some(notice));
+    }
+    let index = selected_index_name(app)?;
+    let command = match args {
+        ["move", shard, from, to] => serde_json::json!({
+            "move": {
+                "index": index,
+                "shard": shard.parse::<u64>().context("shard must be a number")?,
+                "from_node": from,
+                "to_node": to,
+            }
+        }),
+        ["cancel", shard, node] => serde_json::json!({
+            "cancel": {
+                "index": index,
+                "shard": shard.parse::<u64>().context("shard must be a number")?,
+                "node": node,
+            }
+        }),
+        _ => {
+            anyhow::bail!("usage: reroute move <shard> <from> <to> | cancel <shard> <node> | apply")
+        }
+    };
+    let response = post_reroute(&app.client, &app.es_url, &command, true)?;
+    let rejected = reroute_rejected(&response);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            json_value_compact(&command),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    lines.extend(reroute_explanation_lines(&response));
+    lines.push(Line::from(""));
+    if rejected {
+        lines.push(Line::from(Span::styled(
+            "dry-run rejected, nothing to apply",
+            Style::default().fg(Color::Red),
+        )));
+        app.pending_reroute = None;
+    } else {
+        lines.push(Line::from(Span::styled(
+            "dry-run accepted, run :reroute apply to execute",
+            Style::default().fg(Color::Green),
+        )));
+        app.pending_reroute = Some(command);
+    }
+    app.info_panel = Some(InfoPanel::new("Reroute dry-run", lines));
+    Ok(())
+}
+
+pub(crate) fn reroute_rejected(response: &Value) -> bool {
+    response
+        .get("explanations")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .flat_map(|explanation| {
+            explanation
+                .get("decisions")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+        })
+        .any(|decision| decision.get("decision").and_then(Value::as_str) == Some("NO"))
+}
+
+pub(crate) fn reroute_explanation_lines(response: &Value) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let explanations = response.get("explanations").and_then(Value::as_array);
+    for explanation in explanations.into_iter().flatten() {
+        let decisions = explanation.get("decisions").and_then(Value::as_array);
+        for decision in decisions.into_iter().flatten() {
+            let text = |key: &str| {
+                decision
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or("-")
+                    .to_string()
+            };
+            let verdict = text("decision");
+            let color = if verdict == "NO" {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{verdict:<4}"), Style::default().fg(color)),
+                Span::styled(
+                    text("decider"),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(": {}", text("explanation"))),
+            ]));
+        }
+    }
+    lines
+}
+
+pub(crate) fn open_deprecations(app: &mut App) -> Result<()> {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = vec![Line::from(Span::styled(
+        "Warning headers seen this session:",
+        label_style,
+    ))];
+    if let Ok(warnings) = DEPRECATION_WARNINGS.lock() {
+        for warning in warnings.iter() {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {} ", warning.endpoint),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("x{}", warning.count), label_style),
+            ]));
+            lines.push(Line::from(Span::styled(
+                format!("    {}", warning.message),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        if warnings.is_empty() {
+            lines.push(Line::from("  none"));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "_migration/deprecations:",
+        label_style,
+    )));
+    match fetch_migration_deprecations(&app.client, &app.es_url) {
+        Ok(payload) => {
+            let issues = migration_issues(&payload);
+            for issue in &issues {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  [{}] ", issue.level),
+                        Style::default().fg(issue_color(&issue.level)),
+                    ),
+                    Span::styled(format!("{}: ", issue.label()), label_style),
+                    Span::raw(issue.message.clone()),
+                ]));
+            }
+            if issues.is_empty() {
+                lines.push(Line::from("  none"));
+            }
+        }
+        Err(err) => lines.push(Line::from(Span::styled(
+            format!("  unavailable: {err:#}"),
+            Style::default().fg(Color::Red),
+        ))),
+    }
+    app.info_panel = Some(InfoPanel::new("Deprecations", lines));
+    Ok(())
+}
+
+pub(crate) fn issue_color(level: &str) -> Color {
+    if level == "critical" {
+        Color::Red
+    } else {
+        Color::Yellow
+    }
+}
+
+pub(crate) fn open_upgrade_report(app: &mut App) -> Result<()> {
+    let payload = fetch_migration_deprecations(&app.client, &app.es_url)?;
+    let issues = migration_issues(&payload);
+    let label_style = Style::default().fg(Color::Gray);
+    let critical = issues
+        .iter()
+        .filter(|issue| issue.level == "critical")
+        .count();
+    let mut lines = vec![
+        if critical == 0 {
+            Line::from(Span::styled(
+                "no critical issues, cluster is ready to upgrade",
+                Style::default().fg(Color::Green),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!("{critical} critical issues must be resolved before upgrading"),
+                Style::default().fg(Color::Red),
+            ))
+        },
+        Line::from(""),
+    ];
+    for (level, title) in [("critical", "Critical"), ("warning", "Warnings")] {
+        let mut group: Vec<&MigrationIssue> = issues
+            .iter()
+            .filter(|issue| (issue.level == "critical") == (level == "critical"))
+            .collect();
+        group.sort_by(|a, b| {
+            (a.section.as_str(), a.target.as_deref())
+                .cmp(&(b.section.as_str(), b.target.as_deref()))
+        });
+        lines.push(Line::from(Span::styled(
+            format!("{title} ({})", group.len()),
+            Style::default()
+                .fg(issue_color(level))
+                .add_modifier(Modifier::BOLD),
+        )));
+        let mut current = None;
+        for issue in group {
+            let label = issue.label();
+            if current.as_ref() != Some(&label) {
+                lines.push(Line::from(Span::styled(format!("  {label}"), label_style)));
+                current = Some(label);
+            }
+            lines.push(Line::from(format!("    [ ] {}", issue.message)));
+            if let Some(details) = &issue.details {
+                lines.push(Line::from(Span::styled(
+                    format!("        {details}"),
+                    label_style,
+                )));
+            }
+        }
+        lines.push(Line::from(""));
+    }
+    app.info_panel = Some(InfoPanel::new("Upgrade readiness", lines));
+    Ok(())
+}
+
+pub(crate) const LICENSE_WARNING_DAYS: i64 = 30;
+
+pub(crate) fn license_days_left(license: &LicenseInfo, now: i64) -> Option<i64> {
+    license
+        .expiry_date_in_millis
+        .map(|expiry| (expiry - now).div_euclid(86_400_000))
+}
+
+pub(crate) fn license_warning(license: &LicenseInfo, now: i64) -> Option<String> {
+    let days = license_days_left(license, now)?;
+    if days < 0 {
+        Some("license expired".to_string())
+    } else if days <= LICENSE_WARNING_DAYS {
+        Some(format!("license expires in {days}d"))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn open_license_overview(app: &mut App) -> Result<()> {
+    let license = fetch_license(&app.client, &app.es_url)?;
+    app.license = Some(license.clone());
+    let usage = fetch_xpack_usage(&app.client, &app.es_url)?;
+    let label_style = Style::default().fg(Color::Gray);
+    let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("type: ", label_style),
+            Span::styled(
+                text(&license.license_type),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("status: ", label_style),
+            Span::raw(text(&license.status)),
+        ]),
+        Line::from(vec![
+            Span::styled("issued to: ", label_style),
+            Span::raw(text(&license.issued_to)),
+        ]),
+        Line::from(vec![
+            Span::styled("expires: ", label_style),
+            Span::raw(
+                license
+                    .expiry_date_in_millis
+                    .map_or("never".to_string(), |millis| app.time_zone.format(millis)),
+            ),
+        ]),
+    ];
+    if let Some(warning) = license_warning(&license, now_millis()) {
+        lines.push(Line::from(Span::styled(
+            warning,
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("feature usage:", label_style)));
+    for (feature, details) in usage.as_object().into_iter().flatten() {
+        let flag = |key: &str| details.get(key).and_then(Value::as_bool);
+        let (state, color) = match (flag("available"), flag("enabled")) {
+            (Some(false), _) => ("unavailable", Color::DarkGray),
+            (_, Some(true)) => ("enabled", Color::Green),
+            (_, Some(false)) => ("disabled", Color::Gray),
+            _ => ("-", Color::Gray),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("  {feature:<28}")),
+            Span::styled(state, Style::default().fg(color)),
+        ]));
+    }
+    app.info_panel = Some(InfoPanel::new("License", lines));
+    Ok(())
+}
+
+pub(crate) fn open_master_overview(app: &mut App) -> Result<()> {
+    let state = fetch_coordination_state(&app.client, &app.es_url)?;
+    let node_name = |id: &str| {
+        state
+            .pointer(&format!("/nodes/{id}/name"))
+            .and_then(Value::as_str)
+            .map_or_else(|| id.to_string(), |name| format!("{name} ({id})"))
+    };
+    let label_style = Style::default().fg(Color::Gray);
+    let coordination = state.pointer("/metadata/cluster_coordination");
+    let master_id = state.get("master_node").and_then(Value::as_str);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("elected master: ", label_style),
+            Span::styled(
+                master_id.map_or("none".to_string(), node_name),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("term: ", label_style),
+            Span::raw(
+                coordination
+                    .and_then(|value| value.get("term"))
+                    .map_or("-".to_string(), json_value_compact),
+            ),
+        ]),
+        Line::from(""),
+    ];
+    let id_list = |key: &str| -> Vec<String> {
+        coordination
+            .and_then(|value| value.get(key))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect()
+    };
+    let committed = id_list("last_committed_config");
+    let accepted = id_list("last_accepted_config");
+    lines.push(Line::from(Span::styled(
+        format!("voting configuration ({} nodes):", committed.len()),
+        label_style,
+    )));
+    for id in &committed {
+        let marker = if Some(id.as_str()) == master_id {
+            " *"
+        } else {
+            ""
+        };
+        lines.push(Line::from(format!("  {}{marker}", node_name(id))));
+    }
+    if committed.len() % 2 == 0 && !committed.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  even number of voting nodes",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    if accepted != committed {
+        lines.push(Line::from(Span::styled(
+            "  voting configuration change in progress",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    let exclusions: Vec<String> = coordination
+        .and_then(|value| value.get("voting_config_exclusions"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+            entry
+                .get("node_name")
+                .and_then(Value::as_str)
+                .or_else(|| entry.get("node_id").and_then(Value::as_str))
+                .unwrap_or("-")
+                .to_string()
+        })
+        .collect();
+    lines.push(Line::from(vec![
+        Span::styled("voting exclusions: ", label_style),
+        Span::raw(if exclusions.is_empty() {
+            "-".to_string()
+        } else {
+            exclusions.join(", ")
+        }),
+    ]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "master changes seen this session:",
+        label_style,
+    )));
+    for change in app.master_changes.iter().rev() {
+        lines.push(Line::from(format!(
+            "  {} {}",
+            app.time_zone.format(change.at_millis),
+            change.node
+        )));
+    }
+    if app.master_changes.len() > 1 {
+        lines.push(Line::from(Span::styled(
+            format!("  {} master changes", app.master_changes.len() - 1),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    app.info_panel = Some(InfoPanel::new("Master and voting configuration", lines));
+    Ok(())
+}
+
+pub(crate) fn open_event_timeline(app: &mut App) {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = Vec::new();
+    if app.events.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "no cluster events seen this session",
+            label_style,
+        )));
+    }
+    for event in &app.events {
+        let style = match event.level {
+            EventLevel::Info => Style::default(),
+            EventLevel::Warn => Style::default().fg(Color::Yellow),
+            EventLevel::Error => Style::default().fg(Color::Red),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{} ", app.time_zone.format(event.at_millis)),
+                label_style,
+            ),
+            Span::styled(event.message.clone(), style),
+        ]));
+    }
+    let mut panel = InfoPanel::new(format!("Cluster events ({})", app.events.len()), lines);
+    panel.scroll = app.events.len().saturating_sub(AGG_SCROLL_MARGIN) as u16;
+    app.info_panel = Some(panel);
+}
+
+pub(crate) fn open_index_actions(app: &mut App, selected: usize) -> Result<()> {
+    if app.scope_kind != ScopeKind::Indices {
+        anyhow::bail!("index actions need an index selected in the Indices tab");
+    }
+    let index = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no index selected")?;
+    let selected = selected.min(IndexAction::ALL.len() - 1);
+    let label_style = Style::default().fg(Color::Gray);
+    let lines = IndexAction::ALL
+        .iter()
+        .enumerate()
+        .map(|(position, action)| {
+            let marker = if position == selected { "> " } else { "  " };
+            let mut style = if action.destructive() {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            if position == selected {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            let mut spans = vec![Span::styled(format!("{marker}{}", action.as_str()), style)];
+            if action.destructive() {
+                spans.push(Span::styled("  (type the name to confirm)", label_style));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    app.info_panel = Some(InfoPanel::new(format!("Index actions: {index}"), lines));
+    app.prompt = Some(Prompt::IndexActions { index, selected });
+    Ok(())
+}
+
+pub(crate) fn confirm_index_action(app: &mut App) -> Result<()> {
+    let Some(Prompt::IndexActions { index, selected }) = app.prompt.take() else {
+        return Ok(());
+    };
+    app.info_panel = None;
+    let action = IndexAction::ALL[selected];
+    if !action.destructive() {
+        return execute_index_action(app, action, &index);
+    }
+    let lines = vec![
+        Line::from(Span::styled(
+            format!("{} index {index}?", action.as_str()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from("Type the index name and press Enter to confirm. Esc cancels."),
+    ];
+    app.info_panel = Some(InfoPanel::new(
+        format!("Confirm {}", action.as_str()),
+        lines,
+    ));
+    app.pending_index_action = Some((action, index));
+    app.confirm_edit.clear();
+    app.input_mode = InputMode::ConfirmIndexAction;
+    Ok(())
+}
+
+pub(crate) fn submit_index_confirmation(app: &mut App) -> Result<()> {
+    app.info_panel = None;
+    let typed = std::mem::take(&mut app.confirm_edit);
+    let Some((action, index)) = app.pending_index_action.take() else {
+        return Ok(());
+    };
+    if typed.trim() != index {
+        anyhow::bail!("typed name does not match {index}, nothing was changed");
+    }
+    execute_index_action(app, action, &index)
+}
+
+pub(crate) fn report_action_result(app: &mut App, label: &str, result: Result<()>) {
+    if let Err(err) = result {
+        let message = format!("{label}: {err:#}");
+        app.show_toast(message.clone(), true);
+        app.last_error = Some(message);
+    }
+}
+
+fn execute_index_action(app: &mut App, action: IndexAction, index: &str) -> Result<()> {
+    let response = run_index_action(&app.client, &app.es_url, index, action)?;
+    if let Some(task) = response.get("task").and_then(Value::as_str) {
+        app.start_job(
+            format!("{} {index}", action.as_str()),
+            Some(task.to_string()),
+        );
+        app.show_toast(
+            format!("{} {index}: running as task {task}", action.as_str()),
+            false,
+        );
+        return Ok(());
+    }
+    app.show_toast(
+        format!(
+            "{} {index}: {}",
+            action.as_str(),
+            index_action_summary(&response)
+        ),
+        false,
+    );
+    if action.changes_listing() {
+        refresh_indices(app)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn run_alias_command(app: &mut App, args: &[&str]) -> Result<()> {
+    let ops = alias_ops(app, args)?;
+    let lines = ops
+        .iter()
+        .map(|op| match op {
+            AliasOp::Add {
+                index,
+                alias,
+                write,
+            } => {
+                let write = if *write { " (write index)" } else { "" };
+                Line::from(Span::styled(
+                    format!("+ {alias} -> {index}{write}"),
+                    Style::default().fg(Color::Green),
+                ))
+            }
+            AliasOp::Remove { index, alias } => Line::from(Span::styled(
+                format!("- {alias} -> {index}"),
+                Style::default().fg(Color::Red),
+            )),
+        })
+        .chain([
+            Line::from(""),
+            Line::from(Span::styled(
+                "applied atomically with POST /_aliases",
+                Style::default().fg(Color::Gray),
+            )),
+        ])
+        .collect();
+    app.info_panel = Some(InfoPanel::new("Alias changes", lines));
+    app.prompt = Some(Prompt::AliasChange(ops));
+    Ok(())
+}
+
+fn selected_of_kind(app: &App, kind: ScopeKind) -> Option<String> {
+    (app.scope_kind == kind)
+        .then(|| app.selected_scope_name().map(|name| name.to_string()))
+        .flatten()
+}
+
+pub(crate) fn alias_ops(app: &App, args: &[&str]) -> Result<Vec<AliasOp>> {
+    const USAGE: &str =
+        "usage: alias add <alias> [index] | remove [alias] [index] | switch [alias] <index>";
+    let selected_alias = selected_of_kind(app, ScopeKind::Aliases);
+    let selected_index = selected_of_kind(app, ScopeKind::Indices);
+    let targets = |alias: &str| {
+        app.aliases
+            .iter()
+            .find(|entry| entry.alias == alias)
+            .map(|entry| entry.targets.clone())
+            .unwrap_or_default()
+    };
+    let ops = match args {
+        ["add", alias, rest @ ..] if rest.len() <= 1 => {
+            let index = rest
+                .first()
+                .map(|index| index.to_string())
+                .or(selected_index)
+                .context("name the index or select one in the Indices tab")?;
+            vec![AliasOp::Add {
+                index,
+                alias: alias.to_string(),
+                write: false,
+            }]
+        }
+        ["remove", rest @ ..] if rest.len() <= 2 => {
+            let alias = rest
+                .first()
+                .map(|alias| alias.to_string())
+                .or(selected_alias)
+                .context("name the alias or select one in the Aliases tab")?;
+            let indices = match rest
+                .get(1)
+                .map(|index| index.to_string())
+                .or(selected_index)
+            {
+                Some(index) => vec![index],
+                None => targets(&alias)
+                    .into_iter()
+                    .map(|target| target.index_name)
+                    .collect(),
+            };
+            if indices.is_empty() {
+                anyhow::bail!("{alias} does not point at any index");
+            }
+            indices
+                .into_iter()
+                .map(|index| AliasOp::Remove {
+                    index,
+                    alias: alias.clone(),
+                })
+                .collect()
+        }
+        ["switch", index] | ["switch", _, index] => {
+            let alias = match args {
+                [_, alias, _] => alias.to_string(),
+                _ => selected_alias.context("name the alias or select one in the Aliases tab")?,
+            };
+            let current = targets(&alias);
+            if current.len() == 1 && current[0].index_name == *index {
+                anyhow::bail!("{alias} already points at {index}");
+            }
+            let write = current.iter().any(|target| target.is_write_index);
+            let mut ops: Vec<AliasOp> = current
+                .into_iter()
+                .filter(|target| target.index_name != *index)
+                .map(|target| AliasOp::Remove {
+                    index: target.index_name,
+                    alias: alias.clone(),
+                })
+                .collect();
+            ops.push(AliasOp::Add {
+                index: index.to_string(),
+                alias,
+                write,
+            });
+            ops
+        }
+        _ => anyhow::bail!(USAGE),
+    };
+    Ok(ops)
+}
+
+pub(crate) fn apply_alias_change(app: &mut App) -> Result<()> {
+    let Some(Prompt::AliasChange(ops)) = app.prompt.take() else {
+        return Ok(());
+    };
+    app.info_panel = None;
+    let response = update_aliases(&app.client, &app.es_url, &ops)?;
+    app.show_toast(
+        format!(
+            "aliases: {} change(s) {}",
+            ops.len(),
+            index_action_summary(&response)
+        ),
+        false,
+    );
+    refresh_aliases(app)
+}
+
+const ALLOCATION_DECISION_LIMIT: usize = 8;
+
+pub(crate) fn open_health_detail(app: &mut App) -> Result<()> {
+    let health = fetch_health_detail(&app.client, &app.es_url)?;
+    let title = format!(
+        "Cluster health: {} [{}]",
+        health.cluster_name, health.status
+    );
+    app.info_panel = Some(InfoPanel::new(title, health_detail_lines(&health)));
+    app.prompt = Some(Prompt::HealthDetail {
+        unassigned: health.unassigned_shards,
+    });
+    Ok(())
+}
+
+pub(crate) fn health_detail_lines(health: &HealthDetail) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let count = |label: &str, value: u64, warn: bool| {
+        let style = if warn && value > 0 {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Line::from(vec![
+            Span::styled(format!("{label}: "), label_style),
+            Span::styled(value.to_string(), style),
+        ])
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("status: ", label_style),
+            Span::styled(health.status.clone(), status_style(&health.status)),
+            Span::styled(
+                format!(
+                    "  ({:.1}% shards active)",
+                    health.active_shards_percent_as_number
+                ),
+                label_style,
+            ),
+        ]),
+        count("nodes", health.number_of_nodes, false),
+        count("active shards", health.active_shards, false),
+        count("relocating shards", health.relocating_shards, true),
+        count("initializing shards", health.initializing_shards, true),
+        count("unassigned shards", health.unassigned_shards, true),
+        count("pending tasks", health.number_of_pending_tasks, true),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("indices ({})", health.indices.len()),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    let severity = |status: &str| match status {
+        "red" => 0,
+        "yellow" => 1,
+        "green" => 3,
+        _ => 2,
+    };
+    let mut indices: Vec<(&String, &IndexHealth)> = health.indices.iter().collect();
+    indices.sort_by_key(|(name, index)| (severity(&index.status), *name));
+    for (name, index) in indices {
+        let mut spans = vec![
+            Span::styled(
+                format!("  {:<6} ", index.status),
+                status_style(&index.status),
+            ),
+            Span::raw(name.clone()),
+            Span::styled(
+                format!(
+                    "  {}p/{}r active {}",
+                    index.number_of_shards, index.number_of_replicas, index.active_shards
+                ),
+                label_style,
+            ),
+        ];
+        for (label, value) in [
+            ("relocating", index.relocating_shards),
+            ("initializing", index.initializing_shards),
+            ("unassigned", index.unassigned_shards),
+        ] {
+            if value > 0 {
+                spans.push(Span::styled(
+                    format!(" {label} {value}"),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+pub(crate) fn open_allocation_explain(app: &mut App) -> Result<()> {
+    let Some(Prompt::HealthDetail { unassigned }) = app.prompt else {
+        return Ok(());
+    };
+    if unassigned == 0 {
+        anyhow::bail!("no unassigned shards to explain");
+    }
+    app.prompt = None;
+    app.info_panel = None;
+    let explain = fetch_allocation_explain(&app.client, &app.es_url)?;
+    let lines = allocation_explain_lines(&explain)
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    app.info_panel = Some(InfoPanel::new("Allocation explain", lines));
+    Ok(())
+}
+
+pub(crate) fn allocation_explain_lines(explain: &Value) -> Vec<String> {
+    let text =
+        |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+    let mut lines = Vec::new();
+    let shard = explain
+        .get("shard")
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+    let role = match explain.get("primary").and_then(Value::as_bool) {
+        Some(true) => "primary",
+        _ => "replica",
+    };
+    lines.push(format!(
+        "{} shard {shard} ({role}) is {}",
+        text(explain, "index").unwrap_or_else(|| "-".to_string()),
+        text(explain, "current_state").unwrap_or_else(|| "-".to_string())
+    ));
+    if let Some(info) = explain.get("unassigned_info") {
+        let reason = text(info, "reason").unwrap_or_else(|| "-".to_string());
+        let since = text(info, "at").unwrap_or_default();
+        lines.push(format!("reason: {reason} {since}").trim_end().to_string());
+        if let Some(details) = text(info, "details") {
+            lines.push(format!("details: {details}"));
+        }
+    }
+    if let Some(decision) = text(explain, "can_allocate") {
+        lines.push(format!("can allocate: {decision}"));
+    }
+    if let Some(explanation) = text(explain, "allocate_explanation") {
+        lines.push(explanation);
+    }
+    let decisions = explain
+        .get("node_allocation_decisions")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if !decisions.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("node decisions ({})", decisions.len()));
+    }
+    for node in decisions.iter().take(ALLOCATION_DECISION_LIMIT) {
+        lines.push(format!(
+            "  {}: {}",
+            text(node, "node_name").unwrap_or_else(|| "-".to_string()),
+            text(node, "node_decision").unwrap_or_else(|| "-".to_string())
+        ));
+        for decider in node
+            .get("deciders")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            lines.push(format!(
+                "    [{}] {}",
+                text(decider, "decider").unwrap_or_default(),
+                text(decider, "explanation").unwrap_or_default()
+            ));
+        }
+    }
+    if decisions.len() > ALLOCATION_DECISION_LIMIT {
+        lines.push(format!(
+            "  ... {} more nodes",
+            decisions.len() - ALLOCATION_DECISION_LIMIT
+        ));
+    }
+    lines
+}
+
+const INDEX_SETTING_KEYS: [(&str, &str); 8] = [
+    ("shards", "index.number_of_shards"),
+    ("replicas", "index.number_of_replicas"),
+    ("refresh interval", "index.refresh_interval"),
+    ("ILM policy", "index.lifecycle.name"),
+    ("rollover alias", "index.lifecycle.rollover_alias"),
+    ("codec", "index.codec"),
+    ("max result window", "index.max_result_window"),
+    ("default field", "index.query.default_field"),
+];
+
+pub(crate) fn open_index_settings(app: &mut App) -> Result<()> {
+    let name = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no index selected")?;
+    let settings = fetch_index_settings(&app.client, &app.es_url, &name)?;
+    app.info_panel = Some(InfoPanel::new(
+        format!("Settings {name}"),
+        index_settings_lines(&settings),
+    ));
+    Ok(())
+}
+
+pub(crate) fn index_settings_lines(settings: &IndexSettings) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut lines = Vec::new();
+    for (label, key) in INDEX_SETTING_KEYS {
+        let (value, default) = settings.get(key).unwrap_or(("-", false));
+        let mut spans = vec![
+            Span::styled(format!("{label}: "), label_style),
+            Span::raw(value.to_string()),
+        ];
+        if default {
+            spans.push(Span::styled(" (default)", label_style));
+        }
+        lines.push(Line::from(spans));
+    }
+    let analysis: Vec<(&String, &String)> = settings
+        .settings
+        .iter()
+        .filter(|(key, _)| key.starts_with("index.analysis."))
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "analysis",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    if analysis.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  no custom analysis settings",
+            label_style,
+        )));
+    }
+    for (key, value) in analysis {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {}: ", key.trim_start_matches("index.analysis.")),
+                label_style,
+            ),
+            Span::raw(value.clone()),
+        ]));
+    }
+    let shown = |key: &str| {
+        key.starts_with("index.analysis.") || INDEX_SETTING_KEYS.iter().any(|(_, k)| *k == key)
+    };
+    let other: Vec<(&String, &String)> = settings
+        .settings
+        .iter()
+        .filter(|(key, _)| !shown(key))
+        .collect();
+    if !other.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "other settings",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        for (key, value) in other {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {key}: "), label_style),
+                Span::raw(value.clone()),
+            ]));
+        }
+    }
+    lines
+}
+
+pub(crate) fn open_tsdb_overview(app: &mut App) -> Result<()> {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut names: Vec<&String> = app.tsdb.keys().collect();
+    names.sort();
+    let mut lines = Vec::new();
+    for name in names {
+        let info = &app.tsdb[name];
+        lines.push(Line::from(Span::styled(
+            name.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(vec![
+            Span::styled("  time bounds: ", label_style),
+            Span::raw(format!(
+                "{} .. {}",
+                info.start_time.as_deref().unwrap_or("-"),
+                info.end_time.as_deref().unwrap_or("-")
+            )),
+        ]));
+        let downsample = match (&info.downsample_status, &info.downsample_source) {
+            (Some(status), Some(source)) => format!("{status} (from {source})"),
+            (Some(status), None) => status.clone(),
+            (None, _) => "not downsampled".to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  downsampling: ", label_style),
+            Span::raw(downsample),
+        ]));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("No time-series (TSDB) indices"));
+    }
+    app.info_panel = Some(InfoPanel::new("TSDB indices", lines));
+    Ok(())
+}
+
+pub(crate) fn open_terms_lookup(app: &mut App, args: &[&str]) -> Result<()> {
+    let Some(field) = args.first() else {
+        anyhow::bail!("usage: terms <field> [prefix]");
+    };
+    app.terms_lookup = Some(TermsLookup {
+        field: field.to_string(),
+        prefix: args.get(1).copied().unwrap_or_default().to_string(),
+        terms: Vec::new(),
+        complete: false,
+        selected: 0,
+        error: None,
+    });
+    app.input_mode = InputMode::TermsPrefix;
+    refresh_terms_lookup(app);
+    Ok(())
+}
+
+pub(crate) fn refresh_terms_lookup(app: &mut App) {
+    let scope = app.selected_scope_name().map(|name| name.to_string());
+    let Some(lookup) = app.terms_lookup.as_mut() else {
+        return;
+    };
+    let Some(scope) = scope else {
+        lookup.error = Some("no scope selected".to_string());
+        return;
+    };
+    match fetch_terms_enum(
+        &app.client,
+        &app.es_url,
+        &scope,
+        &lookup.field,
+        &lookup.prefix,
+    ) {
+        Ok(response) => {
+            lookup.terms = response.terms;
+            lookup.complete = response.complete;
+            lookup.selected = 0;
+            lookup.error = None;
+        }
+        Err(err) => {
+            lookup.terms.clear();
+            lookup.error = Some(format!("{err:#}"));
+        }
+    }
+}
+
+pub(crate) fn shift_terms_selection(app: &mut App, delta: isize) {
+    let Some(lookup) = app.terms_lookup.as_mut() else {
+        return;
+    };
+    if lookup.terms.is_empty() {
+        return;
+    }
+    let len = lookup.terms.len();
+    lookup.selected = if delta >= 0 {
+        (lookup.selected + 1) % len
+    } else if lookup.selected == 0 {
+        len - 1
+    } else {
+        lookup.selected - 1
+    };
+}
+
+pub(crate) fn apply_selected_term(app: &mut App) {
+    let Some(lookup) = app.terms_lookup.take() else {
+        return;
+    };
+    let Some(term) = lookup.terms.get(lookup.selected) else {
+        return;
+    };
+    let clause = format!("{}:\"{}\"", lookup.field, term.replace('"', "\\\""));
+    app.query = if app.query.trim().is_empty() {
+        clause
+    } else {
+        format!("{} {clause}", app.query.trim())
+    };
+    app.reset_docs_paging();
+    handle_docs_refresh(app);
+}
+
+pub(crate) fn parse_bench_options(args: &[&str]) -> Result<BenchOptions> {
+    let mut options = BenchOptions {
+        runs: 10,
+        warmup: 0,
+        request_cache: None,
+    };
+    for arg in args {
+        match arg.split_once('=') {
+            None => options.runs = arg.parse().context("runs must be a number")?,
+            Some(("warmup", value)) => {
+                options.warmup = value.parse().context("warmup must be a number")?
+            }
+            Some(("cache", "on")) => options.request_cache = Some(true),
+            Some(("cache", "off")) => options.request_cache = Some(false),
+            Some(_) => anyhow::bail!("unknown option {arg:?}, expected N warmup=N cache=on|off"),
+        }
+    }
+    if options.runs == 0 || options.runs > 1000 {
+        anyhow::bail!("runs must be between 1 and 1000");
+    }
+    if options.warmup > 100 {
+        anyhow::bail!("warmup must be at most 100");
+    }
+    Ok(options)
+}
+
+pub(crate) fn run_benchmark(app: &mut App, options: BenchOptions) -> Result<()> {
+    if app.bench_running() {
+        anyhow::bail!("a benchmark is already running, stop it with :bench cancel");
+    }
+    let scope = app
+        .selected_scope_name()
+        .map(|name| name.to_string())
+        .context("no scope selected")?;
+    let params = SearchParams {
+        request_cache: options.request_cache,
+        ..app.search_params()
+    };
+    let client = app.client.clone();
+    let es_url = app.es_url.clone();
+    let query = app.query.clone();
+    let (from, size) = (app.docs_from, app.docs_size);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    app.start_job(format!("bench {scope}"), None);
+    if let Some(job) = app.jobs.last_mut() {
+        job.bench = Some(receiver);
+    }
+    std::thread::spawn(move || {
+        let total = options.warmup + options.runs;
+        let mut report = BenchReport::default();
+        for run in 0..total {
+            let started = Instant::now();
+            let result = fetch_search_took(&client, &es_url, &scope, from, size, &query, &params);
+            let latency = started.elapsed().as_millis() as u64;
+            let progress = BenchEvent::Progress {
+                done: run as u64 + 1,
+                total: total as u64,
+            };
+            if sender.send(progress).is_err() {
+                return;
+            }
+            if run < options.warmup {
+                continue;
+            }
+            match result {
+                Ok(took) => {
+                    report.took_ms.push(took);
+                    report.latency_ms.push(latency);
+                }
+                Err(err) => {
+                    report.errors += 1;
+                    report.first_error.get_or_insert_with(|| format!("{err:#}"));
+                }
+            }
+        }
+        let lines = bench_report_lines(&scope, &query, &options, &report);
+        let _ = sender.send(BenchEvent::Done(lines));
+    });
+    Ok(())
+}
+
+pub(crate) fn cancel_benchmark(app: &mut App) -> Result<()> {
+    if !app.bench_running() {
+        anyhow::bail!("no benchmark is running");
+    }
+    app.jobs.retain(|job| job.bench.is_none());
+    Ok(())
+}
+
+pub(crate) fn bench_report_lines(
+    scope: &str,
+    query: &str,
+    options: &BenchOptions,
+    report: &BenchReport,
+) -> Vec<Line<'static>> {
+    let label_style = Style::default().fg(Color::Gray);
+    let cache = match options.request_cache {
+        Some(true) => "on",
+        Some(false) => "off",
+        None => "default",
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("scope: ", label_style),
+            Span::raw(scope.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("query: ", label_style),
+            Span::raw(if query.is_empty() { "-" } else { query }.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("runs: ", label_style),
+            Span::raw(format!(
+                "{} (warmup {}, request_cache {cache}, errors {})",
+                options.runs, options.warmup, report.errors
+            )),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(err) = &report.first_error {
+        lines.push(Line::from(vec![
+            Span::styled("first error: ", label_style),
+            Span::styled(err.clone(), Style::default().fg(Color::Red)),
+        ]));
+        lines.push(Line::from(""));
+    }
+    for (label, values) in [("took", &report.took_ms), ("client", &report.latency_ms)] {
+        let mut sorted = values.clone();
+        sorted.sort_unstable();
+        let text = match (sorted.first(), sorted.last()) {
+            (Some(min), Some(max)) => format!(
+                "min {min}ms  median {}ms  p95 {}ms  max {max}ms",
+                percentile(&sorted, 50.0),
+                percentile(&sorted, 95.0)
+            ),
+            _ => "no successful runs".to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{label:<8}"), label_style),
+            Span::raw(text),
+        ]));
+    }
+    lines
+}
+
+pub(crate) fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_list_accepts_commas_newlines_and_quotes() {
+        assert_eq!(
+            parse_id_list("a1, b2\n\"c3\"\n\na1 'd4'"),
+            ["a1", "b2", "c3", "d4"]
+        );
+        assert!(parse_id_list(" ,\n").is_empty());
+    }
+
+    #[test]
+    fn drain_refuses_transient_excludes_and_keeps_persistent_ones() {
+        let nodes = ["es-1".to_string(), "es-2".to_string()];
+        let transient = ExcludedNodes {
+            persistent: vec!["es-1".to_string()],
+            transient: vec!["es-3".to_string()],
+        };
+        let err = drain_exclusion(&transient, "remove", "es-1", &nodes).unwrap_err();
+        assert!(err.to_string().contains("transient"), "{err}");
+        let excluded = ExcludedNodes {
+            persistent: vec!["es-1".to_string()],
+            transient: Vec::new(),
+        };
+        assert_eq!(
+            drain_exclusion(&excluded, "add", "es-2", &nodes).unwrap(),
+            ["es-1", "es-2"]
+        );
+        assert!(drain_exclusion(&excluded, "add", "es-1", &nodes).is_err());
+        assert!(drain_exclusion(&excluded, "add", "es-9", &nodes).is_err());
+        assert!(drain_exclusion(&excluded, "remove", "es-2", &nodes).is_err());
+        assert!(
+            drain_exclusion(&excluded, "remove", "es-1", &nodes)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn drain_overview_marks_transient_and_drain_progress() {
+        let excluded = ExcludedNodes {
+            persistent: vec!["es-1".to_string(), "es-2".to_string()],
+            transient: vec!["es-9".to_string()],
+        };
+        let row = |node: &str, shards: &str| AllocationRow {
+            node: Some(node.to_string()),
+            shards: Some(shards.to_string()),
+        };
+        let allocation = [row("es-1", "0"), row("es-2", "4"), row("es-3", "7")];
+        let shards = [ShardRow {
+            node: Some("es-2 -> 10.0.0.3 abc es-3".to_string()),
+            state: Some("RELOCATING".to_string()),
+        }];
+        let text: Vec<String> = drain_overview_lines(&excluded, &allocation, &shards)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(text[0], format!("{EXCLUDE_NAME_SETTING}: es-1,es-2"));
+        assert!(text[1].starts_with("transient: es-9"), "{}", text[1]);
+        assert!(
+            text[3].ends_with("shards=0 relocating=0 drained"),
+            "{}",
+            text[3]
+        );
+        assert!(
+            text[4].ends_with("shards=4 relocating=1 draining"),
+            "{}",
+            text[4]
+        );
+        assert!(text[5].ends_with("shards=7 relocating=0"), "{}", text[5]);
+    }
+
+    #[test]
+    fn reroute_dry_run_flags_any_no_decision() {
+        let response = serde_json::json!({
+            "explanations": [{
+                "decisions": [
+                    { "decider": "move_allocation", "decision": "YES", "explanation": "ok" },
+                    { "decider": "same_shard", "decision": "NO", "explanation": "copy exists" },
+                ]
+            }]
+        });
+        assert!(reroute_rejected(&response));
+        let text: Vec<String> = reroute_explanation_lines(&response)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            text,
+            ["YES move_allocation: ok", "NO  same_shard: copy exists"]
+        );
+        assert!(!reroute_rejected(
+            &serde_json::json!({ "explanations": [] })
+        ));
+        assert!(reroute_explanation_lines(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn reroute_rejects_bad_input_and_apply_without_a_dry_run() {
+        let mut app = test_app_with_index("logs-1");
+        let err = run_reroute_command(&mut app, &["apply"]).unwrap_err();
+        assert_eq!(err.to_string(), "nothing to apply, run a dry-run first");
+        let err = run_reroute_command(&mut app, &["move", "x", "es-1", "es-2"]).unwrap_err();
+        assert_eq!(err.to_string(), "shard must be a number");
+        assert!(run_reroute_command(&mut app, &["cancel", "0"]).is_err());
+        assert_eq!(app.pending_reroute, None);
+    }
+
+    #[test]
+    fn search_params_set_clear_and_reject_extra_values() {
+        let mut app = test_app();
+        set_search_param(&mut app, "preference", &["_local"]).unwrap();
+        set_search_param(&mut app, "routing", &["user-1"]).unwrap();
+        let params = app.search_params();
+        assert_eq!(params.preference.as_deref(), Some("_local"));
+        assert_eq!(params.routing.as_deref(), Some("user-1"));
+        let err = set_search_param(&mut app, "routing", &["a", "b"]).unwrap_err();
+        assert_eq!(err.to_string(), "routing must be a single value");
+        assert!(set_search_param(&mut app, "preference", &[]).is_err());
+        assert_eq!(app.routing.as_deref(), Some("user-1"));
+        set_search_param(&mut app, "preference", &["clear"]).unwrap();
+        assert_eq!(app.preference, None);
+    }
+
+    #[test]
+    fn fuzzy_toggles_and_leaves_empty_queries_as_match_all() {
+        let mut app = test_app();
+        set_fuzzy(&mut app, &[]).unwrap();
+        assert!(app.fuzzy);
+        let err = set_fuzzy(&mut app, &["maybe"]).unwrap_err();
+        assert_eq!(err.to_string(), "usage: fuzzy [on|off]");
+        assert!(app.fuzzy);
+        let params = app.search_params();
+        assert_eq!(query_body("  ", &params), search_body("")["query"]);
+        assert_eq!(query_body("eror", &params)["multi_match"]["query"], "eror");
+        set_fuzzy(&mut app, &["off"]).unwrap();
+        assert!(query_body("eror", &app.search_params())["query_string"].is_object());
+    }
+
+    #[test]
+    fn mget_lines_report_found_missing_and_failed_ids() {
+        let found = DocEntry {
+            version: Some(3),
+            ..test_doc("logs-1", "a1", serde_json::json!({}))
+        };
+        let results = [
+            ("a1".to_string(), MgetResult::Found(found)),
+            ("b2".to_string(), MgetResult::Missing),
+            (
+                "c3".to_string(),
+                MgetResult::Failed("no such index".to_string()),
+            ),
+        ];
+        let text: Vec<String> = mget_lines(&results)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "1 of 3 found",
+                "",
+                "found   a1 logs-1 v3",
+                "missing b2 ",
+                "error   c3 no such index",
+            ]
+        );
+    }
+
+    #[test]
+    fn mget_rejects_empty_lists_and_unreadable_files() {
+        let mut app = test_app();
+        let err = open_mget_lookup(&mut app, &["a1"]).unwrap_err();
+        assert_eq!(err.to_string(), "no scope selected");
+        let mut app = test_app_with_index("logs-1");
+        let err = open_mget_lookup(&mut app, &[",", "''"]).unwrap_err();
+        assert_eq!(err.to_string(), "no ids given");
+        let err = open_mget_lookup(&mut app, &["@/nonexistent/ids.txt"]).unwrap_err();
+        assert_eq!(err.to_string(), "failed to read /nonexistent/ids.txt");
+        assert!(app.info_panel.is_none());
+    }
+
+    #[test]
+    fn favorites_picker_clamps_and_rejects_stale_entries() {
+        let mut app = test_app_with_index("logs-1");
+        open_favorite_picker(&mut app, 3);
+        assert_eq!(app.prompt, Some(Prompt::PickFavorite(0)));
+        assert!(select_favorite(&mut app, 0).is_err());
+        app.state.favorites = vec![app.note_key("logs-1"), app.note_key("gone")];
+        open_favorite_picker(&mut app, 5);
+        assert_eq!(app.prompt, Some(Prompt::PickFavorite(1)));
+        let err = select_favorite(&mut app, 1).unwrap_err();
+        assert_eq!(err.to_string(), "gone no longer exists on this cluster");
+        let err = select_favorite(&mut app, 2).unwrap_err();
+        assert_eq!(err.to_string(), "no favorite selected");
+        assert!(app.nav_back.is_empty());
+    }
+
+    #[test]
+    fn sample_takes_a_seed_and_rejects_garbage() {
+        let mut app = test_app();
+        run_sample_command(&mut app, &["42"]).unwrap();
+        assert_eq!(app.sample_seed, Some(42));
+        let err = run_sample_command(&mut app, &["-1"]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid seed \"-1\"");
+        assert!(run_sample_command(&mut app, &["on", "7"]).is_err());
+        assert_eq!(app.sample_seed, Some(42));
+        run_sample_command(&mut app, &[]).unwrap();
+        assert_eq!(app.sample_seed, None);
+    }
+
+    #[test]
+    fn sort_command_rejects_bad_orders_and_clears() {
+        let mut app = test_app();
+        run_sort_command(&mut app, &["@timestamp", "asc"]).unwrap();
+        assert_eq!(
+            app.sort,
+            Some(SortSpec {
+                field: "@timestamp".to_string(),
+                order: SortOrder::Asc,
+            })
+        );
+        let err = run_sort_command(&mut app, &["took", "up"]).unwrap_err();
+        assert_eq!(err.to_string(), "usage: sort [<field> [asc|desc] | clear]");
+        assert!(run_sort_command(&mut app, &["a", "asc", "b"]).is_err());
+        assert_eq!(
+            app.sort.as_ref().map(|sort| sort.field.as_str()),
+            Some("@timestamp")
+        );
+        run_sort_command(&mut app, &["clear"]).unwrap();
+        assert_eq!(app.sort, None);
+    }
+
+    #[test]
+    fn views_reject_unknown_names_and_load_patterns_as_scopes() {
+        let mut app = test_app_with_index("logs-1");
+        let err = load_view(&mut app, "errors").unwrap_err();
+        assert_eq!(err.to_string(), "no saved view named \"errors\"");
+        assert!(delete_view(&mut app, "errors").is_err());
+        app.state.saved_views = vec![SavedView {
+            name: "errors".to_string(),
+            scope: "logs-*".to_string(),
+            query: "level:error".to_string(),
+            dsl: false,
+            columns: Vec::new(),
+            sort: None,
+        }];
+        load_view(&mut app, "errors").unwrap();
+        assert_eq!(app.scope_pattern.as_deref(), Some("logs-*"));
+        assert_eq!(app.query, "level:error");
+    }
+
+    #[test]
+    fn index_settings_mark_defaults_and_group_analysis() {
+        let settings = IndexSettings {
+            settings: BTreeMap::from([
+                ("index.number_of_shards".to_string(), "3".to_string()),
+                (
+                    "index.analysis.analyzer.folding.tokenizer".to_string(),
+                    "standard".to_string(),
+                ),
+                ("index.uuid".to_string(), "abc".to_string()),
+            ]),
+            defaults: BTreeMap::from([("index.refresh_interval".to_string(), "1s".to_string())]),
+        };
+        let text: Vec<String> = index_settings_lines(&settings)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(text[0], "shards: 3");
+        assert_eq!(text[2], "refresh interval: 1s (default)");
+        assert_eq!(text[3], "ILM policy: -");
+        assert!(text.contains(&"  analyzer.folding.tokenizer: standard".to_string()));
+        assert!(text.contains(&"  index.uuid: abc".to_string()));
+    }
+
+    #[test]
+    fn dedup_groups_count_identical_fingerprints() {
+        let doc = |message: &str, host: &str| serde_json::json!({ "message": message, "host": { "name": host } });
+        let docs = [
+            doc("timeout", "a"),
+            doc("ok", "a"),
+            doc("timeout", "a"),
+            doc("timeout", "b"),
+        ];
+        let fields = ["message".to_string(), "host.name".to_string()];
+        let groups = dedup_groups(&docs, &fields);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].values, ["timeout", "a"]);
+        assert_eq!(groups[0].count, 2);
+        assert_eq!(dedup_groups(&docs, &fields[..1])[0].count, 3);
+        let missing = [
+            serde_json::json!({ "message": "timeout" }),
+            serde_json::json!({}),
+        ];
+        let groups = dedup_groups(&missing, &fields);
+        assert_eq!(groups[0].values, ["timeout", "-"]);
+        assert_eq!(groups[1].values, ["-", "-"]);
+        assert!(dedup_groups(&[], &fields).is_empty());
+    }
+
+    #[test]
+    fn lucene_fields_lists_expanded_fields_once() {
+        assert_eq!(
+            lucene_fields(
+                "+(message:error | host.name:error | message:error) +ConstantScore(@timestamp:[1 TO 2])"
+            ),
+            ["message", "host.name", "@timestamp"]
+        );
+        assert!(lucene_fields("MatchAllDocsQuery").is_empty());
+    }
+
+    #[test]
+    fn trace_lines_mark_the_origin_document() {
+        let app = test_app();
+        let doc = |index: &str, id: &str, service: &str| {
+            test_doc(
+                index,
+                id,
+                serde_json::json!({
+                    "@timestamp": "2024-01-15T08:30:00Z",
+                    "service": { "name": service },
+                    "trace": { "id": "t1" },
+                    "message": "handled",
+                }),
+            )
+        };
+        let docs = [
+            doc("traces-apm-default", "s1", "gateway"),
+            doc("logs-app", "l1", "checkout"),
+        ];
+        let text: Vec<String> =
+            trace_lines(&app, &docs, &("logs-app".to_string(), "l1".to_string()))
+                .iter()
+                .map(|line| line.to_string())
+                .collect();
+        assert_eq!(
+            text[0],
+            "2 correlated document(s) across 2 index(es), oldest first"
+        );
+        assert!(text[2].starts_with("  2024-01-15T08:30:00Z gateway"));
+        assert!(text[4].starts_with("> 2024-01-15T08:30:00Z checkout"));
+        assert_eq!(text[5].trim_end(), "    logs-app l1");
+        let mut app = test_app();
+        let err = open_trace_follow(&mut app).unwrap_err();
+        assert_eq!(err.to_string(), "no document selected");
+        app.documents = vec![test_doc(
+            "logs-app",
+            "l2",
+            serde_json::json!({ "message": "x" }),
+        )];
+        app.docs_state.select(Some(0));
+        let err = open_trace_follow(&mut app).unwrap_err();
+        assert_eq!(err.to_string(), "document has no trace.id");
+    }
+
+    #[test]
+    fn permalinks_quote_ids_and_drop_credentials() {
+        let doc = test_doc("logs-2024.01", "it's/1", Value::Null);
+        let (curl, deep_link) = doc_permalinks("http://user:pw@localhost:9200", &doc).unwrap();
+        assert_eq!(
+            curl,
+            "curl -s 'http://localhost:9200/logs-2024.01/_doc/it'\\''s%2F1'"
+        );
+        assert_eq!(deep_link, "index-lens --open 'logs-2024.01:it'\\''s/1'");
+        let (curl, _) = doc_permalinks("https://proxy.local/es/", &doc).unwrap();
+        assert!(curl.starts_with("curl -s 'https://proxy.local/es/logs-2024.01/_doc/"));
+        assert!(doc_permalinks("localhost:9200", &doc).is_err());
+        let err = copy_permalink(&mut test_app()).unwrap_err();
+        assert_eq!(err.to_string(), "no document selected");
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(percentile(&sorted, 50.0), 5);
+        assert_eq!(percentile(&sorted, 95.0), 10);
+        assert_eq!(percentile(&[7], 95.0), 7);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[test]
+    fn parse_bench_options_reads_flags() {
+        let options = parse_bench_options(&["25", "warmup=3", "cache=off"]).unwrap();
+        assert_eq!(
+            options,
+            BenchOptions {
+                runs: 25,
+                warmup: 3,
+                request_cache: Some(false),
+            }
+        );
+        assert!(parse_bench_options(&["0"]).is_err());
+        assert!(parse_bench_options(&["cache=maybe"]).is_err());
+    }
+
+    #[test]
+    fn license_warning_starts_thirty_days_before_expiry() {
+        let day = 86_400_000;
+        let license = |expiry| LicenseInfo {
+            expiry_date_in_millis: expiry,
+            ..LicenseInfo::default()
+        };
+        assert_eq!(license_warning(&license(None), 0), None);
+        assert_eq!(license_warning(&license(Some(31 * day)), 0), None);
+        assert_eq!(
+            license_warning(&license(Some(30 * day)), 0),
+            Some("license expires in 30d".to_string())
+        );
+        assert_eq!(
+            license_warning(&license(Some(-1)), 0),
+            Some("license expired".to_string())
+        );
+    }
+
+    #[test]
+    fn doc_field_diff_lists_changed_added_and_removed_paths() {
+        let (same, changed) = doc_field_diff(
+            &serde_json::json!({ "host": "web-1", "http": { "status": 200 }, "tag": "a" }),
+            &serde_json::json!({ "host": "web-1", "http": { "status": 500 }, "user": "bob" }),
+        );
+        assert_eq!(same, 1);
+        let changes: Vec<_> = changed
+            .iter()
+            .map(|change| {
+                (
+                    change.path.as_str(),
+                    change.before.as_deref(),
+                    change.after.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("http.status", Some("200"), Some("500")),
+                ("tag", Some("a"), None),
+                ("user", None, Some("bob")),
+            ]
+        );
+        let doc = serde_json::json!({ "host": "web-1", "http": { "status": 200 } });
+        let (same, changed) = doc_field_diff(&doc, &doc);
+        assert_eq!(same, 2);
+        assert!(changed.is_empty());
+        let mut app = test_app();
+        assert_eq!(
+            open_basket_diff(&mut app, 0).unwrap_err().to_string(),
+            "mark at least two documents to compare"
+        );
+    }
+
+    #[test]
+    fn bucket_anomalies_flag_spikes_and_drops_against_recent_buckets() {
+        let counts = [100, 104, 98, 101, 400, 99, 102, 3, 100];
+        let anomalies = bucket_anomalies(&counts);
+        assert_eq!(anomalies[..4], [None, None, None, None]);
+        assert_eq!(anomalies[4], Some(Anomaly::Spike));
+        assert_eq!(anomalies[7], Some(Anomaly::Drop));
+        assert_eq!(anomalies.iter().flatten().count(), 2);
+        assert!(bucket_anomalies(&[0, 0, 0, 2]).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn allocation_explain_lines_summarize_the_shard_and_deciders() {
+        let explain = serde_json::json!({
+            "index": "logs-1",
+            "shard": 2,
+            "primary": false,
+            "current_state": "unassigned",
+            "unassigned_info": { "reason": "NODE_LEFT", "at": "2024-05-01T10:00:00Z" },
+            "can_allocate": "no",
+            "allocate_explanation": "cannot allocate because allocation is not permitted to any of the nodes",
+            "node_allocation_decisions": [{
+                "node_name": "es-1",
+                "node_decision": "no",
+                "deciders": [{
+                    "decider": "same_shard",
+                    "explanation": "a copy of this shard is already allocated to this node"
+                }]
+            }]
+        });
+        assert_eq!(
+            allocation_explain_lines(&explain),
+            [
+                "logs-1 shard 2 (replica) is unassigned",
+                "reason: NODE_LEFT 2024-05-01T10:00:00Z",
+                "can allocate: no",
+                "cannot allocate because allocation is not permitted to any of the nodes",
+                "",
+                "node decisions (1)",
+                "  es-1: no",
+                "    [same_shard] a copy of this shard is already allocated to this node",
+            ]
+        );
+        assert_eq!(
+            allocation_explain_lines(&serde_json::json!({})),
+            ["- shard 0 (replica) is -"]
+        );
+        let nodes: Vec<Value> = (0..ALLOCATION_DECISION_LIMIT + 2)
+            .map(|n| serde_json::json!({ "node_name": format!("es-{n}"), "node_decision": "yes" }))
+            .collect();
+        let lines = allocation_explain_lines(&serde_json::json!({
+            "index": "logs-1",
+            "primary": true,
+            "current_state": "started",
+            "node_allocation_decisions": nodes,
+        }));
+        assert_eq!(lines[0], "logs-1 shard 0 (primary) is started");
+        assert_eq!(lines[2], "node decisions (10)");
+        assert_eq!(lines.last().unwrap(), "  ... 2 more nodes");
+    }
+
+    #[test]
+    fn alias_switch_moves_every_target_and_keeps_the_write_flag() {
+        let mut app = crate::app::test_app();
+        app.aliases = vec![AliasEntry {
+            alias: "logs".to_string(),
+            targets: vec![
+                AliasTarget {
+                    index_name: "logs-1".to_string(),
+                    is_write_index: true,
+                    filtered: false,
+                },
+                AliasTarget {
+                    index_name: "logs-2".to_string(),
+                    is_write_index: false,
+                    filtered: false,
+                },
+            ],
+        }];
+        let ops = alias_ops(&app, &["switch", "logs", "logs-3"]).unwrap();
+        let bodies: Vec<Value> = ops.iter().map(AliasOp::body).collect();
+        assert_eq!(
+            bodies,
+            vec![
+                serde_json::json!({ "remove": { "index": "logs-1", "alias": "logs" } }),
+                serde_json::json!({ "remove": { "index": "logs-2", "alias": "logs" } }),
+                serde_json::json!({
+                    "add": { "index": "logs-3", "alias": "logs", "is_write_index": true }
+                }),
+            ]
+        );
+        assert!(alias_ops(&app, &["add", "recent"]).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub cluster: ClusterConfig,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub clusters: BTreeMap<String, ClusterConfig>,
+    pub display: DisplayConfig,
+    pub update: UpdateConfig,
+    pub stats: StatsConfig,
+    pub share: ShareConfig,
+    pub notes: NotesConfig,
+    pub tags: TagsConfig,
+    pub search: SearchConfig,
+    pub redaction: RedactionConfig,
+    pub trace: TraceConfig,
+    pub enrich: Vec<EnrichConfig>,
+    pub templates: Vec<RequestTemplate>,
+    pub highlight: Vec<HighlightRule>,
+    pub metrics: MetricsConfig,
+    pub refresh: RefreshConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_requests_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighlightRule {
+    pub field: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    pub color: String,
+}
+
+impl HighlightRule {
+    fn new(field: &str, values: &[&str], range: Option<(f64, f64)>, color: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            values: values.iter().map(|value| value.to_string()).collect(),
+            min: range.map(|range| range.0),
+            max: range.map(|range| range.1),
+            color: color.to_string(),
+        }
+    }
+
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        if self.min.is_some() || self.max.is_some() {
+            let number = value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|text| text.parse().ok()));
+            return number.is_some_and(|number| {
+                self.min.is_none_or(|min| number >= min) && self.max.is_none_or(|max| number <= max)
+            });
+        }
+        let text = match value {
+            serde_json::Value::String(text) => text.clone(),
+            serde_json::Value::Number(number) => number.to_string(),
+            serde_json::Value::Bool(flag) => flag.to_string(),
+            _ => return false,
+        };
+        self.values
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(&text))
+    }
+}
+
+pub fn default_highlight_rules() -> Vec<HighlightRule> {
+    const ERRORS: [&str; 6] = ["fatal", "critical", "emergency", "alert", "error", "err"];
+    const WARNINGS: [&str; 2] = ["warn", "warning"];
+    let mut rules = Vec::new();
+    for field in ["log.level", "level"] {
+        rules.push(HighlightRule::new(field, &ERRORS, None, "red"));
+        rules.push(HighlightRule::new(field, &WARNINGS, None, "yellow"));
+    }
+    for field in ["http.response.status_code", "status"] {
+        rules.push(HighlightRule::new(field, &[], Some((500.0, 599.0)), "red"));
+        rules.push(HighlightRule::new(
+            field,
+            &[],
+            Some((400.0, 499.0)),
+            "yellow",
+        ));
+    }
+    rules
+}
+
+impl Config {
+    pub fn highlight_rules(&self) -> Vec<HighlightRule> {
+        if self.highlight.is_empty() {
+            default_highlight_rules()
+        } else {
+            self.highlight.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestTemplate {
+    pub scope: String,
+    pub body: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrichConfig {
+    pub field: String,
+    pub index: String,
+    pub key: String,
+    pub display: String,
+}
+
+impl Default for EnrichConfig {
+    fn default() -> Self {
+        Self {
+            field: String::new(),
+            index: String::new(),
+            key: "_id".to_string(),
+            display: "name".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceConfig {
+    pub indices: Vec<String>,
+    pub size: u64,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            indices: vec!["traces-apm*".to_string(), "logs-*".to_string()],
+            size: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    pub fields: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        let fields = [
+            "*password*",
+            "*passwd*",
+            "*secret*",
+            "*token*",
+            "*api_key*",
+            "*apikey*",
+            "*authorization*",
+            "*cookie*",
+        ];
+        Self {
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    pub shard_warn_threshold: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_shard_requests: Option<u32>,
+    pub ignore_unavailable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminate_after: Option<u64>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            shard_warn_threshold: 200,
+            max_concurrent_shard_requests: None,
+            ignore_unavailable: false,
+            preference: None,
+            routing: None,
+            timeout: None,
+            terminate_after: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagsConfig {
+    pub meta_fields: Vec<String>,
+    pub settings_keys: Vec<String>,
+}
+
+impl Default for TagsConfig {
+    fn default() -> Self {
+        Self {
+            meta_fields: vec![
+                "owner".to_string(),
+                "team".to_string(),
+                "description".to_string(),
+            ],
+            settings_keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotesConfig {
+    pub remote: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    pub sample_size: usize,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            sample_size: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsConfig {
+    pub enabled: bool,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub raw_numbers: bool,
+    pub plain: bool,
+    pub low_refresh: bool,
+    pub ascii: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_buffered_docs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_view: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterConfig {
+    pub url: String,
+    pub auth: AuthMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    pub insecure: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:9200".to_string(),
+            auth: AuthMethod::None,
+            username: None,
+            password: None,
+            api_key: None,
+            insecure: false,
+            ca_cert: None,
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn with_env_credentials(&self) -> ClusterConfig {
+        self.with_credentials_from(|name| std::env::var(name).ok())
+    }
+
+    pub fn with_credentials_from(&self, var: impl Fn(&str) -> Option<String>) -> ClusterConfig {
+        let mut cluster = self.clone();
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        if let Some(api_key) = var("ES_API_KEY") {
+            cluster.auth = AuthMethod::ApiKey;
+            cluster.api_key = Some(api_key);
+        } else if let Some(username) = var("ES_USER") {
+            cluster.auth = AuthMethod::Basic;
+            cluster.username = Some(username);
+            cluster.password = var("ES_PASS");
+        }
+        cluster
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    #[default]
+    None,
+    Basic,
+    ApiKey,
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("INDEX_LENS_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|dir| dir.join("index-lens").join("config.toml"))
+}
+
+pub fn config_modified() -> Option<SystemTime> {
+    let path = config_path()?;
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+pub fn load_config() -> Result<Option<Config>> {
+    let Some(path) = config_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read config {}", path.display()))?;
+    let config =
+        toml::from_str(&text).with_context(|| format!("invalid config {}", path.display()))?;
+    Ok(Some(config))
+}
+
+pub fn save_config(config: &Config) -> Result<PathBuf> {
+    let path = config_path().context("no config directory available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let text = toml::to_string_pretty(config).context("failed to serialize config")?;
+    std::fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).ok();
+    }
+    Ok(path)
+}
+
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn build_client(cluster: &ClusterConfig) -> Result<reqwest::blocking::Client> {
+    let cluster = &cluster.with_env_credentials();
+    let mut headers = HeaderMap::new();
+    let authorization = match cluster.auth {
+        AuthMethod::None => None,
+        AuthMethod::Basic => {
+            let username = cluster.username.as_deref().unwrap_or_default();
+            let password = cluster.password.as_deref().unwrap_or_default();
+            let token = BASE64_STANDARD.encode(format!("{username}:{password}"));
+            Some(format!("Basic {token}"))
+        }
+        AuthMethod::ApiKey => {
+            let api_key = cluster.api_key.as_deref().unwrap_or_default();
+            Some(format!("ApiKey {api_key}"))
+        }
+    };
+    if let Some(value) = authorization {
+        let mut value =
+            HeaderValue::from_str(&value).context("credentials contain invalid characters")?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    let mut builder = reqwest::blocking::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .default_headers(headers)
+        .danger_accept_invalid_certs(cluster.insecure);
+    if let Some(path) = &cluster.ca_cert {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("failed to read CA certificate {}", path.display()))?;
+        let cert = reqwest::Certificate::from_pem(&pem).context("invalid CA certificate")?;
+        builder = builder.add_root_certificate(cert);
+    }
+    builder.build().context("failed to build http client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_credentials_override_config_with_api_key_first() {
+        let cluster = ClusterConfig::default();
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let basic = cluster.with_credentials_from(env(&[("ES_USER", "ann"), ("ES_PASS", "pw")]));
+        assert_eq!(basic.auth, AuthMethod::Basic);
+        assert_eq!(basic.username.as_deref(), Some("ann"));
+        assert_eq!(basic.password.as_deref(), Some("pw"));
+        let api_key =
+            cluster.with_credentials_from(env(&[("ES_USER", "ann"), ("ES_API_KEY", "k")]));
+        assert_eq!(api_key.auth, AuthMethod::ApiKey);
+        assert_eq!(api_key.api_key.as_deref(), Some("k"));
+        let empty = cluster.with_credentials_from(env(&[("ES_USER", "")]));
+        assert_eq!(empty, cluster);
+    }
+}
@@ -0,0 +1,159 @@
+use serde_json::Value;
+
+use crate::format::*;
+
+const ECS_SECTIONS: [&str; 14] = [
+    "event",
+    "log",
+    "service",
+    "message",
+    "error",
+    "trace",
+    "span",
+    "transaction",
+    "http",
+    "url",
+    "user",
+    "host",
+    "container",
+    "process",
+];
+
+pub(crate) fn ecs_field<'a>(source: &'a Value, path: &str) -> Option<&'a Value> {
+    if let Some(value) = source.get(path) {
+        return Some(value);
+    }
+    let mut current = source;
+    for part in path.split('.') {
+        current = current.get(part)?;
+    }
+    Some(current)
+}
+
+pub(crate) fn ecs_text(source: &Value, path: &str) -> Option<String> {
+    match ecs_field(source, path)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        Value::Array(values) => values.first().map(json_value_compact),
+        other => Some(json_value_compact(other)),
+    }
+}
+
+pub(crate) fn is_ecs_doc(source: &Value) -> bool {
+    if ecs_field(source, "ecs.version").is_some() {
+        return true;
+    }
+    ecs_field(source, "@timestamp").is_some()
+        && ecs_field(source, "message").is_some()
+        && (ecs_field(source, "log.level").is_some() || ecs_field(source, "service.name").is_some())
+}
+
+pub(crate) fn ecs_timestamp_millis(source: &Value) -> Option<i64> {
+    match ecs_field(source, "@timestamp")? {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => parse_timestamp_millis(text, now_millis()),
+        _ => None,
+    }
+}
+
+pub(crate) fn ecs_section_lines(source: &Value) -> Vec<String> {
+    let mut flat = Vec::new();
+    flatten_fields(source, None, &mut flat);
+    let section_of = |path: &str| {
+        let head = path.split(['.', '[']).next().unwrap_or_default();
+        ECS_SECTIONS
+            .iter()
+            .position(|section| *section == head)
+            .unwrap_or(ECS_SECTIONS.len())
+    };
+    flat.sort_by_key(|(path, _)| section_of(path));
+    let mut lines = Vec::new();
+    let mut current = None;
+    for (path, value) in flat {
+        let section = section_of(&path);
+        if current != Some(section) {
+            current = Some(section);
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            let title = ECS_SECTIONS.get(section).copied().unwrap_or("other");
+            lines.push(format!("[{title}]"));
+        }
+        lines.push(format!("  {path} = {value}"));
+    }
+    lines
+}
+
+pub(crate) fn flatten_fields(value: &Value, prefix: Option<&str>, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_none() => {
+            for (key, value) in map {
+                let next = match prefix {
+                    Some(prefix) => format!("{prefix}.{key}"),
+                    None => key.to_string(),
+                };
+                flatten_fields(value, Some(&next), out);
+            }
+        }
+        _ => out.push((
+            prefix.unwrap_or("<root>").to_string(),
+            json_value_compact(value),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn detects_nested_and_dotted_ecs_documents() {
+        let nested = json!({
+            "@timestamp": "2024-05-01T10:00:00Z",
+            "log": { "level": "error" },
+            "message": "boom",
+        });
+        let dotted = json!({
+            "@timestamp": "2024-05-01T10:00:00Z",
+            "service.name": "checkout",
+            "message": "ok",
+        });
+        assert!(is_ecs_doc(&nested));
+        assert!(is_ecs_doc(&dotted));
+        assert!(is_ecs_doc(&json!({ "ecs": { "version": "8.11" } })));
+        assert!(!is_ecs_doc(&json!({ "message": "GET /health 200" })));
+        assert_eq!(ecs_text(&nested, "log.level").as_deref(), Some("error"));
+        assert_eq!(
+            ecs_text(&dotted, "service.name").as_deref(),
+            Some("checkout")
+        );
+    }
+
+    #[test]
+    fn section_lines_group_by_ecs_field_set() {
+        let source = json!({
+            "host": { "name": "web-1" },
+            "log": { "level": "warn" },
+            "custom": 1,
+            "message": "slow",
+        });
+        assert_eq!(
+            ecs_section_lines(&source),
+            [
+                "[log]",
+                "  log.level = warn",
+                "",
+                "[message]",
+                "  message = slow",
+                "",
+                "[host]",
+                "  host.name = web-1",
+                "",
+                "[other]",
+                "  custom = 1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::client::*;
+use crate::ecs::*;
+use crate::redact::*;
+
+pub const EXPORT_PAGE_SIZE: u64 = 1000;
+const EXPORT_KEEP_ALIVE: &str = "2m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Ndjson,
+    Csv,
+    JsonArray,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::JsonArray),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportEvent {
+    Progress { written: u64, total: Option<u64> },
+    Done { written: u64 },
+    Failed(String),
+}
+
+pub struct ExportRequest {
+    pub client: reqwest::blocking::Client,
+    pub es_url: String,
+    pub index: String,
+    pub query: String,
+    pub params: SearchParams,
+    pub columns: Vec<String>,
+    pub redactor: Option<Redactor>,
+    pub format: ExportFormat,
+    pub path: PathBuf,
+}
+
+pub fn spawn_export(request: ExportRequest) -> Receiver<ExportEvent> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let event = match run_export(&request, &sender) {
+            Ok(written) => ExportEvent::Done { written },
+            Err(err) => ExportEvent::Failed(format!("{err:#}")),
+        };
+        let _ = sender.send(event);
+    });
+    receiver
+}
+
+fn run_export(request: &ExportRequest, sender: &Sender<ExportEvent>) -> Result<u64> {
+    let file = File::create(&request.path)
+        .with_context(|| format!("failed to create {}", request.path.display()))?;
+    let mut writer = ExportWriter::new(BufWriter::new(file), request.format, &request.columns);
+    let pit = open_pit(
+        &request.client,
+        &request.es_url,
+        &request.index,
+        EXPORT_KEEP_ALIVE,
+        &request.params,
+    )?;
+    let params = SearchParams {
+        pit: Some(PitRef {
+            id: pit.clone(),
+            keep_alive: EXPORT_KEEP_ALIVE.to_string(),
+        }),
+        max_concurrent_shard_requests: None,
+        ignore_unavailable: false,
+        preference: None,
+        routing: None,
+        ..request.params.clone()
+    };
+    let result = (|| {
+        let mut after = None;
+        loop {
+            let page = fetch_page_after(
+                &request.client,
+                &request.es_url,
+                &request.query,
+                &params,
+                EXPORT_PAGE_SIZE,
+                after.as_ref(),
+            )?;
+            for doc in &page.docs {
+                let source = match &request.redactor {
+                    Some(redactor) => redactor.redact(&doc.source, &mut 0),
+                    None => doc.source.clone(),
+                };
+                writer.write(&doc.index, &doc.id, &source)?;
+            }
+            let _ = sender.send(ExportEvent::Progress {
+                written: writer.written,
+                total: page.total,
+            });
+            match page.next {
+                Some(next) if page.docs.len() as u64 == EXPORT_PAGE_SIZE => after = Some(next),
+                _ => break,
+            }
+        }
+        writer.finish()
+    })();
+    let _ = close_pit(&request.client, &request.es_url, &pit);
+    result
+}
+
+pub struct ExportWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    columns: Vec<String>,
+    pub written: u64,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(out: W, format: ExportFormat, columns: &[String]) -> Self {
+        ExportWriter {
+            out,
+            format,
+            columns: columns.to_vec(),
+            written: 0,
+        }
+    }
+
+    pub fn write(&mut self, index: &str, id: &str, source: &Value) -> Result<()> {
+        match self.format {
+            ExportFormat::Ndjson => {
+                let line = serde_json::json!({ "_index": index, "_id": id, "_source": source });
+                writeln!(self.out, "{line}")?;
+            }
+            ExportFormat::JsonArray => {
+                let separator = if self.written == 0 { "[\n" } else { ",\n" };
+                let doc = serde_json::json!({ "_index": index, "_id": id, "_source": source });
+                write!(
+                    self.out,
+                    "{separator}{}",
+                    serde_json::to_string_pretty(&doc)?
+                )?;
+            }
+            ExportFormat::Csv => {
+                if self.written == 0 {
+                    if self.columns.is_empty() {
+                        self.columns = source_columns(source);
+                    }
+                    let header: Vec<String> = ["_index", "_id"]
+                        .into_iter()
+                        .chain(self.columns.iter().map(String::as_str))
+                        .map(csv_field)
+                        .collect();
+                    writeln!(self.out, "{}", header.join(","))?;
+                }
+                let mut row = vec![csv_field(index), csv_field(id)];
+                for column in &self.columns {
+                    row.push(csv_field(&ecs_text(source, column).unwrap_or_default()));
+                }
+                writeln!(self.out, "{}", row.join(","))?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<u64> {
+        if self.format == ExportFormat::JsonArray {
+            let open = if self.written == 0 { "[" } else { "" };
+            writeln!(self.out, "{open}\n]")?;
+        }
+        self.out.flush()?;
+        Ok(self.written)
+    }
+}
+
+fn source_columns(source: &Value) -> Vec<String> {
+    let mut flat = Vec::new();
+    flatten_fields(source, None, &mut flat);
+    flat.into_iter()
+        .map(|(path, _)| path)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writers_emit_ndjson_csv_and_json_arrays() {
+        let docs = [
+            (
+                "logs",
+                "a1",
+                serde_json::json!({ "msg": "ok", "http": { "status": 200 } }),
+            ),
+            (
+                "logs",
+                "b2",
+                serde_json::json!({ "msg": "said \"hi\", left" }),
+            ),
+        ];
+        let render = |format, columns: &[String]| {
+            let mut out = Vec::new();
+            let mut writer = ExportWriter::new(&mut out, format, columns);
+            for (index, id, source) in &docs {
+                writer.write(index, id, source).unwrap();
+            }
+            assert_eq!(writer.finish().unwrap(), 2);
+            String::from_utf8(out).unwrap()
+        };
+        let ndjson = render(ExportFormat::Ndjson, &[]);
+        assert_eq!(ndjson.lines().count(), 2);
+        assert!(ndjson.starts_with("{\"_id\":\"a1\",\"_index\":\"logs\""));
+        assert_eq!(
+            render(ExportFormat::Csv, &[]),
+            "_index,_id,http.status,msg\nlogs,a1,200,ok\nlogs,b2,,\"said \"\"hi\"\", left\"\n"
+        );
+        let array: Value = serde_json::from_str(&render(ExportFormat::JsonArray, &[])).unwrap();
+        assert_eq!(array[1]["_id"], "b2");
+        assert_eq!(
+            ExportFormat::from_path("/tmp/out.JSONL"),
+            Some(ExportFormat::Ndjson)
+        );
+        assert_eq!(ExportFormat::from_path("/tmp/out.txt"), None);
+    }
+}
@@ -0,0 +1,619 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use serde_json::Value;
+
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|value| value.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub(crate) fn parse_timestamp_millis(text: &str, now: i64) -> Option<i64> {
+    parse_timestamp_millis_in(text, now, TimeZone::utc())
+}
+
+pub(crate) fn parse_timestamp_millis_in(text: &str, now: i64, tz: TimeZone) -> Option<i64> {
+    let zone_ms = i64::from(tz.offset_minutes) * 60_000;
+    let text = text.trim().trim_matches('"');
+    if let Some(math) = text.strip_prefix("now") {
+        let math = math.split('/').next().unwrap_or_default();
+        if math.is_empty() {
+            return Some(now);
+        }
+        let (sign, rest) = match math.split_at(1) {
+            ("-", rest) => (-1, rest),
+            ("+", rest) => (1, rest),
+            _ => return None,
+        };
+        return now.checked_add(sign * parse_duration_millis(rest)?);
+    }
+    if text.chars().all(|ch| ch.is_ascii_digit()) && text.len() > 4 {
+        return text.parse().ok();
+    }
+    let date = text.get(..10)?;
+    let mut parts = date.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    let time = text[10..].trim_start_matches(['T', ' ']);
+    if time.is_empty() {
+        return Some(millis - zone_ms);
+    }
+    let (clock, offset) = match time.find(['Z', '+', '-']) {
+        Some(pos) => time.split_at(pos),
+        None => (time, ""),
+    };
+    let mut clock_parts = clock.split(':');
+    let hours: i64 = clock_parts.next()?.parse().ok()?;
+    let minutes: i64 = clock_parts.next().unwrap_or("0").parse().ok()?;
+    let seconds: f64 = clock_parts.next().unwrap_or("0").parse().ok()?;
+    millis += hours * 3_600_000 + minutes * 60_000 + (seconds * 1000.0) as i64;
+    if let Some(rest) = offset.strip_prefix(['+', '-']) {
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let rest = rest.replace(':', "");
+        let offset_hours: i64 = rest.get(..2)?.parse().ok()?;
+        let offset_minutes: i64 = rest.get(2..4).unwrap_or("00").parse().ok()?;
+        millis -= sign * (offset_hours * 3_600_000 + offset_minutes * 60_000);
+    } else if offset.is_empty() {
+        millis -= zone_ms;
+    }
+    Some(millis)
+}
+
+pub(crate) fn parse_duration_millis(text: &str) -> Option<i64> {
+    let unit_at = text.find(|ch: char| !ch.is_ascii_digit())?;
+    let amount: i64 = text[..unit_at].parse().ok()?;
+    let unit_ms = match &text[unit_at..] {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 604_800_000,
+        _ => return None,
+    };
+    amount.checked_mul(unit_ms)
+}
+
+pub(crate) fn format_timestamp_millis(millis: i64) -> String {
+    let days = millis.div_euclid(86_400_000);
+    let rem = millis.rem_euclid(86_400_000);
+    let (year, month, day) = civil_from_days(days);
+    let hours = rem / 3_600_000;
+    let minutes = rem / 60_000 % 60;
+    let seconds = rem / 1000 % 60;
+    format!("{year:04}-{month:02}-{day:02}T{hours:02}:{minutes:02}:{seconds:02}Z")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeZone {
+    pub offset_minutes: i32,
+    pub local: bool,
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        Self {
+            offset_minutes: 0,
+            local: false,
+        }
+    }
+
+    pub fn local() -> Self {
+        Self {
+            offset_minutes: local_offset_minutes().unwrap_or(0),
+            local: true,
+        }
+    }
+
+    pub fn from_setting(setting: Option<&str>) -> Option<Self> {
+        let setting = setting.unwrap_or("local").trim();
+        if setting.eq_ignore_ascii_case("local") {
+            return Some(Self::local());
+        }
+        if setting.eq_ignore_ascii_case("utc") || setting == "Z" {
+            return Some(Self::utc());
+        }
+        Some(Self {
+            offset_minutes: parse_offset_minutes(setting)?,
+            local: false,
+        })
+    }
+
+    pub fn offset(&self) -> String {
+        let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+        let minutes = self.offset_minutes.abs();
+        format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    pub fn label(&self) -> String {
+        match (self.local, self.offset_minutes) {
+            (true, _) => format!("local {}", self.offset()),
+            (false, 0) => "UTC".to_string(),
+            (false, _) => self.offset(),
+        }
+    }
+
+    pub fn format(&self, millis: i64) -> String {
+        let text = format_timestamp_millis(millis + i64::from(self.offset_minutes) * 60_000);
+        if self.offset_minutes == 0 {
+            text
+        } else {
+            format!("{}{}", text.trim_end_matches('Z'), self.offset())
+        }
+    }
+}
+
+fn parse_offset_minutes(text: &str) -> Option<i32> {
+    let (sign, rest) = match text.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let rest = rest.replace(':', "");
+    if rest.len() != 4 || !rest.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = rest[..2].parse().ok()?;
+    let minutes: i32 = rest[2..].parse().ok()?;
+    (hours <= 14 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+fn local_offset_minutes() -> Option<i32> {
+    let output = std::process::Command::new("date")
+        .arg("+%z")
+        .output()
+        .ok()?;
+    parse_offset_minutes(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+pub(crate) fn json_value_inline(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("\"{text}\""),
+        Value::Number(num) => num.to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Null => "null".to_string(),
+        Value::Object(map) if map.is_empty() => "{}".to_string(),
+        Value::Array(values) if values.is_empty() => "[]".to_string(),
+        _ => "<complex>".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PathStep {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+pub(crate) fn parse_json_path(expr: &str) -> Result<Vec<PathStep>> {
+    let expr = expr.trim();
+    let mut rest = expr.strip_prefix('$').unwrap_or(expr);
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .with_context(|| format!("unclosed '[' in {expr:?}"))?;
+            let inner = after[..end].trim();
+            let step = if inner == "*" {
+                PathStep::Wildcard
+            } else if let Ok(idx) = inner.parse::<usize>() {
+                PathStep::Index(idx)
+            } else {
+                let key = inner.trim_matches(|ch| ch == '"' || ch == '\'');
+                if key.is_empty() {
+                    anyhow::bail!("empty key in {expr:?}");
+                }
+                PathStep::Key(key.to_string())
+            };
+            steps.push(step);
+            rest = &after[end + 1..];
+            continue;
+        }
+        let after = rest.strip_prefix('.').unwrap_or(rest);
+        let end = after.find(['.', '[']).unwrap_or(after.len());
+        let key = &after[..end];
+        if key.is_empty() {
+            anyhow::bail!("empty key in {expr:?}");
+        }
+        steps.push(if key == "*" {
+            PathStep::Wildcard
+        } else {
+            PathStep::Key(key.to_string())
+        });
+        rest = &after[end..];
+    }
+    Ok(steps)
+}
+
+pub(crate) fn eval_json_path<'a>(value: &'a Value, steps: &[PathStep]) -> Vec<&'a Value> {
+    let mut current = vec![value];
+    for step in steps {
+        let mut next = Vec::new();
+        for value in current {
+            match (step, value) {
+                (PathStep::Key(key), Value::Object(map)) => next.extend(map.get(key)),
+                (PathStep::Index(idx), Value::Array(values)) => next.extend(values.get(*idx)),
+                (PathStep::Wildcard, Value::Object(map)) => next.extend(map.values()),
+                (PathStep::Wildcard, Value::Array(values)) => next.extend(values.iter()),
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+pub(crate) fn json_path_text(value: &Value, expr: &str) -> String {
+    let Ok(steps) = parse_json_path(expr) else {
+        return "<invalid path>".to_string();
+    };
+    let values: Vec<String> = eval_json_path(value, &steps)
+        .into_iter()
+        .map(json_value_compact)
+        .collect();
+    if values.is_empty() {
+        "-".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+pub(crate) fn json_value_compact(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        _ => serde_json::to_string(value).unwrap_or_else(|_| "<invalid json>".to_string()),
+    }
+}
+
+pub(crate) fn format_count(value: u64, raw: bool) -> String {
+    if raw {
+        return value.to_string();
+    }
+    if value < 100_000 {
+        let digits = value.to_string();
+        let mut out = String::new();
+        for (idx, ch) in digits.chars().enumerate() {
+            if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+                out.push(',');
+            }
+            out.push(ch);
+        }
+        return out;
+    }
+    let units = [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")];
+    let value = value as f64;
+    for (scale, suffix) in units {
+        if value >= scale {
+            return format!("{:.1}{suffix}", value / scale);
+        }
+    }
+    value.to_string()
+}
+
+pub(crate) fn format_bytes(value: u64, raw: bool) -> String {
+    if raw {
+        return format!("{value}b");
+    }
+    let units = ["kb", "mb", "gb", "tb", "pb"];
+    if value < 1024 {
+        return format!("{value}b");
+    }
+    let mut scaled = value as f64 / 1024.0;
+    let mut unit = units[0];
+    for next in &units[1..] {
+        if scaled < 1024.0 {
+            break;
+        }
+        scaled /= 1024.0;
+        unit = next;
+    }
+    format!("{scaled:.1}{unit}")
+}
+
+const BLOB_MIN_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FieldBlob {
+    Url(String),
+    Bytes { data: Vec<u8>, mime: Option<String> },
+}
+
+impl FieldBlob {
+    pub(crate) fn detect(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if (text.starts_with("http://") || text.starts_with("https://"))
+            && !text.contains(char::is_whitespace)
+        {
+            return Some(FieldBlob::Url(text.to_string()));
+        }
+        if let Some(rest) = text.strip_prefix("data:")
+            && let Some((header, payload)) = rest.split_once(',')
+            && let Some(mime) = header.strip_suffix(";base64")
+        {
+            let data = BASE64_STANDARD.decode(payload).ok()?;
+            return Some(FieldBlob::Bytes {
+                data,
+                mime: (!mime.is_empty()).then(|| mime.to_string()),
+            });
+        }
+        let compact: String = text.split_whitespace().collect();
+        if compact.len() < BLOB_MIN_LEN || !compact.len().is_multiple_of(4) {
+            return None;
+        }
+        let data = BASE64_STANDARD.decode(&compact).ok()?;
+        let mime = sniff_mime(&data).map(str::to_string);
+        Some(FieldBlob::Bytes { data, mime })
+    }
+
+    pub(crate) fn extension(&self) -> &'static str {
+        let FieldBlob::Bytes { mime, .. } = self else {
+            return "url";
+        };
+        match mime.as_deref() {
+            Some("image/png") => "png",
+            Some("image/jpeg") => "jpg",
+            Some("image/gif") => "gif",
+            Some("application/pdf") => "pdf",
+            Some("application/zip") => "zip",
+            Some("application/gzip") => "gz",
+            Some(mime) if mime.starts_with("text/") => "txt",
+            _ => "bin",
+        }
+    }
+
+    pub(crate) fn hint(&self, raw: bool) -> String {
+        match self {
+            FieldBlob::Url(url) => format!("link {url}"),
+            FieldBlob::Bytes { data, mime } => format!(
+                "{} {}",
+                mime.as_deref().unwrap_or("bytes"),
+                format_bytes(data.len() as u64, raw)
+            ),
+        }
+    }
+}
+
+fn sniff_mime(data: &[u8]) -> Option<&'static str> {
+    let signatures: [(&[u8], &str); 6] = [
+        (b"\x89PNG", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"%PDF", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+    ];
+    signatures
+        .iter()
+        .find(|(magic, _)| data.starts_with(magic))
+        .map(|(_, mime)| *mime)
+}
+
+pub(crate) fn collect_field_blobs(value: &Value) -> Vec<(String, FieldBlob)> {
+    let mut out = Vec::new();
+    collect_blobs_at(value, String::new(), &mut out);
+    out
+}
+
+fn collect_blobs_at(value: &Value, path: String, out: &mut Vec<(String, FieldBlob)>) {
+    match value {
+        Value::String(text) => {
+            if let Some(blob) = FieldBlob::detect(text) {
+                out.push((path, blob));
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map {
+                let next = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                collect_blobs_at(value, next, out);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                collect_blobs_at(value, format!("{path}[{index}]"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn format_count_str(value: Option<&str>, raw: bool) -> String {
+    match value {
+        Some(text) => text
+            .parse::<u64>()
+            .map(|value| format_count(value, raw))
+            .unwrap_or_else(|_| text.to_string()),
+        None => "-".to_string(),
+    }
+}
+
+pub(crate) fn format_bytes_str(value: Option<&str>, raw: bool) -> String {
+    match value {
+        Some(text) => text
+            .parse::<u64>()
+            .map(|value| format_bytes(value, raw))
+            .unwrap_or_else(|_| text.to_string()),
+        None => "-".to_string(),
+    }
+}
+
+pub(crate) fn truncate_string(value: &str, max_len: usize) -> String {
+    if value.chars().count() <= max_len {
+        return value.to_string();
+    }
+    let mut out: String = value.chars().take(max_len).collect();
+    out.push_str("...");
+    out
+}
+
+pub(crate) fn truncate_start(value: &str, max_len: usize) -> String {
+    let count = value.chars().count();
+    if count <= max_len {
+        return value.to_string();
+    }
+    let keep = max_len.saturating_sub(3);
+    let tail: String = value.chars().skip(count - keep).collect();
+    format!("...{tail}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_millis_accepts_ms_and_rejects_overflow() {
+        assert_eq!(parse_duration_millis("500ms"), Some(500));
+        assert_eq!(parse_duration_millis("2s"), Some(2_000));
+        assert_eq!(parse_duration_millis("99999999999999w"), None);
+        assert_eq!(parse_duration_millis("5x"), None);
+    }
+
+    #[test]
+    fn field_blobs_detect_links_and_encoded_bytes() {
+        let png = BASE64_STANDARD.encode([b"\x89PNG\r\n\x1a\n".as_slice(), &[0; 60]].concat());
+        let source = serde_json::json!({
+            "url": { "full": "https://example.com/a.png" },
+            "attachment": { "data": png },
+            "thumb": format!("data:image/gif;base64,{}", BASE64_STANDARD.encode("GIF89a")),
+            "message": "not a blob at all, just words in a sentence that runs on a bit longer",
+        });
+        let blobs = collect_field_blobs(&source);
+        let paths: Vec<&str> = blobs.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["attachment.data", "thumb", "url.full"]);
+        assert_eq!(blobs[0].1.hint(false), "image/png 68b");
+        assert_eq!(blobs[0].1.extension(), "png");
+        assert_eq!(blobs[1].1.extension(), "gif");
+        assert_eq!(
+            blobs[2].1,
+            FieldBlob::Url("https://example.com/a.png".to_string())
+        );
+    }
+    use proptest::prelude::*;
+
+    #[test]
+    fn format_count_humanizes_large_values() {
+        assert_eq!(format_count(999, false), "999");
+        assert_eq!(format_count(12_345, false), "12,345");
+        assert_eq!(format_count(1_234_567, false), "1.2M");
+        assert_eq!(format_count(3_400_000_000, false), "3.4B");
+        assert_eq!(format_count(1_234_567, true), "1234567");
+        assert_eq!(format_count(0, false), "0");
+        assert_eq!(format_count(99_999, false), "99,999");
+        assert_eq!(format_count(100_000, false), "100.0K");
+        assert_eq!(format_count_str(Some("n/a"), false), "n/a");
+        assert_eq!(format_count_str(None, false), "-");
+    }
+
+    #[test]
+    fn format_bytes_uses_binary_units() {
+        assert_eq!(format_bytes(512, false), "512b");
+        assert_eq!(format_bytes(1536, false), "1.5kb");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024, false), "5.0gb");
+        assert_eq!(format_bytes(1536, true), "1536b");
+        assert_eq!(format_bytes(1023, false), "1023b");
+        assert_eq!(format_bytes(1024, false), "1.0kb");
+        assert_eq!(format_bytes(u64::MAX, false), "16384.0pb");
+    }
+
+    #[test]
+    fn json_path_extracts_nested_values() {
+        let doc = serde_json::json!({
+            "service": { "name": "api" },
+            "tags": ["a", "b"],
+            "spans": [{ "id": 1 }, { "id": 2 }],
+        });
+        let eval = |expr: &str| {
+            let steps = parse_json_path(expr).unwrap();
+            eval_json_path(&doc, &steps)
+                .into_iter()
+                .map(json_value_compact)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(eval("$.service.name"), ["api"]);
+        assert_eq!(eval("tags[1]"), ["b"]);
+        assert_eq!(eval("spans[*].id"), ["1", "2"]);
+        assert_eq!(eval("$['service'].name"), ["api"]);
+        assert!(eval("missing.field").is_empty());
+        assert!(parse_json_path("a[0").is_err());
+        assert!(parse_json_path("a..b").is_err());
+    }
+
+    #[test]
+    fn timestamps_round_trip_through_civil_dates() {
+        let now = 0;
+        let millis = parse_timestamp_millis("2024-02-29T13:45:30.250Z", now).unwrap();
+        assert_eq!(format_timestamp_millis(millis), "2024-02-29T13:45:30Z");
+        assert_eq!(parse_timestamp_millis("1970-01-02", now), Some(86_400_000));
+        assert_eq!(
+            parse_timestamp_millis("2024-01-01T02:00:00+02:00", now),
+            parse_timestamp_millis("2024-01-01T00:00:00Z", now)
+        );
+        assert_eq!(parse_timestamp_millis("now-1h", 7_200_000), Some(3_600_000));
+        assert_eq!(parse_timestamp_millis("2024-13-01", now), None);
+        let tokyo = TimeZone::from_setting(Some("+09:00")).unwrap();
+        assert_eq!(
+            parse_timestamp_millis_in("2024-01-01T09:00:00", now, tokyo),
+            parse_timestamp_millis("2024-01-01T00:00:00Z", now)
+        );
+        assert_eq!(
+            parse_timestamp_millis_in("2024-01-01T00:00:00Z", now, tokyo),
+            parse_timestamp_millis("2024-01-01T00:00:00Z", now)
+        );
+        assert_eq!(tokyo.format(millis), "2024-02-29T22:45:30+09:00");
+        assert_eq!(tokyo.label(), "+09:00");
+        assert_eq!(TimeZone::utc().format(millis), "2024-02-29T13:45:30Z");
+        assert_eq!(TimeZone::from_setting(Some("utc")), Some(TimeZone::utc()));
+        assert_eq!(TimeZone::from_setting(Some("Europe/Paris")), None);
+    }
+
+    proptest! {
+        #[test]
+        fn truncate_string_respects_char_limit(value in "\\PC{0,40}", max_len in 0usize..30) {
+            let out = truncate_string(&value, max_len);
+            let chars = value.chars().count();
+            if chars <= max_len {
+                prop_assert_eq!(out, value);
+            } else {
+                prop_assert_eq!(out.chars().count(), max_len + 3);
+                prop_assert!(out.ends_with("..."));
+                prop_assert!(value.starts_with(&out[..out.len() - 3]));
+            }
+        }
+    }
+}
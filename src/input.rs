@@ -0,0 +1,81 @@
+use std::io;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+
+use crate::app::*;
+use crate::commands::*;
+use crate::keymap::*;
+use crate::terminal::*;
+use crate::ui::*;
+
+pub(crate) fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    launch: Option<LaunchTarget>,
+    signals: &Signals,
+) -> Result<()> {
+    let mut last_tick = Instant::now();
+    refresh_all(app);
+    if let Some(target) = launch {
+        open_launch_target(app, target);
+    }
+
+    loop {
+        if signals.terminate.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        if signals.resumed.swap(false, Ordering::Relaxed) {
+            setup_terminal()?;
+            terminal.clear()?;
+            app.dirty = true;
+        }
+
+        if app.needs_redraw() {
+            record_crash_state(app);
+            terminal.draw(|frame| ui(frame, app))?;
+            app.dirty = false;
+        }
+
+        let tick_rate = app.tick_rate();
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if event::poll(timeout)? {
+            let event = event::read()?;
+            app.dirty = true;
+            if let Event::Key(key) = event {
+                if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    suspend(terminal)?;
+                    signals.resumed.store(false, Ordering::Relaxed);
+                    continue;
+                }
+                if let Some(action) = key_action(app, key) {
+                    app.update(action);
+                }
+            }
+        }
+
+        if app.should_quit {
+            return Ok(());
+        }
+
+        app.poll_update_check();
+        app.poll_jobs();
+        if app.refresh_due() {
+            refresh_background(app);
+        }
+
+        if last_tick.elapsed() >= tick_rate {
+            last_tick = Instant::now();
+            if let Some(cluster) = handle_config_reload(app) {
+                open_reconnect_confirm(app, cluster);
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::action::*;
+use crate::app::*;
+
+pub fn key_action(app: &App, key: KeyEvent) -> Option<Action> {
+    match app.input_mode {
+        InputMode::Normal if app.prompt.is_some() => prompt_key_action(app, key),
+        InputMode::Normal if app.info_panel.is_some() => panel_key_action(key),
+        InputMode::Normal => normal_key_action(app, key),
+        InputMode::TermsPrefix => match key.code {
+            KeyCode::Up => Some(Action::MoveTermsSelection(-1)),
+            KeyCode::Down => Some(Action::MoveTermsSelection(1)),
+            _ => edit_key_action(key),
+        },
+        InputMode::Query
+        | InputMode::Command
+        | InputMode::ScopeFilter
+        | InputMode::ResultsFilter
+        | InputMode::Jump
+        | InputMode::DrawerSearch
+        | InputMode::ExportPath
+        | InputMode::ConfirmIndexAction => edit_key_action(key),
+    }
+}
+
+fn prompt_key_action(app: &App, key: KeyEvent) -> Option<Action> {
+    match (&app.prompt, key.code) {
+        (Some(Prompt::Quit), KeyCode::Char('c')) => Some(Action::CancelJobsAndQuit),
+        (Some(Prompt::Quit), KeyCode::Char('d')) => Some(Action::DetachAndQuit),
+        (Some(Prompt::Reconnect(_)), KeyCode::Char('y')) => Some(Action::ConfirmReconnect),
+        (Some(Prompt::OpenScope(_)), KeyCode::Char('y') | KeyCode::Enter) => {
+            Some(Action::ConfirmScope)
+        }
+        (Some(Prompt::PickCluster(_)), KeyCode::Up) => Some(Action::MoveClusterSelection(-1)),
+        (Some(Prompt::PickCluster(_)), KeyCode::Down) => Some(Action::MoveClusterSelection(1)),
+        (Some(Prompt::PickCluster(_)), KeyCode::Enter) => Some(Action::ConfirmCluster),
+        (Some(Prompt::BuildAggregation { .. }), KeyCode::Up) => {
+            Some(Action::MoveAggregationField(-1))
+        }
+        (Some(Prompt::BuildAggregation { .. }), KeyCode::Down) => {
+            Some(Action::MoveAggregationField(1))
+        }
+        (Some(Prompt::BuildAggregation { .. }), KeyCode::Left) => {
+            Some(Action::CycleAggregationKind(-1))
+        }
+        (Some(Prompt::BuildAggregation { .. }), KeyCode::Right) => {
+            Some(Action::CycleAggregationKind(1))
+        }
+        (Some(Prompt::BuildAggregation { .. }), KeyCode::Enter) => Some(Action::ConfirmAggregation),
+        (Some(Prompt::PickSort { .. }), KeyCode::Up) => Some(Action::MoveSortSelection(-1)),
+        (Some(Prompt::PickSort { .. }), KeyCode::Down) => Some(Action::MoveSortSelection(1)),
+        (Some(Prompt::PickSort { .. }), KeyCode::Left | KeyCode::Right) => {
+            Some(Action::ToggleSortOrder)
+        }
+        (Some(Prompt::PickSort { .. }), KeyCode::Enter) => Some(Action::ConfirmSort),
+        (Some(Prompt::PickFavorite(_)), KeyCode::Up) => Some(Action::MoveFavoriteSelection(-1)),
+        (Some(Prompt::PickFavorite(_)), KeyCode::Down) => Some(Action::MoveFavoriteSelection(1)),
+        (Some(Prompt::PickFavorite(_)), KeyCode::Enter) => Some(Action::ConfirmFavorite),
+        (Some(Prompt::PickView(_)), KeyCode::Up) => Some(Action::MoveViewSelection(-1)),
+        (Some(Prompt::PickView(_)), KeyCode::Down) => Some(Action::MoveViewSelection(1)),
+        (Some(Prompt::PickView(_)), KeyCode::Enter) => Some(Action::LoadSelectedView),
+        (Some(Prompt::PickView(_)), KeyCode::Delete | KeyCode::Char('x')) => {
+            Some(Action::DeleteSelectedView)
+        }
+        (Some(Prompt::PickBlob(_)), KeyCode::Up) => Some(Action::MoveBlobSelection(-1)),
+        (Some(Prompt::PickBlob(_)), KeyCode::Down) => Some(Action::MoveBlobSelection(1)),
+        (Some(Prompt::PickBlob(_)), KeyCode::Enter) => Some(Action::OpenSelectedBlob),
+        (Some(Prompt::ExportMarked(_)), KeyCode::Up) => Some(Action::MoveMarkedExportSelection(-1)),
+        (Some(Prompt::ExportMarked(_)), KeyCode::Down) => {
+            Some(Action::MoveMarkedExportSelection(1))
+        }
+        (Some(Prompt::ExportMarked(_)), KeyCode::Enter) => Some(Action::ExportMarked),
+        (Some(Prompt::PickBasket(_)), KeyCode::Up) => Some(Action::MoveBasketSelection(-1)),
+        (Some(Prompt::PickBasket(_)), KeyCode::Down) => Some(Action::MoveBasketSelection(1)),
+        (Some(Prompt::PickBasket(_)), KeyCode::Enter) => Some(Action::OpenBasketDoc),
+        (Some(Prompt::PickBasket(_)), KeyCode::Char('d')) => Some(Action::DiffBasketDocs),
+        (Some(Prompt::PickBasket(_)), KeyCode::Delete | KeyCode::Char('x')) => {
+            Some(Action::RemoveBasketDoc)
+        }
+        (Some(Prompt::PickBasket(_)), KeyCode::Char('e')) => Some(Action::OpenMarkedExport),
+        (Some(Prompt::PickBasket(_)), KeyCode::Char('c')) => Some(Action::ClearBasket),
+        (Some(Prompt::HealthDetail { .. }), KeyCode::Char('a')) => Some(Action::ExplainAllocation),
+        (Some(Prompt::AliasChange(_)), KeyCode::Char('y') | KeyCode::Enter) => {
+            Some(Action::ApplyAliasChange)
+        }
+        (Some(Prompt::IndexActions { .. }), KeyCode::Up) => {
+            Some(Action::MoveIndexActionSelection(-1))
+        }
+        (Some(Prompt::IndexActions { .. }), KeyCode::Down) => {
+            Some(Action::MoveIndexActionSelection(1))
+        }
+        (Some(Prompt::IndexActions { .. }), KeyCode::Enter) => Some(Action::RunIndexOp),
+        (Some(Prompt::PickColumns(_)), KeyCode::Up) => Some(Action::MoveColumnSelection(-1)),
+        (Some(Prompt::PickColumns(_)), KeyCode::Down) => Some(Action::MoveColumnSelection(1)),
+        (Some(Prompt::PickColumns(_)), KeyCode::Char(' ')) => Some(Action::ToggleColumn),
+        (Some(Prompt::PickColumns(_)), KeyCode::Enter) => Some(Action::ClosePanel),
+        (_, KeyCode::Esc | KeyCode::Char('n')) => Some(Action::ClosePanel),
+        _ => panel_key_action(key),
+    }
+}
+
+fn panel_key_action(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Some(Action::ClosePanel),
+        KeyCode::Up => Some(Action::ScrollPanel(-1)),
+        KeyCode::Down => Some(Action::ScrollPanel(1)),
+        KeyCode::PageUp => Some(Action::ScrollPanel(-10)),
+        KeyCode::PageDown => Some(Action::ScrollPanel(10)),
+        _ => None,
+    }
+}
+
+fn normal_key_action(app: &App, key: KeyEvent) -> Option<Action> {
+    let on_nav = app.focus == Focus::LeftNav;
+    let action = match key.code {
+        KeyCode::Char('q') => Action::Quit,
+        KeyCode::Char('r') => Action::Refresh,
+        KeyCode::Char('/') if app.show_doc_drawer => Action::StartDrawerSearch,
+        KeyCode::Char('n') if app.show_doc_drawer && !app.drawer_search.is_empty() => {
+            Action::MoveDrawerMatch(1)
+        }
+        KeyCode::Char('N') if app.show_doc_drawer && !app.drawer_search.is_empty() => {
+            Action::MoveDrawerMatch(-1)
+        }
+        KeyCode::Char('/') | KeyCode::Char('?') => Action::StartQuery,
+        KeyCode::Char(':') => Action::StartCommand,
+        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Action::StartScopeFilter
+        }
+        KeyCode::Char('f') => Action::StartResultsFilter,
+        KeyCode::Char('G') => Action::StartJump,
+        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::NavBack,
+        KeyCode::Left if key.modifiers.contains(KeyModifiers::ALT) => Action::NavBack,
+        KeyCode::Right if key.modifiers.contains(KeyModifiers::ALT) => Action::NavForward,
+        KeyCode::Backspace => Action::NavBack,
+        KeyCode::Char('`') => Action::ToggleLastScope,
+        KeyCode::Tab | KeyCode::BackTab => Action::ToggleFocus,
+        KeyCode::Char('1') => Action::SelectScopeKind(ScopeKind::Indices),
+        KeyCode::Char('2') => Action::SelectScopeKind(ScopeKind::Aliases),
+        KeyCode::Char('3') => Action::SelectScopeKind(ScopeKind::DataStreams),
+        KeyCode::Up => Action::SelectPrev,
+        KeyCode::Down => Action::SelectNext,
+        KeyCode::Enter if on_nav && app.scope_kind == ScopeKind::Indices => {
+            Action::OpenIndexDetails
+        }
+        KeyCode::Enter if on_nav && app.scope_kind == ScopeKind::Aliases => {
+            Action::OpenAliasDetails
+        }
+        KeyCode::Enter if on_nav && app.scope_kind == ScopeKind::DataStreams => {
+            Action::OpenLifecycleDetails
+        }
+        KeyCode::Right | KeyCode::Left if on_nav && app.scope_kind == ScopeKind::Aliases => {
+            Action::SetAliasExpanded(key.code == KeyCode::Right)
+        }
+        KeyCode::Right | KeyCode::Left
+            if app.focus == Focus::Results && !app.result_columns().is_empty() =>
+        {
+            Action::MoveCellCursor(if key.code == KeyCode::Right { 1 } else { -1 })
+        }
+        KeyCode::Char('x') if app.focus == Focus::Results => Action::ExpandCell,
+        KeyCode::Char('m') => Action::ToggleMappingsTab,
+        KeyCode::Char('D') => Action::ToggleQueryMode,
+        KeyCode::Char('s') if app.scope_kind == ScopeKind::Indices => Action::OpenIndexSettings,
+        KeyCode::Enter | KeyCode::Char('o')
+            if app.focus == Focus::Results && app.right_tab == RightTab::Results =>
+        {
+            Action::ToggleDocDrawer
+        }
+        KeyCode::Esc if app.show_doc_drawer => Action::CloseDocDrawer,
+        KeyCode::Char('d') => Action::ReloadDocs,
+        KeyCode::Char('n') => Action::NextPage,
+        KeyCode::Char('p') => Action::PrevPage,
+        KeyCode::Char('v') if app.show_doc_drawer => Action::CycleDocView,
+        KeyCode::Char('R') if app.show_doc_drawer => Action::RefetchDoc,
+        KeyCode::Char('t') if app.show_doc_drawer => Action::FollowTrace,
+        KeyCode::Char('w') if app.show_doc_drawer => Action::OpenBlobPicker,
+        KeyCode::Char('[') if app.show_doc_drawer => Action::MoveDrawerField(-1),
+        KeyCode::Char(']') if app.show_doc_drawer => Action::MoveDrawerField(1),
+        KeyCode::Char('i') if app.show_doc_drawer => Action::AddFieldFilter { exclude: false },
+        KeyCode::Char('e') if app.show_doc_drawer => Action::AddFieldFilter { exclude: true },
+        KeyCode::Char('L') => Action::ToggleLogView,
+        KeyCode::Char('E') => Action::ExplainQuery,
+        KeyCode::Char('O') => Action::ToggleOperator,
+        KeyCode::Char('C') => Action::OpenClusterPicker,
+        KeyCode::Char('a') => Action::OpenAggregationBuilder,
+        KeyCode::Char('c') => Action::OpenColumnPicker,
+        KeyCode::Char('S') => Action::OpenSortPicker,
+        KeyCode::Char('*') => Action::ToggleFavorite,
+        KeyCode::Char('F') => Action::OpenFavoritePicker,
+        KeyCode::Char('V') => Action::OpenViewPicker,
+        KeyCode::Char('y') if app.focus == Focus::Results => Action::CopyPermalink,
+        KeyCode::Char(' ') if app.focus == Focus::Results => Action::ToggleMark,
+        KeyCode::Char('Y') => Action::OpenMarkedExport,
+        KeyCode::Char('B') => Action::OpenBasket,
+        KeyCode::Char('X') => Action::StartExport,
+        KeyCode::Char('T') => Action::OpenEventTimeline,
+        KeyCode::Char('H') => Action::OpenHealthDetail,
+        KeyCode::Char('M') if on_nav && app.scope_kind == ScopeKind::Indices => {
+            Action::OpenIndexActions
+        }
+        KeyCode::Char('M') if on_nav && app.scope_kind == ScopeKind::Aliases => {
+            Action::StartAliasEdit
+        }
+        _ => return None,
+    };
+    Some(action)
+}
+
+fn edit_key_action(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc => Some(Action::InputCancel),
+        KeyCode::Enter => Some(Action::InputSubmit),
+        KeyCode::Backspace => Some(Action::InputBackspace),
+        KeyCode::Char(ch) => Some(Action::InputChar(ch)),
+        _ => None,
+    }
+}
@@ -0,0 +1,127 @@
+mod action;
+mod app;
+mod client;
+mod commands;
+mod config;
+mod ecs;
+mod export;
+mod format;
+mod input;
+mod keymap;
+mod metrics;
+mod patterns;
+mod query;
+mod redact;
+mod setup;
+mod share;
+mod state;
+mod stats;
+mod terminal;
+mod ui;
+mod update;
+
+use std::io;
+
+use anyhow::{Context, Result};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+
+use crate::input::run_app;
+use crate::setup::run_setup_wizard;
+use crate::terminal::{
+    Signals, install_panic_hook, register_signals, restore_terminal, setup_terminal,
+};
+
+#[cfg(feature = "embed")]
+pub use action::Action;
+#[cfg(feature = "embed")]
+pub use app::*;
+#[cfg(feature = "embed")]
+pub use client::*;
+#[cfg(feature = "embed")]
+pub use commands::run_command;
+#[cfg(feature = "embed")]
+pub use config::*;
+#[cfg(feature = "embed")]
+pub use keymap::key_action;
+#[cfg(feature = "embed")]
+pub use terminal::{ColorDepth, TerminalCaps};
+
+const USAGE: &str = "usage: index-lens [--version] [self-update | stats]\n       index-lens [--open <scope>:<id>] [--scope <scope>] [--query <query>]";
+
+pub fn run() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let launch = match args.as_slice() {
+        [] => None,
+        ["--version" | "-V"] => {
+            println!("{}", update::version_string());
+            return Ok(());
+        }
+        ["--help" | "-h"] => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        ["self-update"] => return update::self_update(),
+        ["stats"] => return stats::print_stats(),
+        [flag, ..] if flag.starts_with("--") => Some(
+            app::LaunchTarget::parse(&args)
+                .with_context(|| format!("invalid arguments\n{USAGE}"))?,
+        ),
+        _ => anyhow::bail!("unrecognized arguments {args:?}\n{USAGE}"),
+    };
+    let config = config::load_config()?;
+    let signals = register_signals()?;
+    install_panic_hook();
+    setup_terminal()?;
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend).context("failed to create terminal")?;
+
+    let res = start(&mut terminal, config, launch, &signals);
+
+    restore_terminal();
+
+    res
+}
+
+fn start(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    config: Option<config::Config>,
+    launch: Option<app::LaunchTarget>,
+    signals: &Signals,
+) -> Result<()> {
+    let config = match config {
+        Some(config) => config,
+        None if std::env::var_os("ES_URL").is_some() => config::Config::default(),
+        None => match run_setup_wizard(terminal, signals)? {
+            Some(config) => config,
+            None => return Ok(()),
+        },
+    };
+    let mut config = config;
+    let caps = terminal::TerminalCaps::detect();
+    if caps.color == terminal::ColorDepth::None {
+        config.display.plain = true;
+    }
+    let es_url = std::env::var("ES_URL").unwrap_or_else(|_| config.cluster.url.clone());
+    let client = config::build_client(&config.cluster)?;
+    let check_updates = config.update.check;
+    let metrics_listen = config.metrics.listen.clone();
+    let mut app = app::App::new(config, es_url, client);
+    app.caps = caps;
+    if let Some(listen) = metrics_listen
+        && let Err(err) = metrics::spawn_metrics_server(&listen)
+    {
+        app.last_error = Some(format!("metrics: {err:#}"));
+    }
+    app.state = state::load_state().unwrap_or_default();
+    if check_updates {
+        app.update_check = Some(update::spawn_update_check());
+    }
+    let result = run_app(terminal, &mut app, launch, signals);
+    let _ = app::close_pit_session(&mut app);
+    if app.config.stats.enabled {
+        let _ = stats::save_session_stats(&app.usage);
+    }
+    result
+}
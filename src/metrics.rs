@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+const LATENCY_BUCKETS: [f64; 9] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+#[derive(Debug, Clone, Default)]
+pub struct Latency {
+    pub buckets: [u64; LATENCY_BUCKETS.len()],
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl Latency {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub requests: BTreeMap<String, u64>,
+    pub errors: BTreeMap<String, u64>,
+    pub latency: BTreeMap<String, Latency>,
+}
+
+impl Metrics {
+    pub fn record(&mut self, method: &str, path: &str, error: Option<&str>, elapsed: Duration) {
+        *self.requests.entry(method.to_string()).or_default() += 1;
+        if let Some(kind) = error {
+            *self.errors.entry(kind.to_string()).or_default() += 1;
+        }
+        let endpoint = if path.ends_with("/_search") || path.ends_with("/_msearch") {
+            "search"
+        } else {
+            "other"
+        };
+        self.latency
+            .entry(endpoint.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP index_lens_requests_total Requests sent to the cluster.\n");
+        out.push_str("# TYPE index_lens_requests_total counter\n");
+        for (method, count) in &self.requests {
+            let _ = writeln!(
+                out,
+                "index_lens_requests_total{{method=\"{method}\"}} {count}"
+            );
+        }
+        out.push_str("# HELP index_lens_request_errors_total Requests that failed.\n");
+        out.push_str("# TYPE index_lens_request_errors_total counter\n");
+        for (kind, count) in &self.errors {
+            let _ = writeln!(
+                out,
+                "index_lens_request_errors_total{{kind=\"{kind}\"}} {count}"
+            );
+        }
+        out.push_str("# HELP index_lens_request_duration_seconds Request latency.\n");
+        out.push_str("# TYPE index_lens_request_duration_seconds histogram\n");
+        for (endpoint, latency) in &self.latency {
+            let name = "index_lens_request_duration_seconds";
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(latency.buckets) {
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{{endpoint=\"{endpoint}\",le=\"{bound}\"}} {count}"
+                );
+            }
+            let count = latency.count;
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{endpoint=\"{endpoint}\",le=\"+Inf\"}} {count}"
+            );
+            let _ = writeln!(out, "{name}_sum{{endpoint=\"{endpoint}\"}} {}", latency.sum);
+            let _ = writeln!(out, "{name}_count{{endpoint=\"{endpoint}\"}} {count}");
+        }
+        out
+    }
+}
+
+pub static METRICS: Mutex<Option<Metrics>> = Mutex::new(None);
+
+pub fn record_request_metrics(method: &str, path: &str, error: Option<&str>, elapsed: Duration) {
+    if let Ok(mut metrics) = METRICS.lock()
+        && let Some(metrics) = metrics.as_mut()
+    {
+        metrics.record(method, path, error, elapsed);
+    }
+}
+
+pub fn spawn_metrics_server(listen: &str) -> Result<SocketAddr> {
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("failed to listen on {listen}"))?;
+    let addr = listener
+        .local_addr()
+        .context("failed to read listener address")?;
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.get_or_insert_with(Metrics::default);
+    }
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = serve_metrics(stream);
+        }
+    });
+    Ok(addr)
+}
+
+fn serve_metrics(mut stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = match path {
+        "/metrics" => {
+            let body = METRICS
+                .lock()
+                .ok()
+                .and_then(|metrics| metrics.as_ref().map(Metrics::render))
+                .unwrap_or_default();
+            ("200 OK", body)
+        }
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_emits_counters_and_latency_histograms() {
+        let mut metrics = Metrics::default();
+        metrics.record("POST", "/logs/_search", None, Duration::from_millis(40));
+        metrics.record(
+            "GET",
+            "/_cluster/health",
+            Some("timeout"),
+            Duration::from_secs(3),
+        );
+        let text = metrics.render();
+        assert!(text.contains("index_lens_requests_total{method=\"POST\"} 1\n"));
+        assert!(text.contains("index_lens_request_errors_total{kind=\"timeout\"} 1\n"));
+        assert!(text.contains(
+            "index_lens_request_duration_seconds_bucket{endpoint=\"search\",le=\"0.05\"} 1\n"
+        ));
+        assert!(text.contains(
+            "index_lens_request_duration_seconds_bucket{endpoint=\"other\",le=\"2.5\"} 0\n"
+        ));
+        assert!(text.contains("index_lens_request_duration_seconds_count{endpoint=\"other\"} 1\n"));
+
+        assert!(
+            Metrics::default()
+                .render()
+                .lines()
+                .all(|line| line.starts_with('#'))
+        );
+        let mut metrics = Metrics::default();
+        metrics.record("POST", "/_msearch", None, Duration::from_secs(9));
+        let text = metrics.render();
+        assert!(text.contains(
+            "index_lens_request_duration_seconds_bucket{endpoint=\"search\",le=\"5\"} 0\n"
+        ));
+        assert!(text.contains(
+            "index_lens_request_duration_seconds_bucket{endpoint=\"search\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(
+            spawn_metrics_server("not-an-address")
+                .unwrap_err()
+                .to_string()
+                .starts_with("failed to listen on not-an-address")
+        );
+    }
+}
@@ -0,0 +1,92 @@
+pub const WILDCARD: &str = "<*>";
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogPattern {
+    pub tokens: Vec<String>,
+    pub count: usize,
+}
+
+impl LogPattern {
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        let same = self
+            .tokens
+            .iter()
+            .zip(tokens)
+            .filter(|(a, b)| a == b && a.as_str() != WILDCARD)
+            .count();
+        same as f64 / tokens.len().max(1) as f64
+    }
+
+    fn absorb(&mut self, tokens: &[String]) {
+        for (slot, token) in self.tokens.iter_mut().zip(tokens) {
+            if slot != token {
+                *slot = WILDCARD.to_string();
+            }
+        }
+        self.count += 1;
+    }
+}
+
+pub fn tokenize(message: &str) -> Vec<String> {
+    message
+        .split_whitespace()
+        .map(|token| {
+            if token.chars().any(|ch| ch.is_ascii_digit()) {
+                WILDCARD.to_string()
+            } else {
+                token.to_string()
+            }
+        })
+        .collect()
+}
+
+pub fn cluster_messages<'a>(messages: impl IntoIterator<Item = &'a str>) -> Vec<LogPattern> {
+    let mut patterns: Vec<LogPattern> = Vec::new();
+    for message in messages {
+        let tokens = tokenize(message);
+        let best = patterns
+            .iter_mut()
+            .filter(|pattern| pattern.tokens.len() == tokens.len())
+            .map(|pattern| (pattern.similarity(&tokens), pattern))
+            .filter(|(score, _)| *score >= SIMILARITY_THRESHOLD)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        match best {
+            Some((_, pattern)) => pattern.absorb(&tokens),
+            None => patterns.push(LogPattern { tokens, count: 1 }),
+        }
+    }
+    patterns.sort_by_key(|pattern| std::cmp::Reverse(pattern.count));
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_messages_merges_variable_tokens() {
+        let patterns = cluster_messages([
+            "connection to db-1 failed after 30ms",
+            "user alice logged in",
+            "connection to cache failed after 12ms",
+            "user bob logged in",
+            "connection to db-2 failed after 7ms",
+        ]);
+        let templates: Vec<(String, usize)> = patterns
+            .iter()
+            .map(|pattern| (pattern.tokens.join(" "), pattern.count))
+            .collect();
+        assert_eq!(
+            templates,
+            [
+                ("connection to <*> failed after <*>".to_string(), 3),
+                ("user <*> logged in".to_string(), 2),
+            ]
+        );
+        assert!(cluster_messages([]).is_empty());
+        let lengths = cluster_messages(["disk full", "disk full on /var", "disk full"]);
+        let counts: Vec<usize> = lengths.iter().map(|pattern| pattern.count).collect();
+        assert_eq!(counts, [2, 1]);
+    }
+}
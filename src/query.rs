@@ -0,0 +1,124 @@
+use regex::Regex;
+
+use crate::app::*;
+use crate::format::*;
+
+pub(crate) fn tsdb_range_warning(app: &App) -> Option<String> {
+    if app.scope_kind != ScopeKind::Indices {
+        return None;
+    }
+    let info = app.tsdb.get(app.selected_scope_name()?)?;
+    let now = now_millis();
+    let (lower, upper) = timestamp_query_range(&app.query, now, app.time_zone)?;
+    let start = info
+        .start_time
+        .as_deref()
+        .and_then(|value| parse_timestamp_millis(value, now));
+    let end = info
+        .end_time
+        .as_deref()
+        .and_then(|value| parse_timestamp_millis(value, now));
+    let before = matches!((upper, start), (Some(upper), Some(start)) if upper < start);
+    let after = matches!((lower, end), (Some(lower), Some(end)) if lower >= end);
+    let bound =
+        |value: Option<i64>| value.map_or("*".to_string(), |millis| app.time_zone.format(millis));
+    (before || after).then(|| format!("outside tsdb range {}..{}", bound(start), bound(end)))
+}
+
+pub(crate) fn timestamp_query_range(
+    query: &str,
+    now: i64,
+    tz: TimeZone,
+) -> Option<(Option<i64>, Option<i64>)> {
+    let range =
+        Regex::new(r"@timestamp:[\[{](\S+)\s+TO\s+([^\]}\s]+)[\]}]").expect("valid range regex");
+    if let Some(caps) = range.captures(query) {
+        let bound = |text: &str| (text != "*").then(|| parse_timestamp_millis_in(text, now, tz))?;
+        return Some((bound(&caps[1]), bound(&caps[2])));
+    }
+    let compare = Regex::new(r"@timestamp:(>=?|<=?)(\S+)").expect("valid compare regex");
+    let caps = compare.captures(query)?;
+    let value = parse_timestamp_millis_in(&caps[2], now, tz)?;
+    if caps[1].starts_with('>') {
+        Some((Some(value), None))
+    } else {
+        Some((None, Some(value)))
+    }
+}
+
+pub(crate) fn query_cost_warnings(query: &str, from: u64) -> Vec<&'static str> {
+    let mut warnings = Vec::new();
+    let mut push = |warning: &'static str| {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    };
+    for term in query.split_whitespace() {
+        let term = term.trim_start_matches(['(', '+', '-', '!']);
+        let (field, value) = match term.split_once(':') {
+            Some((field, value)) if !field.is_empty() => (Some(field), value),
+            _ => (None, term),
+        };
+        if field.is_some_and(|field| field.contains('*')) {
+            push("wildcard fields");
+        }
+        if value.starts_with('*') || value.starts_with('?') {
+            push("leading wildcard");
+        }
+        if value.len() > 1 && value.starts_with('/') {
+            push("regexp");
+        }
+        if value
+            .rsplit_once('~')
+            .is_some_and(|(head, _)| !head.is_empty() && !head.ends_with('"'))
+        {
+            push("fuzzy");
+        }
+    }
+    if from >= 10_000 {
+        push("deep paging");
+    } else if from >= 1_000 {
+        push("large from");
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_cost_warnings_flags_expensive_constructs() {
+        assert!(query_cost_warnings("title:clean", 0).is_empty());
+        assert_eq!(query_cost_warnings("title:*code", 0), ["leading wildcard"]);
+        assert_eq!(query_cost_warnings("name:/al.*/", 0), ["regexp"]);
+        assert_eq!(query_cost_warnings("*:martin", 0), ["wildcard fields"]);
+        assert_eq!(query_cost_warnings("kleppman~2", 0), ["fuzzy"]);
+        assert_eq!(query_cost_warnings("", 20_000), ["deep paging"]);
+    }
+
+    #[test]
+    fn timestamp_query_range_reads_bounds() {
+        let now = 0;
+        let utc = TimeZone::utc();
+        let day = |text: &str| parse_timestamp_millis(text, now);
+        assert_eq!(
+            timestamp_query_range("@timestamp:[2024-01-01 TO 2024-01-31]", now, utc),
+            Some((day("2024-01-01"), day("2024-01-31")))
+        );
+        assert_eq!(
+            timestamp_query_range("host:a AND @timestamp:>=2024-01-01", now, utc),
+            Some((day("2024-01-01"), None))
+        );
+        assert_eq!(
+            timestamp_query_range("@timestamp:[* TO 2024-01-31}", now, utc),
+            Some((None, day("2024-01-31")))
+        );
+        assert_eq!(timestamp_query_range("host:a", now, utc), None);
+        let plus_two = TimeZone::from_setting(Some("+02:00")).unwrap();
+        assert_eq!(
+            timestamp_query_range("@timestamp:>=2024-01-01", now, plus_two),
+            Some((day("2024-01-01").map(|millis| millis - 7_200_000), None))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use regex::{Regex, RegexBuilder};
+use serde_json::{Map, Value};
+
+pub const MASK: &str = "***";
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    key_patterns: Vec<Regex>,
+    path_patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(fields: &[String]) -> Self {
+        let mut redactor = Redactor::default();
+        for field in fields {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            let Some(pattern) = glob_regex(field) else {
+                continue;
+            };
+            if field.contains('.') {
+                redactor.path_patterns.push(pattern);
+            } else {
+                redactor.key_patterns.push(pattern);
+            }
+        }
+        redactor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.key_patterns.is_empty() && self.path_patterns.is_empty()
+    }
+
+    pub fn is_sensitive(&self, path: &str, key: &str) -> bool {
+        self.key_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(key))
+            || self
+                .path_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(path))
+    }
+
+    pub fn redact(&self, value: &Value, redacted: &mut usize) -> Value {
+        self.redact_at(value, "", redacted)
+    }
+
+    fn redact_at(&self, value: &Value, prefix: &str, redacted: &mut usize) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut out = Map::new();
+                for (key, value) in map {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    if self.is_sensitive(&path, key) {
+                        *redacted += 1;
+                        out.insert(key.clone(), Value::String(MASK.to_string()));
+                    } else {
+                        out.insert(key.clone(), self.redact_at(value, &path, redacted));
+                    }
+                }
+                Value::Object(out)
+            }
+            Value::Array(values) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| self.redact_at(value, prefix, redacted))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+pub(crate) fn glob_regex(glob: &str) -> Option<Regex> {
+    let pattern = glob
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+    RegexBuilder::new(&format!("^{pattern}$"))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::RedactionConfig;
+
+    #[test]
+    fn default_rules_mask_sensitive_keys_at_any_depth() {
+        let redactor = Redactor::new(&RedactionConfig::default().fields);
+        let mut redacted = 0;
+        let value = json!({
+            "user": { "name": "ann", "Password": "hunter2" },
+            "headers": [{ "Authorization": "Bearer x" }],
+            "message": "token rotated",
+        });
+        let out = redactor.redact(&value, &mut redacted);
+        assert_eq!(redacted, 2);
+        assert_eq!(out["user"]["Password"], MASK);
+        assert_eq!(out["user"]["name"], "ann");
+        assert_eq!(out["headers"][0]["Authorization"], MASK);
+        assert_eq!(out["message"], "token rotated");
+    }
+
+    #[test]
+    fn path_rules_match_dotted_paths() {
+        let redactor = Redactor::new(&["user.email".to_string(), "*.ip".to_string()]);
+        let mut redacted = 0;
+        let value = json!({
+            "email": "top@example.com",
+            "user": { "email": "ann@example.com" },
+            "client": { "geo": { "ip": "10.0.0.1" } },
+            "ip": "10.0.0.2",
+        });
+        let out = redactor.redact(&value, &mut redacted);
+        assert_eq!(redacted, 2);
+        assert_eq!(out["email"], "top@example.com");
+        assert_eq!(out["user"]["email"], MASK);
+        assert_eq!(out["client"]["geo"]["ip"], MASK);
+        assert_eq!(out["ip"], "10.0.0.2");
+        assert!(Redactor::new(&[]).is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::client::*;
+use crate::config::*;
+use crate::terminal::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum WizardField {
+    Url,
+    Auth,
+    Username,
+    Password,
+    ApiKey,
+    Insecure,
+    CaCert,
+}
+
+pub(crate) struct SetupWizard {
+    pub(crate) url: String,
+    pub(crate) auth: AuthMethod,
+    pub(crate) username: String,
+    pub(crate) password: String,
+    pub(crate) api_key: String,
+    pub(crate) insecure: bool,
+    pub(crate) ca_cert: String,
+    pub(crate) field: usize,
+    pub(crate) status: Option<(String, bool)>,
+}
+
+impl SetupWizard {
+    pub(crate) fn new() -> Self {
+        Self {
+            url: ClusterConfig::default().url,
+            auth: AuthMethod::None,
+            username: String::new(),
+            password: String::new(),
+            api_key: String::new(),
+            insecure: false,
+            ca_cert: String::new(),
+            field: 0,
+            status: None,
+        }
+    }
+
+    pub(crate) fn fields(&self) -> Vec<WizardField> {
+        let mut fields = vec![WizardField::Url, WizardField::Auth];
+        match self.auth {
+            AuthMethod::None => {}
+            AuthMethod::Basic => fields.extend([WizardField::Username, WizardField::Password]),
+            AuthMethod::ApiKey => fields.push(WizardField::ApiKey),
+        }
+        fields.extend([WizardField::Insecure, WizardField::CaCert]);
+        fields
+    }
+
+    pub(crate) fn current_field(&self) -> WizardField {
+        let fields = self.fields();
+        fields[self.field.min(fields.len() - 1)]
+    }
+
+    pub(crate) fn shift_field(&mut self, delta: isize) {
+        let len = self.fields().len();
+        let current = self.field.min(len - 1);
+        self.field = if delta >= 0 {
+            (current + 1) % len
+        } else if current == 0 {
+            len - 1
+        } else {
+            current - 1
+        };
+    }
+
+    pub(crate) fn text_mut(&mut self, field: WizardField) -> Option<&mut String> {
+        match field {
+            WizardField::Url => Some(&mut self.url),
+            WizardField::Username => Some(&mut self.username),
+            WizardField::Password => Some(&mut self.password),
+            WizardField::ApiKey => Some(&mut self.api_key),
+            WizardField::CaCert => Some(&mut self.ca_cert),
+            WizardField::Auth | WizardField::Insecure => None,
+        }
+    }
+
+    pub(crate) fn cluster(&self) -> ClusterConfig {
+        let non_empty = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let basic = self.auth == AuthMethod::Basic;
+        ClusterConfig {
+            url: self.url.trim().to_string(),
+            auth: self.auth,
+            username: non_empty(&self.username).filter(|_| basic),
+            password: (!self.password.is_empty() && basic).then(|| self.password.clone()),
+            api_key: non_empty(&self.api_key).filter(|_| self.auth == AuthMethod::ApiKey),
+            insecure: self.insecure,
+            ca_cert: non_empty(&self.ca_cert).map(PathBuf::from),
+        }
+    }
+
+    pub(crate) fn test_connection(&mut self) {
+        let cluster = self.cluster();
+        let result =
+            build_client(&cluster).and_then(|client| fetch_cluster_health(&client, &cluster.url));
+        self.status = Some(match result {
+            Ok(health) => (
+                format!(
+                    "connected to {} (status {})",
+                    health.cluster_name, health.status
+                ),
+                true,
+            ),
+            Err(err) => (format!("connection failed: {err:#}"), false),
+        });
+    }
+}
+
+pub(crate) fn run_setup_wizard(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    signals: &Signals,
+) -> Result<Option<Config>> {
+    let mut wizard = SetupWizard::new();
+    loop {
+        if signals.terminate.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        terminal.draw(|frame| render_setup_wizard(frame, &wizard))?;
+        if !event::poll(Duration::from_millis(200))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let field = wizard.current_field();
+        match key.code {
+            KeyCode::Char('c') if ctrl => return Ok(None),
+            KeyCode::Esc => return Ok(Some(Config::default())),
+            KeyCode::Char('t') if ctrl => wizard.test_connection(),
+            KeyCode::Enter => {
+                let config = Config {
+                    cluster: wizard.cluster(),
+                    ..Config::default()
+                };
+                match save_config(&config) {
+                    Ok(_) => return Ok(Some(config)),
+                    Err(err) => wizard.status = Some((format!("{err:#}"), false)),
+                }
+            }
+            KeyCode::Tab | KeyCode::Down => wizard.shift_field(1),
+            KeyCode::BackTab | KeyCode::Up => wizard.shift_field(-1),
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if field == WizardField::Auth => {
+                wizard.auth = match (wizard.auth, key.code) {
+                    (AuthMethod::None, KeyCode::Left) => AuthMethod::ApiKey,
+                    (AuthMethod::None, _) => AuthMethod::Basic,
+                    (AuthMethod::Basic, KeyCode::Left) => AuthMethod::None,
+                    (AuthMethod::Basic, _) => AuthMethod::ApiKey,
+                    (AuthMethod::ApiKey, KeyCode::Left) => AuthMethod::Basic,
+                    (AuthMethod::ApiKey, _) => AuthMethod::None,
+                };
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ')
+                if field == WizardField::Insecure =>
+            {
+                wizard.insecure = !wizard.insecure;
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = wizard.text_mut(field) {
+                    text.pop();
+                }
+            }
+            KeyCode::Char(ch) => {
+                if let Some(text) = wizard.text_mut(field) {
+                    text.push(ch);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn render_setup_wizard(frame: &mut ratatui::Frame, wizard: &SetupWizard) {
+    let size = frame.size();
+    let label_style = Style::default().fg(Color::Gray);
+    let path = config_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Welcome to index-lens",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(vec![
+            Span::styled("No config found, it will be written to ", label_style),
+            Span::raw(path),
+        ]),
+        Line::from(""),
+    ];
+    let current = wizard.current_field();
+    for field in wizard.fields() {
+        let value = match field {
+            WizardField::Url => wizard.url.clone(),
+            WizardField::Auth => match wizard.auth {
+                AuthMethod::None => "< none >".to_string(),
+                AuthMethod::Basic => "< basic >".to_string(),
+                AuthMethod::ApiKey => "< api key >".to_string(),
+            },
+            WizardField::Username => wizard.username.clone(),
+            WizardField::Password => "*".repeat(wizard.password.chars().count()),
+            WizardField::ApiKey => "*".repeat(wizard.api_key.chars().count()),
+            WizardField::Insecure => if wizard.insecure { "[x]" } else { "[ ]" }.to_string(),
+            WizardField::CaCert => wizard.ca_cert.clone(),
+        };
+        let value_style = if field == current {
+            Style::default().fg(Color::Black).bg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        let marker = if field == current { "> " } else { "  " };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{:<18}", wizard_field_label(field)), label_style),
+            Span::styled(
+                if value.is_empty() {
+                    " ".to_string()
+                } else {
+                    value
+                },
+                value_style,
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+    if let Some((message, ok)) = &wizard.status {
+        let style = if *ok {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::Red)
+        };
+        lines.push(Line::from(Span::styled(message.as_str(), style)));
+    } else {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        "Tab/Up/Down move  Left/Right change  Ctrl-T test  Enter save  Esc skip",
+        label_style,
+    )));
+
+    let width = size.width.saturating_sub(4).min(90);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, area);
+    let wizard_block =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Setup"));
+    frame.render_widget(wizard_block, area);
+}
+
+pub(crate) fn wizard_field_label(field: WizardField) -> &'static str {
+    match field {
+        WizardField::Url => "Cluster URL",
+        WizardField::Auth => "Auth method",
+        WizardField::Username => "Username",
+        WizardField::Password => "Password",
+        WizardField::ApiKey => "API key",
+        WizardField::Insecure => "Skip TLS verify",
+        WizardField::CaCert => "CA cert (PEM)",
+    }
+}
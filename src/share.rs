@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::LOCATION;
+use serde_json::{Value, json};
+
+use crate::app::*;
+use crate::format::*;
+
+pub enum ShareTarget {
+    Link(String),
+    File(PathBuf),
+}
+
+pub struct ShareOutcome {
+    pub target: ShareTarget,
+    pub sampled: usize,
+    pub redacted: usize,
+}
+
+pub fn share_query(app: &App) -> Result<ShareOutcome> {
+    let (payload, sampled, redacted) = share_payload(app);
+    let target = match &app.config.share.endpoint {
+        Some(endpoint) => ShareTarget::Link(upload_share(endpoint, &payload)?),
+        None => ShareTarget::File(write_share_file(&payload)?),
+    };
+    Ok(ShareOutcome {
+        target,
+        sampled,
+        redacted,
+    })
+}
+
+pub fn share_payload(app: &App) -> (Value, usize, usize) {
+    let mut redacted = 0;
+    let sample: Vec<Value> = app
+        .visible_docs()
+        .into_iter()
+        .take(app.config.share.sample_size)
+        .map(|doc| {
+            json!({
+                "_id": doc.id,
+                "_source": app.redactor.redact(&doc.source, &mut redacted),
+            })
+        })
+        .collect();
+    let sampled = sample.len();
+    let cluster = app
+        .health
+        .as_ref()
+        .map(|health| health.cluster_name.clone());
+    let payload = json!({
+        "cluster": cluster,
+        "scope": app.selected_scope_name(),
+        "query": app.query,
+        "total": app.docs_total,
+        "shared_at": format_timestamp_millis(now_millis()),
+        "sample": sample,
+    });
+    (payload, sampled, redacted)
+}
+
+fn upload_share(endpoint: &str, payload: &Value) -> Result<String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(format!("index-lens/{}", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(10))
+        .build()
+        .context("failed to build http client")?;
+    let response = client
+        .post(endpoint)
+        .json(payload)
+        .send()
+        .with_context(|| format!("failed to reach {endpoint}"))?
+        .error_for_status()?;
+    if let Some(location) = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Ok(location.to_string());
+    }
+    let body = response.text().context("invalid share response")?;
+    share_link_from_body(&body).context("share response did not contain a link")
+}
+
+pub fn share_link_from_body(body: &str) -> Option<String> {
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        return ["url", "html_url", "link"]
+            .iter()
+            .find_map(|key| value.get(key).and_then(Value::as_str))
+            .map(|link| link.to_string());
+    }
+    let body = body.trim();
+    body.starts_with("http").then(|| body.to_string())
+}
+
+fn write_share_file(payload: &Value) -> Result<PathBuf> {
+    let dir = dirs::data_local_dir()
+        .context("no data directory available")?
+        .join("index-lens")
+        .join("shares");
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("share-{}.json", now_millis()));
+    let contents = serde_json::to_string_pretty(payload).context("failed to encode share")?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_link_reads_json_or_plain_bodies() {
+        assert_eq!(
+            share_link_from_body(r#"{"html_url":"https://paste.example/abc"}"#).as_deref(),
+            Some("https://paste.example/abc")
+        );
+        assert_eq!(
+            share_link_from_body("https://paste.example/abc\n").as_deref(),
+            Some("https://paste.example/abc")
+        );
+        assert_eq!(share_link_from_body("created"), None);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::client::SortSpec;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppState {
+    pub notes: BTreeMap<String, String>,
+    pub snapshots: BTreeMap<String, ResultSnapshot>,
+    pub query_options: BTreeMap<String, QueryOptions>,
+    pub columns: BTreeMap<String, Vec<String>>,
+    pub favorites: Vec<String>,
+    pub saved_views: Vec<SavedView>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedView {
+    pub name: String,
+    pub scope: String,
+    pub query: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dsl: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortSpec>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_field: Option<String>,
+    pub fields: Vec<String>,
+    pub lenient: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<QueryOperator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_should_match: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum QueryOperator {
+    #[default]
+    And,
+    Or,
+}
+
+impl QueryOperator {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryOperator::And => "AND",
+            QueryOperator::Or => "OR",
+        }
+    }
+
+    pub fn toggled(self) -> QueryOperator {
+        match self {
+            QueryOperator::And => QueryOperator::Or,
+            QueryOperator::Or => QueryOperator::And,
+        }
+    }
+}
+
+impl QueryOptions {
+    pub fn is_default(&self) -> bool {
+        *self == QueryOptions::default()
+    }
+
+    pub fn summary(&self) -> Vec<String> {
+        let mut parts = Vec::new();
+        if !self.fields.is_empty() {
+            parts.push(format!("fields={}", self.fields.join(",")));
+        } else if let Some(field) = &self.default_field {
+            parts.push(format!("default_field={field}"));
+        }
+        if self.lenient {
+            parts.push("lenient".to_string());
+        }
+        if let Some(operator) = self.operator {
+            parts.push(operator.as_str().to_string());
+        }
+        if let Some(msm) = &self.minimum_should_match {
+            parts.push(format!("msm={msm}"));
+        }
+        parts
+    }
+
+    pub fn apply(&self, query_string: &mut serde_json::Value) {
+        if !self.fields.is_empty() {
+            query_string["fields"] = serde_json::json!(self.fields);
+        } else if let Some(field) = &self.default_field {
+            query_string["default_field"] = serde_json::json!(field);
+        }
+        if self.lenient {
+            query_string["lenient"] = serde_json::Value::Bool(true);
+        }
+        if let Some(operator) = self.operator {
+            query_string["default_operator"] = serde_json::json!(operator.as_str());
+        }
+        if let Some(msm) = &self.minimum_should_match {
+            query_string["minimum_should_match"] = serde_json::json!(msm);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultSnapshot {
+    pub scope: String,
+    pub query: String,
+    pub taken_millis: i64,
+    pub total: Option<u64>,
+    pub docs: Vec<SnapshotDoc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotDoc {
+    pub index: String,
+    pub id: String,
+    pub version: Option<u64>,
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl ResultSnapshot {
+    pub fn diff(&self, fresh: &ResultSnapshot) -> SnapshotDiff {
+        let key = |doc: &SnapshotDoc| format!("{}/{}", doc.index, doc.id);
+        let mut diff = SnapshotDiff::default();
+        for doc in &fresh.docs {
+            match self
+                .docs
+                .iter()
+                .find(|old| old.index == doc.index && old.id == doc.id)
+            {
+                None => diff.added.push(key(doc)),
+                Some(old) if old.digest != doc.digest || old.version != doc.version => {
+                    diff.changed.push(key(doc))
+                }
+                Some(_) => {}
+            }
+        }
+        for old in &self.docs {
+            if !fresh
+                .docs
+                .iter()
+                .any(|doc| doc.index == old.index && doc.id == old.id)
+            {
+                diff.removed.push(key(old));
+            }
+        }
+        diff
+    }
+}
+
+pub fn source_digest(source: &serde_json::Value) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in source.to_string().bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
+
+pub fn state_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("INDEX_LENS_STATE") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::data_local_dir().map(|dir| dir.join("index-lens").join("state.toml"))
+}
+
+pub fn load_state() -> Result<AppState> {
+    let Some(path) = state_path() else {
+        return Ok(AppState::default());
+    };
+    if !path.exists() {
+        return Ok(AppState::default());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("invalid state file {}", path.display()))
+}
+
+pub fn save_state(state: &AppState) -> Result<()> {
+    let Some(path) = state_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let text = toml::to_string(state).context("failed to encode state")?;
+    std::fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn doc(id: &str, version: u64, source: serde_json::Value) -> SnapshotDoc {
+        SnapshotDoc {
+            index: "logs".to_string(),
+            id: id.to_string(),
+            version: Some(version),
+            digest: source_digest(&source),
+        }
+    }
+
+    #[test]
+    fn snapshot_diff_reports_added_removed_and_changed() {
+        let before = ResultSnapshot {
+            docs: vec![
+                doc("a", 1, json!({ "n": 1 })),
+                doc("b", 1, json!({ "n": 2 })),
+                doc("c", 1, json!({ "n": 3 })),
+            ],
+            ..ResultSnapshot::default()
+        };
+        let after = ResultSnapshot {
+            docs: vec![
+                doc("a", 1, json!({ "n": 1 })),
+                doc("c", 2, json!({ "n": 4 })),
+                doc("d", 1, json!({ "n": 5 })),
+            ],
+            ..ResultSnapshot::default()
+        };
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, ["logs/d"]);
+        assert_eq!(diff.removed, ["logs/b"]);
+        assert_eq!(diff.changed, ["logs/c"]);
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn query_operator_shows_in_summary_and_survives_toml() {
+        let mut options = QueryOptions {
+            minimum_should_match: Some("75%".to_string()),
+            ..QueryOptions::default()
+        };
+        assert_eq!(options.summary(), ["msm=75%"]);
+        options.operator = Some(options.operator.unwrap_or_default().toggled());
+        assert_eq!(options.operator, Some(QueryOperator::Or));
+        assert_eq!(options.summary(), ["OR", "msm=75%"]);
+        let text = toml::to_string(&options).unwrap();
+        assert!(text.contains("operator = \"OR\""), "{text}");
+        assert_eq!(toml::from_str::<QueryOptions>(&text).unwrap(), options);
+        assert_eq!(QueryOperator::Or.toggled(), QueryOperator::And);
+        let mut query_string = json!({});
+        QueryOptions::default().apply(&mut query_string);
+        assert_eq!(query_string, json!({}));
+    }
+
+    #[test]
+    fn saved_views_round_trip_through_toml() {
+        let state = AppState {
+            saved_views: vec![SavedView {
+                name: "errors".to_string(),
+                scope: "logs-*".to_string(),
+                query: "level:error".to_string(),
+                dsl: false,
+                columns: vec!["@timestamp".to_string(), "message".to_string()],
+                sort: Some(SortSpec {
+                    field: "@timestamp".to_string(),
+                    order: crate::client::SortOrder::Desc,
+                }),
+            }],
+            ..AppState::default()
+        };
+        let text = toml::to_string(&state).unwrap();
+        assert!(text.contains("order = \"desc\""));
+        assert!(!text.contains("dsl"));
+        assert_eq!(toml::from_str::<AppState>(&text).unwrap(), state);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageStats {
+    pub sessions: u64,
+    pub counts: BTreeMap<String, u64>,
+}
+
+impl UsageStats {
+    pub fn record(&mut self, key: impl Into<String>) {
+        *self.counts.entry(key.into()).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: &UsageStats) {
+        self.sessions += other.sessions;
+        for (key, count) in &other.counts {
+            *self.counts.entry(key.clone()).or_default() += count;
+        }
+    }
+}
+
+pub fn stats_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("INDEX_LENS_STATS") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::data_local_dir().map(|dir| dir.join("index-lens").join("stats.toml"))
+}
+
+pub fn load_stats() -> Result<UsageStats> {
+    let Some(path) = stats_path() else {
+        return Ok(UsageStats::default());
+    };
+    if !path.exists() {
+        return Ok(UsageStats::default());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("invalid stats file {}", path.display()))
+}
+
+pub fn save_session_stats(session: &UsageStats) -> Result<()> {
+    let Some(path) = stats_path() else {
+        return Ok(());
+    };
+    let mut stats = load_stats().unwrap_or_default();
+    stats.merge(session);
+    stats.sessions += 1;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let text = toml::to_string(&stats).context("failed to encode stats")?;
+    std::fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+}
+
+pub fn print_stats() -> Result<()> {
+    let stats = load_stats()?;
+    let path = stats_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "-".to_string());
+    println!("usage stats ({path}), kept locally and never sent anywhere");
+    println!("sessions: {}", stats.sessions);
+    let mut counts: Vec<(&String, &u64)> = stats.counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let width = counts.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, count) in counts {
+        println!("{key:<width$}  {count}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_adds_counts_and_sessions() {
+        let mut total = UsageStats::default();
+        total.record("action:Refresh");
+        let mut session = UsageStats::default();
+        session.record("action:Refresh");
+        session.record("command:bench");
+        total.merge(&session);
+        assert_eq!(total.counts["action:Refresh"], 2);
+        assert_eq!(total.counts["command:bench"], 1);
+        let text = toml::to_string(&total).unwrap();
+        assert_eq!(toml::from_str::<UsageStats>(&text).unwrap(), total);
+    }
+}
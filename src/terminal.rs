@@ -0,0 +1,277 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use crossterm::cursor::Show;
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::app::*;
+use crate::client::*;
+use crate::ui::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    None,
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalCaps {
+    pub color: ColorDepth,
+    pub unicode: bool,
+}
+
+impl Default for TerminalCaps {
+    fn default() -> Self {
+        Self {
+            color: ColorDepth::TrueColor,
+            unicode: true,
+        }
+    }
+}
+
+impl TerminalCaps {
+    pub fn detect() -> Self {
+        Self::from_env(|key| std::env::var(key).ok().filter(|value| !value.is_empty()))
+    }
+
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default().to_ascii_lowercase();
+        let colorterm = var("COLORTERM").unwrap_or_default().to_ascii_lowercase();
+        let windows_terminal = var("WT_SESSION").is_some();
+        let color = if var("NO_COLOR").is_some() || term == "dumb" {
+            ColorDepth::None
+        } else if colorterm == "truecolor" || colorterm == "24bit" || windows_terminal {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Basic
+        };
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|key| var(key))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let unicode = if term == "dumb" || term == "linux" {
+            false
+        } else if cfg!(windows) {
+            windows_terminal || var("TERM_PROGRAM").is_some() || !locale.is_empty()
+        } else {
+            locale.contains("utf-8") || locale.contains("utf8")
+        };
+        Self { color, unicode }
+    }
+}
+
+pub(crate) struct CrashContext {
+    pub(crate) state: String,
+    pub(crate) requests: VecDeque<String>,
+}
+
+pub(crate) static CRASH_CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    state: String::new(),
+    requests: VecDeque::new(),
+});
+
+pub(crate) struct Signals {
+    pub(crate) terminate: Arc<AtomicBool>,
+    pub(crate) resumed: Arc<AtomicBool>,
+}
+
+pub(crate) fn setup_terminal() -> Result<()> {
+    enable_raw_mode().context("failed to enable raw mode")?;
+    execute!(io::stdout(), EnterAlternateScreen).context("failed to enter alternate screen")?;
+    Ok(())
+}
+
+pub(crate) fn restore_terminal() {
+    disable_raw_mode().ok();
+    execute!(io::stdout(), LeaveAlternateScreen, Show).ok();
+}
+
+pub(crate) fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+        match write_crash_report(info) {
+            Ok(path) => eprintln!("crash report written to {}", path.display()),
+            Err(err) => eprintln!("failed to write crash report: {err:#}"),
+        }
+    }));
+}
+
+pub(crate) fn record_request(line: String) {
+    if let Ok(mut crash) = CRASH_CONTEXT.lock() {
+        if crash.requests.len() >= 20 {
+            crash.requests.pop_front();
+        }
+        crash.requests.push_back(line);
+    }
+}
+
+pub(crate) fn record_crash_state(app: &App) {
+    let es_url = reqwest::Url::parse(&app.es_url)
+        .map(|url| redact_url(&url))
+        .unwrap_or_else(|_| "<invalid url>".to_string());
+    let state = [
+        format!("es_url: {es_url}"),
+        format!("connection: {:?}", app.connection),
+        format!("scope: {}", scope_label(app)),
+        format!("focus: {:?}", app.focus),
+        format!("input_mode: {:?}", app.input_mode),
+        format!("query_len: {}", app.query.len()),
+        format!(
+            "indices: {} aliases: {} datastreams: {}",
+            app.indices.len(),
+            app.aliases.len(),
+            app.datastreams.len()
+        ),
+        format!(
+            "documents: {} from: {} size: {} total: {:?}",
+            app.documents.len(),
+            app.docs_from,
+            app.docs_size,
+            app.docs_total
+        ),
+        format!(
+            "drawer: {} view: {:?}",
+            app.show_doc_drawer, app.doc_view_mode
+        ),
+        format!("last_error: {}", app.last_error.is_some()),
+    ]
+    .join("\n");
+    if let Ok(mut crash) = CRASH_CONTEXT.lock() {
+        crash.state = state;
+    }
+}
+
+pub(crate) fn write_crash_report(info: &std::panic::PanicHookInfo<'_>) -> Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|value| value.as_secs())
+        .unwrap_or(0);
+    let path = std::env::temp_dir().join(format!("index-lens-crash-{timestamp}.txt"));
+    let backtrace = Backtrace::force_capture();
+    let (state, requests) = match CRASH_CONTEXT.lock() {
+        Ok(crash) => (
+            crash.state.clone(),
+            crash.requests.iter().cloned().collect::<Vec<_>>(),
+        ),
+        Err(_) => (String::new(), Vec::new()),
+    };
+    let report = format!(
+        "index-lens crash report\nversion: {}\ntimestamp: {timestamp}\n\n\
+         == panic ==\n{info}\n\n== state ==\n{state}\n\n\
+         == recent requests ==\n{}\n\n== backtrace ==\n{backtrace}\n",
+        env!("CARGO_PKG_VERSION"),
+        requests.join("\n"),
+    );
+    std::fs::write(&path, report).context("failed to write crash report")?;
+    Ok(path)
+}
+
+pub(crate) fn register_signals() -> Result<Signals> {
+    let terminate = Arc::new(AtomicBool::new(false));
+    let resumed = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&terminate))
+            .context("failed to register signal handler")?;
+    }
+    #[cfg(unix)]
+    {
+        use signal_hook::consts::{SIGCONT, SIGHUP};
+        signal_hook::flag::register(SIGHUP, Arc::clone(&terminate))
+            .context("failed to register signal handler")?;
+        signal_hook::flag::register(SIGCONT, Arc::clone(&resumed))
+            .context("failed to register signal handler")?;
+    }
+    Ok(Signals { terminate, resumed })
+}
+
+#[cfg(unix)]
+pub(crate) fn suspend(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    restore_terminal();
+    signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)
+        .context("failed to suspend process")?;
+    setup_terminal()?;
+    terminal.clear()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn suspend(_terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    Ok(())
+}
+
+pub(crate) fn copy_to_clipboard(text: &str) -> Result<()> {
+    let encoded = BASE64_STANDARD.encode(text);
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{encoded}\x07").context("failed to write clipboard sequence")?;
+    stdout.flush().context("failed to flush clipboard sequence")
+}
+
+pub(crate) fn open_url(url: &str) -> Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    };
+    std::process::Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {program}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(vars: &[(&str, &str)]) -> TerminalCaps {
+        TerminalCaps::from_env(|key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn caps_follow_term_and_locale() {
+        let full = caps(&[
+            ("TERM", "xterm-256color"),
+            ("COLORTERM", "truecolor"),
+            ("LANG", "en_US.UTF-8"),
+        ]);
+        assert_eq!(full.color, ColorDepth::TrueColor);
+        assert!(full.unicode || cfg!(windows));
+        let limited = caps(&[("TERM", "xterm-256color"), ("LANG", "C")]);
+        assert_eq!(limited.color, ColorDepth::Ansi256);
+        let dumb = caps(&[("TERM", "dumb"), ("LANG", "en_US.UTF-8")]);
+        assert_eq!(dumb.color, ColorDepth::None);
+        assert!(!dumb.unicode);
+        assert_eq!(caps(&[("NO_COLOR", "1")]).color, ColorDepth::None);
+    }
+}
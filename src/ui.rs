@@ -0,0 +1,2706 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::Title;
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, Tabs, Wrap,
+};
+use serde_json::Value;
+
+use crate::app::*;
+use crate::client::*;
+use crate::commands::*;
+use crate::config::*;
+use crate::ecs::*;
+use crate::format::*;
+use crate::query::*;
+use crate::terminal::*;
+
+pub(crate) const MIN_WIDTH: u16 = 50;
+pub(crate) const MIN_HEIGHT: u16 = 12;
+
+pub(crate) fn ui(frame: &mut ratatui::Frame, app: &mut App) {
+    let size = frame.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        render_too_small(frame, size);
+    } else {
+        render_main(frame, app);
+    }
+
+    if app.plain() {
+        strip_colors(frame.buffer_mut());
+    } else if app.caps.color != ColorDepth::TrueColor {
+        limit_palette(frame.buffer_mut(), app.caps.color);
+    }
+    if !app.caps.unicode || app.config.display.ascii {
+        asciify(frame.buffer_mut());
+    }
+}
+
+fn render_main(frame: &mut ratatui::Frame, app: &mut App) {
+    let size = frame.size();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(size);
+
+    render_top_bar(frame, chunks[0], app);
+    render_footer(frame, chunks[2], app);
+
+    let body_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)])
+        .split(chunks[1]);
+
+    render_left_nav(frame, body_chunks[0], app);
+    render_right_main(frame, body_chunks[1], app);
+
+    if app.show_doc_drawer {
+        render_doc_drawer(frame, chunks[1], app);
+    }
+
+    if let Some(info_panel) = &app.info_panel {
+        render_info_panel(frame, info_panel, app.plain());
+    }
+
+    if let Some(lookup) = &app.terms_lookup {
+        render_terms_lookup(frame, lookup, app.plain());
+    }
+
+    if app.input_mode == InputMode::Command {
+        render_command_line(frame, ":", &app.command_edit);
+    }
+    if app.input_mode == InputMode::ConfirmIndexAction {
+        render_command_line(frame, "type the index name: ", &app.confirm_edit);
+    }
+}
+
+pub(crate) fn strip_colors(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
+}
+
+pub(crate) fn limit_palette(buffer: &mut Buffer, depth: ColorDepth) {
+    for cell in buffer.content.iter_mut() {
+        cell.fg = downgrade_color(cell.fg, depth);
+        cell.bg = downgrade_color(cell.bg, depth);
+    }
+}
+
+pub(crate) fn downgrade_color(color: Color, depth: ColorDepth) -> Color {
+    let rgb = match color {
+        Color::Rgb(r, g, b) if depth == ColorDepth::Basic => (r, g, b),
+        Color::Indexed(idx) if depth == ColorDepth::Basic && idx >= 16 => indexed_rgb(idx),
+        Color::Indexed(idx) if depth == ColorDepth::Basic => return BASIC_COLORS[idx as usize].0,
+        Color::Rgb(r, g, b) if depth == ColorDepth::Ansi256 => {
+            let level = |value: u8| ((value as u16 * 5 + 127) / 255) as u8;
+            return Color::Indexed(16 + 36 * level(r) + 6 * level(g) + level(b));
+        }
+        _ => return color,
+    };
+    nearest_basic(rgb)
+}
+
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (128, 0, 0)),
+    (Color::Green, (0, 128, 0)),
+    (Color::Yellow, (128, 128, 0)),
+    (Color::Blue, (0, 0, 128)),
+    (Color::Magenta, (128, 0, 128)),
+    (Color::Cyan, (0, 128, 128)),
+    (Color::Gray, (192, 192, 192)),
+    (Color::DarkGray, (128, 128, 128)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (0, 0, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn indexed_rgb(idx: u8) -> (u8, u8, u8) {
+    if idx >= 232 {
+        let level = 8 + (idx - 232) * 10;
+        return (level, level, level);
+    }
+    let idx = idx - 16;
+    let scale = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+    (scale(idx / 36), scale(idx / 6 % 6), scale(idx % 6))
+}
+
+fn nearest_basic((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        let diff = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        diff(r, cr) + diff(g, cg) + diff(b, cb)
+    };
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+pub(crate) fn asciify(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        let replacement = match cell.symbol() {
+            "─" | "━" | "═" => "-",
+            "│" | "┃" | "║" => "|",
+            "┌" | "┐" | "└" | "┘" | "├" | "┤" | "┬" | "┴" | "┼" | "╭" | "╮" | "╰" | "╯" => {
+                "+"
+            }
+            "…" => ".",
+            "▲" => "^",
+            "▼" => "v",
+            symbol if symbol.is_ascii() => continue,
+            _ => "?",
+        };
+        cell.set_symbol(replacement);
+    }
+}
+
+pub(crate) fn panel<'a>(plain: bool, title: impl Into<Title<'a>>) -> Block<'a> {
+    let borders = if plain { Borders::TOP } else { Borders::ALL };
+    Block::default().borders(borders).title(title)
+}
+
+pub(crate) fn render_too_small(frame: &mut ratatui::Frame, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("current {}x{}", area.width, area.height)),
+        Line::from(format!("need at least {MIN_WIDTH}x{MIN_HEIGHT}")),
+        Line::from("resize the window or press q to quit"),
+    ];
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let message_area = Rect {
+        x: area.x,
+        y: area.y + top,
+        width: area.width,
+        height: area.height.saturating_sub(top),
+    };
+    let message = Paragraph::new(lines).alignment(Alignment::Center);
+    frame.render_widget(message, message_area);
+}
+
+pub(crate) fn render_top_bar(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let label_style = Style::default().fg(Color::Gray);
+    let cluster_name = app
+        .health
+        .as_ref()
+        .map(|health| health.cluster_name.as_str())
+        .unwrap_or("-");
+    let cluster_name = match &app.cluster_name {
+        Some(profile) => format!("{profile}/{cluster_name}"),
+        None => cluster_name.to_string(),
+    };
+    let cluster_style = app
+        .health
+        .as_ref()
+        .map(|health| status_style(&health.status))
+        .unwrap_or_else(|| Style::default().fg(Color::Gray));
+    let auth = auth_label(&app.config.cluster, &app.es_url);
+    let scope = scope_label(app);
+    let mode = app.query_mode.label();
+    let (status_text, status_style) = status_summary(app);
+    let (conn_text, conn_style) = connection_summary(app);
+    let cluster_status = match (&app.health, app.plain()) {
+        (Some(health), true) => format!(" [{}]", health.status),
+        _ => String::new(),
+    };
+
+    let mut spans = vec![
+        Span::styled("cluster:", label_style),
+        Span::raw(" "),
+        Span::styled(cluster_name, cluster_style),
+        Span::raw(cluster_status),
+        Span::raw("  "),
+        Span::styled("conn:", label_style),
+        Span::raw(" "),
+        Span::styled(conn_text, conn_style),
+        Span::raw("  "),
+        Span::styled("auth:", label_style),
+        Span::raw(" "),
+        Span::raw(auth),
+        Span::raw("  "),
+        Span::styled("scope:", label_style),
+        Span::raw(" "),
+        Span::raw(scope),
+        Span::raw("  "),
+        Span::styled("mode:", label_style),
+        Span::raw(" "),
+        Span::raw(mode),
+        Span::raw("  "),
+        Span::styled(status_text, status_style),
+        Span::raw("  "),
+        Span::styled("tz:", label_style),
+        Span::raw(" "),
+        Span::raw(app.time_zone.label()),
+    ];
+    if let Some(version) = &app.update_available {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            format!("update {version} available"),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    let deprecations = deprecation_warning_count();
+    if deprecations > 0 {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            format!("deprecations: {deprecations}"),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(warning) = app
+        .license
+        .as_ref()
+        .and_then(|license| license_warning(license, now_millis()))
+    {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            warning,
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+    }
+    if app.plain() {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled("focus:", label_style));
+        spans.push(Span::raw(" "));
+        spans.push(Span::raw(focus_label(app)));
+    }
+
+    let header = Paragraph::new(Line::from(spans)).block(panel(
+        app.plain(),
+        freshness_title(app, "TopBar", DataKind::Health),
+    ));
+    frame.render_widget(header, area);
+}
+
+pub(crate) fn render_left_nav(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
+    let mut constraints = vec![
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(3),
+    ];
+    let show_favorites = area.height >= 3 + 3 + 7 + 5;
+    let show_saved_views = area.height >= 3 + 3 + 7 + 5 + 7;
+    if show_favorites {
+        constraints.push(Constraint::Length(5));
+    }
+    if show_saved_views {
+        constraints.push(Constraint::Length(7));
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+
+    let tabs = Tabs::new(
+        [
+            ScopeKind::Indices,
+            ScopeKind::Aliases,
+            ScopeKind::DataStreams,
+        ]
+        .into_iter()
+        .map(|kind| {
+            let count = app.filtered_scope_indices_for(kind).len();
+            Line::from(format!("{} ({count})", scope_title(kind)))
+        })
+        .collect::<Vec<_>>(),
+    )
+    .select(scope_tab_index(app.scope_kind))
+    .highlight_style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(panel(app.plain(), "Scope"));
+    frame.render_widget(tabs, chunks[0]);
+
+    let filter_text = match app.input_mode {
+        InputMode::ScopeFilter => app.scope_filter_edit.as_str(),
+        _ => app.scope_filter.as_str(),
+    };
+    let mut filter_spans = vec![
+        Span::styled("Filter", Style::default().fg(Color::Gray)),
+        Span::raw(": "),
+        Span::raw(if filter_text.is_empty() {
+            "-"
+        } else {
+            filter_text
+        }),
+    ];
+    match ScopeMatcher::parse(filter_text) {
+        ScopeMatcher::Regex(_) => {
+            filter_spans.push(Span::styled(" (regex)", Style::default().fg(Color::Cyan)));
+        }
+        ScopeMatcher::Invalid(err) => {
+            filter_spans.push(Span::styled(
+                format!(" invalid regex: {err}"),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        _ => {}
+    }
+    let filter_line = Line::from(filter_spans);
+    let filter_block = Paragraph::new(filter_line).block(panel(app.plain(), "Search"));
+    frame.render_widget(filter_block, chunks[1]);
+
+    let (scope_items, mut scope_state) = build_scope_items(app);
+    let scope_list = List::new(scope_items)
+        .block(panel(
+            app.plain(),
+            freshness_title(
+                app,
+                scope_title(app.scope_kind),
+                DataKind::for_scope(app.scope_kind),
+            ),
+        ))
+        .highlight_style(list_focus_style(app.focus == Focus::LeftNav))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(scope_list, chunks[2], &mut scope_state);
+
+    if !show_favorites {
+        return;
+    }
+
+    let favorites = app.favorites();
+    let favorites_items: Vec<ListItem> = if favorites.is_empty() {
+        vec![ListItem::new(Line::from("No favorites"))]
+    } else {
+        let selected = app.selected_scope_name();
+        favorites
+            .iter()
+            .map(|name| {
+                let style = if selected == Some(*name) {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(Span::styled(name.to_string(), style)))
+            })
+            .collect()
+    };
+    let favorites = List::new(favorites_items).block(panel(app.plain(), "Favorites"));
+    frame.render_widget(favorites, chunks[3]);
+
+    if !show_saved_views {
+        return;
+    }
+
+    let saved_view_items: Vec<ListItem> = if app.state.saved_views.is_empty() {
+        vec![ListItem::new(Line::from("No saved views"))]
+    } else {
+        app.state
+            .saved_views
+            .iter()
+            .map(|view| {
+                let summary = format!("{}  {}  {}", view.name, view.scope, view.query);
+                ListItem::new(Line::from(truncate_string(&summary, 60)))
+            })
+            .collect()
+    };
+    let saved_views = List::new(saved_view_items).block(panel(app.plain(), "Saved Views"));
+    frame.render_widget(saved_views, chunks[4]);
+}
+
+pub(crate) fn render_right_main(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(0)])
+        .split(area);
+
+    let query_line = query_line(app);
+    let filter_line = filter_chips_line(app);
+    let results_line = results_summary_line(app);
+    let query_block = Paragraph::new(vec![query_line, filter_line, results_line])
+        .block(panel(app.plain(), "Query"));
+    frame.render_widget(query_block, chunks[0]);
+
+    if app.right_tab == RightTab::Mappings {
+        render_mappings(frame, chunks[1], app);
+        return;
+    }
+
+    let mut title = results_title(
+        app.docs_from,
+        app.documents.len() as u64,
+        app.docs_total,
+        app.config.display.raw_numbers,
+    );
+    let visible_docs = app.visible_docs();
+    let filter = app.results_filter.as_str();
+    let editing_filter = app.input_mode == InputMode::ResultsFilter;
+    if editing_filter || !filter.is_empty() {
+        let cursor = if editing_filter { "_" } else { "" };
+        title.push_str(&format!(
+            " find: {filter}{cursor} ({}/{})",
+            visible_docs.len(),
+            app.documents.len()
+        ));
+    }
+    if app.input_mode == InputMode::Jump {
+        title.push_str(&format!(" jump to row or text: {}_", app.jump_edit));
+    }
+    if !app.basket.is_empty() {
+        title.push_str(&format!(" basket: {}", app.basket.len()));
+    }
+    if app.input_mode == InputMode::ExportPath {
+        title.push_str(&format!(" export to: {}_", app.export_edit));
+    }
+    for job in app
+        .jobs
+        .iter()
+        .filter(|job| job.progress.is_some() || job.bench.is_some())
+    {
+        let total = job.total.map_or("?".to_string(), |total| total.to_string());
+        title.push_str(&format!(" {} {}/{total}", job.label, job.written));
+    }
+    if app.documents.is_empty() {
+        let title = freshness_title(app, title, DataKind::Docs);
+        let empty = Paragraph::new(results_empty_state(app)).block(panel(app.plain(), title));
+        frame.render_widget(empty, chunks[1]);
+        return;
+    }
+    let ignore_case = filter_ignores_case(filter);
+    let id_width = result_id_width(chunks[1].width);
+    let path_column = app
+        .json_path
+        .as_ref()
+        .filter(|view| view.column)
+        .map(|view| view.expr.as_str());
+    let path_width = if path_column.is_some() {
+        chunks[1].width.saturating_sub(id_width) / 3
+    } else {
+        0
+    };
+    let enrich_columns: Vec<&EnrichConfig> = app
+        .config
+        .enrich
+        .iter()
+        .filter(|lookup| {
+            visible_docs
+                .iter()
+                .any(|doc| ecs_field(&doc.source, &lookup.field).is_some())
+        })
+        .collect();
+    let enrich_width = 20;
+    let summary_width = chunks[1]
+        .width
+        .saturating_sub(id_width + path_width + enrich_width * enrich_columns.len() as u16 + 5);
+    let columns = app.result_columns();
+    let highlight_rules = app.config.highlight_rules();
+    let column_width = summary_width / columns.len().max(1) as u16;
+
+    let log_view = app.log_view_active();
+    let rows: Vec<Row> = if visible_docs.is_empty() {
+        vec![Row::new(vec![
+            Cell::from("No matches"),
+            Cell::from(format!("no loaded document contains {filter:?}")),
+        ])]
+    } else if log_view {
+        visible_docs
+            .iter()
+            .map(|doc| log_view_row(app, doc, filter, ignore_case))
+            .collect()
+    } else {
+        visible_docs
+            .iter()
+            .map(|doc| {
+                let id = truncate_string(&doc.id, id_width as usize);
+                let mut cells = vec![Cell::from(highlight_line_with(&id, filter, ignore_case))];
+                if let Some(expr) = path_column {
+                    let extracted = json_path_text(&app.display_source(doc), expr);
+                    let extracted = truncate_string(&extracted, path_width as usize);
+                    cells.push(Cell::from(highlight_line_with(
+                        &extracted,
+                        filter,
+                        ignore_case,
+                    )));
+                }
+                for lookup in &enrich_columns {
+                    let enriched = app.enriched_value(lookup, doc).unwrap_or_default();
+                    cells.push(Cell::from(Span::styled(
+                        truncate_string(&enriched, enrich_width as usize),
+                        Style::default().fg(Color::Cyan),
+                    )));
+                }
+                if columns.is_empty() {
+                    let preview = doc_summary(app, doc, summary_width as usize);
+                    cells.push(Cell::from(highlight_line_with(
+                        &preview,
+                        filter,
+                        ignore_case,
+                    )));
+                } else {
+                    let source = app.display_source(doc);
+                    for column in columns {
+                        let value = ecs_text(&source, column).unwrap_or_default();
+                        let value = truncate_string(&value, column_width as usize);
+                        let style = ecs_field(&source, column)
+                            .and_then(|raw| value_highlight_style(&highlight_rules, column, raw));
+                        cells.push(match style {
+                            Some(style) => Cell::from(Span::styled(value, style)),
+                            None => Cell::from(highlight_line_with(&value, filter, ignore_case)),
+                        });
+                    }
+                }
+                if app.is_marked(doc) {
+                    Row::new(cells).style(Style::default().fg(Color::Magenta))
+                } else {
+                    Row::new(cells)
+                }
+            })
+            .collect()
+    };
+    let mut header_cells = vec![Cell::from("id")];
+    let mut widths = vec![Constraint::Length(id_width)];
+    if log_view {
+        header_cells = ["time", "level", "service", "message"]
+            .into_iter()
+            .map(Cell::from)
+            .collect();
+        widths = vec![
+            Constraint::Length(19),
+            Constraint::Length(7),
+            Constraint::Length(14),
+            Constraint::Min(10),
+        ];
+    } else {
+        if let Some(expr) = path_column {
+            header_cells.push(Cell::from(truncate_string(expr, path_width as usize)));
+            widths.push(Constraint::Length(path_width));
+        }
+        for lookup in &enrich_columns {
+            let label = format!("{}>{}", lookup.field, lookup.display);
+            header_cells.push(Cell::from(truncate_string(&label, enrich_width as usize)));
+            widths.push(Constraint::Length(enrich_width));
+        }
+        if columns.is_empty() {
+            header_cells.push(Cell::from("preview"));
+            widths.push(Constraint::Min(10));
+        } else {
+            let selected_column = app.selected_column();
+            for (index, column) in columns.iter().enumerate() {
+                let label = truncate_string(column, column_width as usize);
+                header_cells.push(
+                    if app.focus == Focus::Results && selected_column == Some(column.as_str()) {
+                        Cell::from(label).style(Style::default().add_modifier(Modifier::REVERSED))
+                    } else {
+                        Cell::from(label)
+                    },
+                );
+                widths.push(if index + 1 == columns.len() {
+                    Constraint::Min(10)
+                } else {
+                    Constraint::Length(column_width)
+                });
+            }
+        }
+    }
+    let header = Row::new(header_cells).style(
+        Style::default()
+            .fg(Color::Gray)
+            .add_modifier(Modifier::BOLD),
+    );
+    let mut footer_cells = vec![Cell::from("page")];
+    let mut has_stats = false;
+    if !log_view && !visible_docs.is_empty() {
+        if path_column.is_some() {
+            footer_cells.push(Cell::from(""));
+        }
+        footer_cells.extend(enrich_columns.iter().map(|_| Cell::from("")));
+        let sources: Vec<_> = visible_docs
+            .iter()
+            .map(|doc| app.display_source(doc))
+            .collect();
+        for column in columns {
+            let stats = numeric_column_stats(sources.iter().map(|source| source.as_ref()), column);
+            has_stats |= stats.is_some();
+            let text = stats.map(|stats| stats.summary()).unwrap_or_default();
+            footer_cells.push(Cell::from(truncate_string(&text, column_width as usize)));
+        }
+    }
+    let title = freshness_title(app, title, DataKind::Docs);
+    let mut table = Table::new(rows, widths)
+        .header(header)
+        .block(panel(app.plain(), title))
+        .highlight_style(list_focus_style(app.focus == Focus::Results));
+    if has_stats {
+        table = table.footer(Row::new(footer_cells).style(Style::default().fg(Color::Gray)));
+    }
+    frame.render_stateful_widget(table, chunks[1], &mut app.docs_state);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NumericStats {
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+    pub(crate) avg: f64,
+}
+
+impl NumericStats {
+    pub(crate) fn summary(&self) -> String {
+        let number = |value: f64| {
+            if value.fract() == 0.0 && value.abs() < 1e15 {
+                format!("{value:.0}")
+            } else {
+                format!("{value:.2}")
+            }
+        };
+        format!(
+            "{}..{} avg {}",
+            number(self.min),
+            number(self.max),
+            number(self.avg)
+        )
+    }
+}
+
+pub(crate) fn numeric_column_stats<'a>(
+    sources: impl IntoIterator<Item = &'a Value>,
+    field: &str,
+) -> Option<NumericStats> {
+    let values: Vec<f64> = sources
+        .into_iter()
+        .filter_map(|source| ecs_field(source, field).and_then(Value::as_f64))
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    Some(NumericStats {
+        min: values.iter().copied().fold(f64::INFINITY, f64::min),
+        max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        avg: values.iter().sum::<f64>() / values.len() as f64,
+    })
+}
+
+pub(crate) fn render_mappings(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
+    let focused = app.focus == Focus::Results;
+    let plain = app.plain();
+    let Some(view) = app.mapping.as_mut() else {
+        let message = Paragraph::new("No mapping loaded (m to return to results)")
+            .block(panel(plain, "Mappings"));
+        frame.render_widget(message, area);
+        return;
+    };
+    let title = format!("Mappings: {} ({} fields)", view.scope, view.fields.len());
+    let items: Vec<ListItem> = if view.fields.is_empty() {
+        vec![ListItem::new(Line::from("No mapped fields"))]
+    } else {
+        view.fields.iter().map(mapping_field_item).collect()
+    };
+    let list = List::new(items)
+        .block(panel(plain, title))
+        .highlight_style(list_focus_style(focused));
+    frame.render_stateful_widget(list, area, &mut view.state);
+}
+
+fn mapping_field_item(field: &MappingField) -> ListItem<'static> {
+    let label_style = Style::default().fg(Color::Gray);
+    let indent = "  ".repeat(field.depth);
+    let name = if field.multi_field {
+        format!("{indent}.{}", field.name)
+    } else {
+        format!("{indent}{}", field.name)
+    };
+    let type_style = match field.field_type.as_str() {
+        "object" | "nested" => Style::default().fg(Color::Blue),
+        "text" | "match_only_text" => Style::default().fg(Color::Green),
+        "keyword" | "constant_keyword" | "wildcard" => Style::default().fg(Color::Cyan),
+        "date" | "date_nanos" => Style::default().fg(Color::Magenta),
+        _ => Style::default().fg(Color::Yellow),
+    };
+    let mut spans = vec![
+        Span::raw(format!("{name:<32} ")),
+        Span::styled(format!("{:<12}", field.field_type), type_style),
+    ];
+    if let Some(analyzer) = &field.analyzer {
+        spans.push(Span::styled(format!(" analyzer={analyzer}"), label_style));
+    }
+    if let Some(analyzer) = &field.search_analyzer {
+        spans.push(Span::styled(
+            format!(" search_analyzer={analyzer}"),
+            label_style,
+        ));
+    }
+    if field.multi_field {
+        spans.push(Span::styled(" multi-field", label_style));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+pub(crate) fn render_doc_drawer(frame: &mut ratatui::Frame, body: Rect, app: &App) {
+    if body.height < 5 {
+        return;
+    }
+    let drawer_width = drawer_width(body.width);
+    let drawer_area = Rect {
+        x: body.right().saturating_sub(drawer_width),
+        y: body.y,
+        width: drawer_width,
+        height: body.height,
+    };
+    frame.render_widget(Clear, drawer_area);
+    let lines = doc_drawer_lines(app, drawer_area.height.saturating_sub(2) as usize);
+    let drawer = Paragraph::new(lines).block(panel(app.plain(), "Doc"));
+    frame.render_widget(drawer, drawer_area);
+}
+
+pub(crate) fn render_info_panel(frame: &mut ratatui::Frame, info_panel: &InfoPanel, plain: bool) {
+    let size = frame.size();
+    let width = (size.width.saturating_mul(70) / 100)
+        .max(40)
+        .min(size.width);
+    let height = (size.height.saturating_mul(70) / 100)
+        .max(8)
+        .min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, area);
+    let title = format!("{} (Esc close, Up/Down scroll)", info_panel.title);
+    let mut body = Paragraph::new(info_panel.lines.clone())
+        .block(panel(plain, title))
+        .scroll((info_panel.scroll, 0));
+    if info_panel.wrap {
+        body = body.wrap(Wrap { trim: false });
+    }
+    frame.render_widget(body, area);
+}
+
+pub(crate) fn render_terms_lookup(frame: &mut ratatui::Frame, lookup: &TermsLookup, plain: bool) {
+    let size = frame.size();
+    let width = 50.min(size.width);
+    let height = 16.min(size.height);
+    let area = Rect {
+        x: size.width.saturating_sub(width) / 2,
+        y: size.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, area);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("prefix: ", Style::default().fg(Color::Gray)),
+            Span::raw(lookup.prefix.clone()),
+            Span::styled("_", Style::default().fg(Color::Cyan)),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(error) = &lookup.error {
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    } else if lookup.terms.is_empty() {
+        lines.push(Line::from("No matching terms"));
+    }
+    for (idx, term) in lookup.terms.iter().enumerate() {
+        let style = if idx == lookup.selected {
+            list_focus_style(true)
+        } else {
+            Style::default()
+        };
+        let marker = if idx == lookup.selected { "> " } else { "  " };
+        lines.push(Line::from(Span::styled(format!("{marker}{term}"), style)));
+    }
+    if !lookup.terms.is_empty() && !lookup.complete {
+        lines.push(Line::from(Span::styled(
+            "(partial results)",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    let title = format!("Terms: {} (Enter add to query)", lookup.field);
+    let body = Paragraph::new(lines).block(panel(plain, title));
+    frame.render_widget(body, area);
+}
+
+pub(crate) fn render_footer(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let key_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let label_style = Style::default().fg(Color::Gray);
+    let mut spans = Vec::new();
+    let hints = match app.active_toast() {
+        Some(toast) => {
+            let style = if toast.error {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Green)
+            };
+            spans.push(Span::styled(toast.message.clone(), style));
+            Vec::new()
+        }
+        None => footer_hints(app),
+    };
+    for (key, label) in hints {
+        if !spans.is_empty() {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(key, key_style));
+        spans.push(Span::styled(format!(" {label}"), label_style));
+    }
+    let crumbs = nav_breadcrumb(app);
+    let hints_width = Line::from(spans.clone()).width() as u16;
+    let remaining = area.width.saturating_sub(hints_width + 2);
+    let crumbs_width = if remaining < 8 {
+        0
+    } else {
+        (crumbs.chars().count() as u16).min(remaining)
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(crumbs_width)])
+        .split(area);
+    frame.render_widget(Paragraph::new(Line::from(spans)), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            truncate_start(&crumbs, crumbs_width as usize),
+            label_style,
+        ))
+        .alignment(Alignment::Right),
+        chunks[1],
+    );
+}
+
+pub(crate) fn nav_breadcrumb(app: &App) -> String {
+    let cluster = app
+        .health
+        .as_ref()
+        .map(|health| health.cluster_name.as_str())
+        .unwrap_or("-");
+    let mut crumbs = vec![cluster.to_string()];
+    if let Some(scope) = app.selected_scope_name() {
+        crumbs.push(scope.to_string());
+    }
+    if !app.query.is_empty() {
+        crumbs.push(app.query.clone());
+    }
+    if app.show_doc_drawer
+        && let Some(doc) = app.selected_doc()
+    {
+        crumbs.push(doc.id.clone());
+    }
+    crumbs.join(" > ")
+}
+
+pub(crate) fn footer_hints(app: &App) -> Vec<(&'static str, &'static str)> {
+    match app.input_mode {
+        InputMode::Query => return vec![("Enter", "search"), ("Esc", "cancel")],
+        InputMode::Command => return vec![("Enter", "run"), ("Esc", "cancel")],
+        InputMode::Jump => return vec![("Enter", "keep (empty: last row)"), ("Esc", "back")],
+        InputMode::DrawerSearch => return vec![("Enter", "keep"), ("Esc", "clear")],
+        InputMode::ExportPath => {
+            return vec![("Enter", "export (.ndjson .csv .json)"), ("Esc", "cancel")];
+        }
+        InputMode::ConfirmIndexAction => return vec![("Enter", "confirm"), ("Esc", "cancel")],
+        InputMode::ScopeFilter | InputMode::ResultsFilter => {
+            return vec![("Enter", "keep filter"), ("Esc", "clear")];
+        }
+        InputMode::TermsPrefix => {
+            return vec![("Up/Down", "pick"), ("Enter", "insert"), ("Esc", "cancel")];
+        }
+        InputMode::Normal => {}
+    }
+    match app.prompt {
+        Some(Prompt::Quit) => {
+            return vec![
+                ("c", "cancel tasks and quit"),
+                ("d", "detach and quit"),
+                ("Esc", "stay"),
+            ];
+        }
+        Some(Prompt::Reconnect(_)) => return vec![("y", "reconnect"), ("n", "keep current")],
+        Some(Prompt::OpenScope(_)) => return vec![("y", "search pattern"), ("n", "cancel")],
+        Some(Prompt::PickCluster(_)) => {
+            return vec![("Up/Down", "pick"), ("Enter", "switch"), ("Esc", "cancel")];
+        }
+        Some(Prompt::PickSort { .. }) => {
+            return vec![
+                ("Up/Down", "field"),
+                ("Left/Right", "order"),
+                ("Enter", "apply"),
+                ("Esc", "cancel"),
+            ];
+        }
+        Some(Prompt::PickFavorite(_)) => {
+            return vec![("Up/Down", "pick"), ("Enter", "open"), ("Esc", "cancel")];
+        }
+        Some(Prompt::PickView(_)) => {
+            return vec![
+                ("Up/Down", "pick"),
+                ("Enter", "load"),
+                ("x", "delete"),
+                ("Esc", "cancel"),
+            ];
+        }
+        Some(Prompt::PickBlob(_)) => {
+            return vec![
+                ("Up/Down", "pick"),
+                ("Enter", "open/save"),
+                ("Esc", "cancel"),
+            ];
+        }
+        Some(Prompt::PickBasket(_)) => {
+            return vec![
+                ("Enter", "open"),
+                ("d", "diff next"),
+                ("x", "remove"),
+                ("e", "export"),
+                ("c", "clear"),
+                ("Esc", "close"),
+            ];
+        }
+        Some(Prompt::AliasChange(_)) => return vec![("y", "apply"), ("n", "cancel")],
+        Some(Prompt::IndexActions { .. }) => {
+            return vec![("Up/Down", "pick"), ("Enter", "run"), ("Esc", "cancel")];
+        }
+        Some(Prompt::HealthDetail { .. }) => {
+            return vec![
+                ("Up/Down", "scroll"),
+                ("a", "explain unassigned"),
+                ("Esc", "close"),
+            ];
+        }
+        Some(Prompt::ExportMarked(_)) => {
+            return vec![("Up/Down", "pick"), ("Enter", "export"), ("Esc", "cancel")];
+        }
+        Some(Prompt::PickColumns(_)) => {
+            return vec![("Up/Down", "field"), ("Space", "toggle"), ("Enter", "done")];
+        }
+        Some(Prompt::BuildAggregation { .. }) => {
+            return vec![
+                ("Up/Down", "field"),
+                ("Left/Right", "type"),
+                ("Enter", "run"),
+                ("Esc", "cancel"),
+            ];
+        }
+        None => {}
+    }
+    if app.info_panel.is_some() {
+        return vec![
+            ("Esc", "close"),
+            ("Up/Down", "scroll"),
+            ("PgUp/PgDn", "page"),
+        ];
+    }
+    let mut hints = match (app.focus, app.show_doc_drawer) {
+        (Focus::Results, _) if app.right_tab == RightTab::Mappings => {
+            vec![("Up/Down", "field"), ("m", "results")]
+        }
+        (Focus::Results, true) => vec![
+            ("Up/Down", "document"),
+            ("v", "view"),
+            ("y", "permalink"),
+            ("R", "re-fetch"),
+            ("Esc", "close"),
+            ("f", "find"),
+        ],
+        (Focus::Results, false) => vec![
+            ("Up/Down", "select"),
+            ("Enter", "open"),
+            ("n/p", "load more"),
+            ("f", "find"),
+            ("G", "jump"),
+        ],
+        (Focus::LeftNav, _) => {
+            vec![
+                ("Up/Down", "select"),
+                ("Enter", "details"),
+                ("1-3", "kind"),
+                ("^F", "filter"),
+            ]
+        }
+    };
+    if app.show_doc_drawer
+        && app
+            .selected_doc()
+            .is_some_and(|doc| ecs_field(&doc.source, "trace.id").is_some())
+    {
+        hints.insert(4, ("t", "trace"));
+    }
+    if !app.nav_back.is_empty() {
+        hints.push(("^O", "back"));
+    }
+    if !app.nav_forward.is_empty() {
+        hints.push(("Alt-Right", "forward"));
+    }
+    hints.extend([
+        ("/", "query"),
+        (":", "command"),
+        ("Tab", "focus"),
+        ("q", "quit"),
+    ]);
+    hints
+}
+
+pub(crate) fn render_command_line(frame: &mut ratatui::Frame, prefix: &str, command: &str) {
+    let size = frame.size();
+    let area = Rect {
+        x: 0,
+        y: size.height.saturating_sub(1),
+        width: size.width,
+        height: 1,
+    };
+    frame.render_widget(Clear, area);
+    let line = Line::from(vec![
+        Span::styled(prefix.to_string(), Style::default().fg(Color::Cyan)),
+        Span::raw(command.to_string()),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+pub(crate) fn scope_tab_index(scope: ScopeKind) -> usize {
+    match scope {
+        ScopeKind::Indices => 0,
+        ScopeKind::Aliases => 1,
+        ScopeKind::DataStreams => 2,
+    }
+}
+
+pub(crate) fn empty_state_lines(
+    headline: impl Into<String>,
+    detail: Option<String>,
+    hints: &[(&str, &str)],
+) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(Span::styled(
+        headline.into(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    if let Some(detail) = detail {
+        lines.push(Line::from(Span::styled(
+            detail,
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    lines.push(Line::from(""));
+    for (key, action) in hints {
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {key}"), Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" {action}")),
+        ]));
+    }
+    lines
+}
+
+pub(crate) fn scope_empty_state(app: &App) -> Vec<Line<'static>> {
+    let kind = scope_title(app.scope_kind);
+    if matches!(app.connection, ConnectionState::Disconnected { .. }) {
+        return empty_state_lines("Cluster unreachable", None, &[("r", "retry")]);
+    }
+    if !app.scope_filter.is_empty() {
+        return empty_state_lines(
+            format!("No {kind} match"),
+            Some(app.scope_filter.clone()),
+            &[("^F", "edit filter"), ("1-3", "other kind")],
+        );
+    }
+    empty_state_lines(
+        format!("No {kind} here"),
+        None,
+        &[("1-3", "other kind"), ("r", "refresh")],
+    )
+}
+
+pub(crate) fn results_empty_state(app: &App) -> Vec<Line<'static>> {
+    if matches!(app.connection, ConnectionState::Disconnected { .. }) {
+        return empty_state_lines(
+            "Cluster unreachable",
+            Some("results load again once the connection is back".to_string()),
+            &[("r", "retry now"), (":cluster", "switch cluster")],
+        );
+    }
+    let Some(scope) = app.selected_scope_name() else {
+        if app.filtered_scope_indices().is_empty() {
+            return empty_state_lines(
+                "Nothing to search yet",
+                Some(format!("the {} list is empty", scope_title(app.scope_kind))),
+                &[
+                    ("1-3", "switch kind"),
+                    ("^F", "change the scope filter"),
+                    ("r", "refresh"),
+                ],
+            );
+        }
+        return empty_state_lines(
+            "No scope selected",
+            Some("pick an index, alias or data stream on the left".to_string()),
+            &[
+                ("Tab", "focus the scope list"),
+                ("Up/Down", "pick a scope"),
+                ("1-3", "switch kind"),
+                ("^F", "filter scopes"),
+            ],
+        );
+    };
+    let query = app.query.trim();
+    if !query.is_empty() || !app.filters.is_empty() {
+        let mut hints = vec![("/", "edit the query")];
+        if !app.filters.is_empty() {
+            hints.push((":filter clear", "drop filter chips"));
+        }
+        if !app.fuzzy && !query.is_empty() {
+            hints.push((":fuzzy on", "try fuzzy matching"));
+        }
+        let detail = if query.is_empty() {
+            format!("{scope} with {} filter(s)", app.filters.len())
+        } else {
+            format!("{scope}: {query}")
+        };
+        return empty_state_lines("No documents match", Some(detail), &hints);
+    }
+    empty_state_lines(
+        format!("{scope} has no documents"),
+        None,
+        &[("r", "refresh"), ("Tab", "pick another scope")],
+    )
+}
+
+pub(crate) fn freshness_title<'a>(
+    app: &App,
+    title: impl Into<Cow<'a, str>>,
+    kind: DataKind,
+) -> Line<'a> {
+    let mut spans = vec![Span::raw(title)];
+    if let Some(freshness) = app.freshness.get(&kind)
+        && let Some(fetched_at) = freshness.fetched_at
+    {
+        let (text, style) = freshness_badge(fetched_at.elapsed(), freshness.failures);
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(text, style));
+    }
+    Line::from(spans)
+}
+
+pub(crate) fn freshness_badge(age: Duration, failures: u32) -> (String, Style) {
+    let secs = age.as_secs();
+    let mut text = if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / 3600)
+    };
+    if failures > 0 {
+        text.push_str(&format!(", {failures} failed"));
+    }
+    let color = if secs >= 120 || failures >= 3 {
+        Color::Red
+    } else if secs >= 30 || failures > 0 {
+        Color::Yellow
+    } else {
+        Color::DarkGray
+    };
+    (text, Style::default().fg(color))
+}
+
+pub(crate) fn scope_title(scope: ScopeKind) -> &'static str {
+    match scope {
+        ScopeKind::Indices => "Indices",
+        ScopeKind::Aliases => "Aliases",
+        ScopeKind::DataStreams => "DataStreams",
+    }
+}
+
+pub(crate) fn query_line<'a>(app: &'a App) -> Line<'a> {
+    let label_style = Style::default().fg(Color::Gray);
+    let value = match app.input_mode {
+        InputMode::Query => app.query_edit.as_str(),
+        _ => app.query.as_str(),
+    };
+    let error = match app.query_mode {
+        QueryMode::Dsl => parse_dsl_query(value).err(),
+        QueryMode::QueryString => None,
+    };
+    let value = if value.is_empty() { "-" } else { value };
+    let suffix = if app.input_mode == InputMode::Query {
+        "*"
+    } else {
+        ""
+    };
+    let label = match app.query_mode {
+        QueryMode::QueryString => "Query",
+        QueryMode::Dsl => "DSL",
+    };
+    let mut spans = vec![
+        Span::styled(format!("{label}{suffix}"), label_style),
+        Span::raw(": "),
+    ];
+    if let Some(err) = error {
+        spans.push(Span::styled(
+            format!("[{err:#}] "),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans.push(Span::raw(value));
+    Line::from(spans)
+}
+
+pub(crate) fn filter_chips_line<'a>(app: &'a App) -> Line<'a> {
+    let label_style = Style::default().fg(Color::Gray);
+    let mut spans = vec![Span::styled("Filters", label_style), Span::raw(": ")];
+    if app.query.trim().is_empty() {
+        spans.push(Span::raw("(none)"));
+    } else {
+        let chip = truncate_string(app.query.trim(), 40);
+        spans.push(Span::styled(
+            format!(" {} ", chip),
+            Style::default().bg(Color::DarkGray).fg(Color::Black),
+        ));
+    }
+    for filter in &app.filters {
+        let (sign, color) = if filter.exclude {
+            ("-", Color::Red)
+        } else {
+            ("+", Color::Green)
+        };
+        let chip = format!(
+            "{sign}{}:{}",
+            filter.field,
+            json_value_compact(&filter.value)
+        );
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" {} ", truncate_string(&chip, 40)),
+            Style::default().bg(color).fg(Color::Black),
+        ));
+    }
+    if let Some(sort) = &app.sort {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" sort: {} {} ", sort.field, sort.order.as_str()),
+            Style::default().bg(Color::Cyan).fg(Color::Black),
+        ));
+    }
+    if app.fuzzy {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            " fuzzy ",
+            Style::default().bg(Color::Magenta).fg(Color::Black),
+        ));
+    }
+    if app.request_template().is_some() {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            " template ",
+            Style::default().bg(Color::Blue).fg(Color::Black),
+        ));
+    }
+    let limits = [
+        app.search_timeout
+            .as_ref()
+            .map(|timeout| format!("timeout={timeout}")),
+        app.terminate_after
+            .map(|limit| format!("terminate_after={limit}")),
+    ];
+    for limit in limits.into_iter().flatten() {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" {limit} "),
+            Style::default().bg(Color::Blue).fg(Color::Black),
+        ));
+    }
+    for option in app.query_options().summary() {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" {option} "),
+            Style::default().bg(Color::Blue).fg(Color::Black),
+        ));
+    }
+    if let Some(warning) = tsdb_range_warning(app) {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" ! {warning} "),
+            Style::default().bg(Color::Red).fg(Color::Black),
+        ));
+    }
+    for warning in match app.query_mode {
+        QueryMode::QueryString => query_cost_warnings(&app.query, app.docs_from),
+        QueryMode::Dsl => Vec::new(),
+    } {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" ! {warning} "),
+            Style::default().bg(Color::Yellow).fg(Color::Black),
+        ));
+    }
+    Line::from(spans)
+}
+
+pub(crate) fn results_summary_line<'a>(app: &'a App) -> Line<'a> {
+    let label_style = Style::default().fg(Color::Gray);
+    let hits = app
+        .docs_total
+        .map(|value| format_count(value, app.config.display.raw_numbers))
+        .unwrap_or_else(|| "-".to_string());
+    let took = app
+        .search_took_ms
+        .map(|value| format!("{value}ms"))
+        .unwrap_or_else(|| "-".to_string());
+    let failed = app.search_shards_failed.unwrap_or(0);
+    let timed_out = app.search_timed_out.unwrap_or(false);
+    let mut parts = vec![format!("hits {hits}"), format!("took {took}")];
+    if failed > 0 {
+        parts.push(format!("shard_fail {failed}"));
+    }
+    if timed_out {
+        parts.push("timeout".to_string());
+    }
+    if app.search_terminated_early == Some(true) {
+        parts.push("terminated early".to_string());
+    }
+    if app.partial_results() {
+        parts.push("partial".to_string());
+    }
+    let status = parts.join(" | ");
+    let mut spans = vec![Span::styled("Results", label_style), Span::raw(": ")];
+    let status_style = if failed > 0 || timed_out {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else if app.partial_results() {
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    spans.push(Span::styled(status, status_style));
+    Line::from(spans)
+}
+
+pub(crate) fn results_title(from: u64, loaded: u64, total: Option<u64>, raw: bool) -> String {
+    if total == Some(0) {
+        return "Results (0)".to_string();
+    }
+    if loaded == 0 {
+        return format!("Results (from {})", format_count(from, raw));
+    }
+    let first = format_count(from + 1, raw);
+    let last = format_count(from + loaded, raw);
+    match total {
+        Some(total) => format!("Results ({first}-{last} of {})", format_count(total, raw)),
+        None => format!("Results ({first}-{last})"),
+    }
+}
+
+pub(crate) fn result_id_width(total_width: u16) -> u16 {
+    let min = 12;
+    let max = 28;
+    let target = total_width / 3;
+    target.clamp(min, max)
+}
+
+const DRAWER_BLOB_HINTS: usize = 3;
+const DRAWER_MATCH_CONTEXT: usize = 2;
+
+pub(crate) fn drawer_body_lines(app: &App, source: &Value) -> Vec<String> {
+    match app.doc_view_mode {
+        DocViewMode::Pretty => json_lines_expanded(source),
+        DocViewMode::Raw => json_lines_raw(source),
+        DocViewMode::Flatten => json_lines_flatten(source),
+        DocViewMode::Ecs => ecs_section_lines(source),
+    }
+}
+
+pub(crate) fn drawer_match_lines(lines: &[String], search: &str) -> Vec<usize> {
+    if search.is_empty() {
+        return Vec::new();
+    }
+    let needle = search.to_ascii_lowercase();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.to_ascii_lowercase().contains(&needle))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+pub(crate) fn doc_drawer_lines(app: &App, max_lines: usize) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let Some(doc) = app.selected_doc() else {
+        return empty_state_lines(
+            "No document selected",
+            None,
+            &[("Up/Down", "pick a row"), ("Tab", "focus results")],
+        );
+    };
+    let source = app.display_source(doc);
+
+    lines.push(Line::from(vec![
+        Span::styled("ID: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(&doc.id),
+    ]));
+    if !doc.index.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("Index: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(&doc.index),
+        ]));
+    }
+    if let Some(version) = doc.version {
+        let seq_no = doc
+            .seq_no
+            .map(|seq_no| format!("  seq_no {seq_no}"))
+            .unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::styled("Version: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!("{version}{seq_no}")),
+        ]));
+    }
+    if let Some((id, change)) = &app.doc_change
+        && id == &doc.id
+    {
+        let (text, color) = match change {
+            DocChange::Unchanged => ("unchanged since loaded".to_string(), Color::Green),
+            DocChange::Modified { version } => (
+                format!(
+                    "modified since loaded (was version {})",
+                    version.map_or("-".to_string(), |value| value.to_string())
+                ),
+                Color::Yellow,
+            ),
+            DocChange::Deleted => ("deleted since loaded".to_string(), Color::Red),
+        };
+        lines.push(Line::from(Span::styled(text, Style::default().fg(color))));
+    }
+    lines.push(doc_view_line(
+        app.doc_view_mode,
+        app.plain(),
+        is_ecs_doc(&doc.source),
+    ));
+    lines.push(Line::from(vec![
+        Span::styled("Actions", Style::default().fg(Color::Gray)),
+        Span::raw(if app.doc_view_mode == DocViewMode::Flatten {
+            ": [ ] field  i include  e exclude  / search"
+        } else {
+            ": v Flatten to include/exclude fields  / search"
+        }),
+    ]));
+    let blobs = collect_field_blobs(&source);
+    for (path, blob) in blobs.iter().take(DRAWER_BLOB_HINTS) {
+        lines.push(Line::from(vec![
+            Span::styled("Blob ", Style::default().fg(Color::Gray)),
+            Span::styled(path.clone(), Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" {}", blob.hint(app.config.display.raw_numbers))),
+        ]));
+    }
+    if !blobs.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "w open links or save bytes",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    if let Some(view) = &app.json_path {
+        lines.push(Line::from(vec![
+            Span::styled("Path ", Style::default().fg(Color::Gray)),
+            Span::styled(view.expr.as_str(), Style::default().fg(Color::Cyan)),
+        ]));
+        match parse_json_path(&view.expr) {
+            Ok(steps) => {
+                let values = eval_json_path(&source, &steps);
+                if values.is_empty() {
+                    lines.push(Line::from("  (no match)"));
+                }
+                for value in values {
+                    lines.push(Line::from(format!("  {}", json_value_compact(value))));
+                }
+            }
+            Err(err) => lines.push(Line::from(format!("  {err:#}"))),
+        }
+    }
+    lines.push(Line::from(""));
+    if max_lines > 0 && lines.len() >= max_lines {
+        lines.truncate(max_lines);
+        return lines;
+    }
+
+    let token = highlight_token(&app.query);
+    let rules = app.config.highlight_rules();
+    let body_lines = drawer_body_lines(app, &source);
+    let search = app.drawer_search.as_str();
+    let matches = drawer_match_lines(&body_lines, search);
+    let current = (!matches.is_empty()).then(|| matches[app.drawer_match % matches.len()]);
+    if app.input_mode == InputMode::DrawerSearch || !search.is_empty() {
+        let cursor = if app.input_mode == InputMode::DrawerSearch {
+            "_"
+        } else {
+            ""
+        };
+        let count = match current {
+            Some(_) => format!("{}/{}", app.drawer_match % matches.len() + 1, matches.len()),
+            None => "no matches".to_string(),
+        };
+        lines.insert(
+            lines.len() - 1,
+            Line::from(vec![
+                Span::styled("Find: ", Style::default().fg(Color::Gray)),
+                Span::raw(format!("{search}{cursor} ")),
+                Span::styled(format!("({count})"), Style::default().fg(Color::Cyan)),
+            ]),
+        );
+    }
+    let field_cursor = (app.doc_view_mode == DocViewMode::Flatten && !body_lines.is_empty())
+        .then(|| app.drawer_field.min(body_lines.len() - 1));
+    let skip = current
+        .or(field_cursor)
+        .map_or(0, |line| line.saturating_sub(DRAWER_MATCH_CONTEXT));
+    let mut truncated = false;
+    for (index, line) in body_lines.into_iter().enumerate().skip(skip) {
+        if lines.len() >= max_lines {
+            truncated = true;
+            break;
+        }
+        if matches.binary_search(&index).is_ok() {
+            let mut found = highlight_line_with(&line, search, true);
+            if current == Some(index) {
+                found = found.style(Style::default().bg(Color::DarkGray));
+            }
+            lines.push(found);
+        } else if field_cursor == Some(index) {
+            lines.push(Line::from(Span::styled(
+                line,
+                Style::default().add_modifier(Modifier::REVERSED),
+            )));
+        } else if let Some(style) =
+            stack_frame_style(&line).or_else(|| drawer_line_highlight(&rules, &line))
+        {
+            lines.push(Line::from(Span::styled(line, style)));
+        } else if let Some(ref token) = token {
+            lines.push(highlight_line(&line, token));
+        } else {
+            lines.push(Line::from(line));
+        }
+    }
+
+    if truncated && max_lines > 0 {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            *last = Line::from("...");
+        }
+    }
+    lines
+}
+
+pub(crate) fn doc_view_line(mode: DocViewMode, plain: bool, ecs: bool) -> Line<'static> {
+    let active = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let inactive = Style::default().fg(Color::Gray);
+    let pretty = if mode == DocViewMode::Pretty {
+        active
+    } else {
+        inactive
+    };
+    let raw = if mode == DocViewMode::Raw {
+        active
+    } else {
+        inactive
+    };
+    let flat = if mode == DocViewMode::Flatten {
+        active
+    } else {
+        inactive
+    };
+    let label = |name: &'static str, current: DocViewMode| {
+        if plain && mode == current {
+            format!("[{name}]")
+        } else {
+            name.to_string()
+        }
+    };
+    let mut spans = vec![
+        Span::styled("View: ", Style::default().fg(Color::Gray)),
+        Span::styled(label("Pretty", DocViewMode::Pretty), pretty),
+        Span::raw(" | "),
+        Span::styled(label("Raw", DocViewMode::Raw), raw),
+        Span::raw(" | "),
+        Span::styled(label("Flatten", DocViewMode::Flatten), flat),
+    ];
+    if ecs {
+        let style = if mode == DocViewMode::Ecs {
+            active
+        } else {
+            inactive
+        };
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(label("ECS", DocViewMode::Ecs), style));
+    }
+    Line::from(spans)
+}
+
+pub(crate) fn json_lines_pretty(value: &Value) -> Vec<String> {
+    serde_json::to_string_pretty(value)
+        .unwrap_or_else(|_| "<invalid json>".to_string())
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+const MULTILINE_MARKER: &str = "@@multiline:";
+
+pub(crate) fn json_lines_expanded(value: &Value) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let marked = mark_multiline_strings(value, &mut blocks);
+    if blocks.is_empty() {
+        return json_lines_pretty(value);
+    }
+    let mut out = Vec::new();
+    for line in json_lines_pretty(&marked) {
+        let block = line
+            .find(&format!("\"{MULTILINE_MARKER}"))
+            .and_then(|start| {
+                let rest = &line[start + MULTILINE_MARKER.len() + 1..];
+                let index: usize = rest[..rest.find('"')?].parse().ok()?;
+                Some((start, blocks.get(index)?))
+            });
+        let Some((start, block)) = block else {
+            out.push(line);
+            continue;
+        };
+        let prefix = &line[..start];
+        let indent = prefix.len() - prefix.trim_start().len() + 2;
+        out.push(prefix.trim_end().to_string());
+        for text in block.lines() {
+            let text = text.trim_end_matches('\r').replace('\t', "    ");
+            out.push(format!("{:indent$}{text}", ""));
+        }
+    }
+    out
+}
+
+fn mark_multiline_strings(value: &Value, blocks: &mut Vec<String>) -> Value {
+    match value {
+        Value::String(text) if text.trim_end().contains('\n') => {
+            blocks.push(text.trim_end().to_string());
+            Value::String(format!("{MULTILINE_MARKER}{}", blocks.len() - 1))
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), mark_multiline_strings(value, blocks)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| mark_multiline_strings(value, blocks))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+pub(crate) fn stack_frame_style(line: &str) -> Option<Style> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("Caused by:") || trimmed.starts_with("Traceback ") {
+        return Some(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+    }
+    let frame = trimmed.starts_with("at ") || trimmed.starts_with("File \"");
+    if !frame {
+        return None;
+    }
+    let library = [
+        "java.",
+        "javax.",
+        "sun.",
+        "jdk.",
+        "node:internal",
+        "node_modules",
+        "site-packages",
+    ]
+    .iter()
+    .any(|marker| trimmed.contains(marker));
+    Some(if library {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::Cyan)
+    })
+}
+
+pub(crate) fn json_lines_raw(value: &Value) -> Vec<String> {
+    vec![serde_json::to_string(value).unwrap_or_else(|_| "<invalid json>".to_string())]
+}
+
+pub(crate) fn json_lines_flatten(value: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    flatten_json_value(value, None, &mut out);
+    if out.is_empty() {
+        out.push("<empty>".to_string());
+    }
+    out
+}
+
+pub(crate) fn flatten_json_value(value: &Value, prefix: Option<&str>, out: &mut Vec<String>) {
+    let mut leaves = Vec::new();
+    flatten_json_leaves(value, prefix, &mut leaves);
+    out.extend(
+        leaves
+            .into_iter()
+            .map(|(path, value)| format!("{path} = {}", json_value_inline(value))),
+    );
+}
+
+pub(crate) fn flatten_json_leaves<'a>(
+    value: &'a Value,
+    prefix: Option<&str>,
+    out: &mut Vec<(String, &'a Value)>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_none() => {
+            for (key, value) in map {
+                let next = match prefix {
+                    Some(prefix) => format!("{prefix}.{key}"),
+                    None => key.to_string(),
+                };
+                flatten_json_leaves(value, Some(&next), out);
+            }
+        }
+        Value::Array(values) if !values.is_empty() || prefix.is_none() => {
+            for (idx, value) in values.iter().enumerate() {
+                let next = format!("{}[{idx}]", prefix.unwrap_or_default());
+                flatten_json_leaves(value, Some(&next), out);
+            }
+        }
+        _ => out.push((prefix.unwrap_or("<root>").to_string(), value)),
+    }
+}
+
+pub(crate) fn highlight_token(query: &str) -> Option<String> {
+    let token = query
+        .split_whitespace()
+        .find(|part| !part.is_empty())
+        .map(|part| part.trim_matches('"').trim_matches('\'').to_string());
+    token.filter(|value| !value.is_empty())
+}
+
+pub(crate) fn highlight_line(line: &str, token: &str) -> Line<'static> {
+    highlight_line_with(line, token, false)
+}
+
+pub(crate) fn highlight_line_with(line: &str, token: &str, ignore_case: bool) -> Line<'static> {
+    let (haystack, token) = if ignore_case {
+        (line.to_ascii_lowercase(), token.to_ascii_lowercase())
+    } else {
+        (line.to_string(), token.to_string())
+    };
+    let token = token.as_str();
+    if token.is_empty() || !haystack.contains(token) {
+        return Line::from(line.to_string());
+    }
+    let mut spans = Vec::new();
+    let mut rest = line;
+    let mut rest_haystack = haystack.as_str();
+    while let Some(pos) = rest_haystack.find(token) {
+        let (before, after) = rest.split_at(pos);
+        let (matched, after) = after.split_at(token.len());
+        if !before.is_empty() {
+            spans.push(Span::raw(before.to_string()));
+        }
+        spans.push(Span::styled(
+            matched.to_string(),
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+        rest = after;
+        rest_haystack = &rest_haystack[pos + token.len()..];
+    }
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest.to_string()));
+    }
+    Line::from(spans)
+}
+
+pub(crate) fn build_scope_items(app: &App) -> (Vec<ListItem<'_>>, ListState) {
+    let filtered = app.filtered_scope_indices();
+    let mut state = ListState::default();
+    let selected = app.scope_selected();
+    let selected_pos = selected.and_then(|idx| filtered.iter().position(|value| *value == idx));
+    state.select(selected_pos);
+
+    let items: Vec<ListItem> = if filtered.is_empty() {
+        scope_empty_state(app)
+            .into_iter()
+            .map(ListItem::new)
+            .collect()
+    } else {
+        filtered
+            .iter()
+            .map(|idx| match app.scope_kind {
+                ScopeKind::Indices => {
+                    let entry = &app.indices[*idx];
+                    scope_line_index(
+                        entry,
+                        app.tsdb.get(&entry.name),
+                        app.index_tags.get(&entry.name),
+                        app.config.display.raw_numbers,
+                    )
+                }
+                ScopeKind::Aliases => {
+                    let entry = &app.aliases[*idx];
+                    scope_line_alias(entry, app.expanded_aliases.contains(&entry.alias))
+                }
+                ScopeKind::DataStreams => scope_line_datastream(&app.datastreams[*idx]),
+            })
+            .collect()
+    };
+    (items, state)
+}
+
+pub(crate) fn scope_line_index<'a>(
+    entry: &'a IndexEntry,
+    tsdb: Option<&TsdbInfo>,
+    tags: Option<&IndexTags>,
+    raw: bool,
+) -> ListItem<'a> {
+    let status = match entry.health.as_str() {
+        "green" => Span::styled("green", Style::default().fg(Color::Green)),
+        "yellow" => Span::styled("yellow", Style::default().fg(Color::Yellow)),
+        "red" => Span::styled("red", Style::default().fg(Color::Red)),
+        _ => Span::styled(entry.health.as_str(), Style::default().fg(Color::Gray)),
+    };
+    let mut spans = vec![
+        Span::styled(&entry.name, Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(" "),
+        status,
+        Span::raw(format!(
+            " docs={} size={}",
+            format_count_str(entry.docs_count.as_deref(), raw),
+            format_bytes_str(entry.store_size.as_deref(), raw)
+        )),
+    ];
+    if let Some(info) = tsdb {
+        let label = match info.downsample_status.as_deref() {
+            Some(status) => format!(" tsdb ds:{status}"),
+            None => " tsdb".to_string(),
+        };
+        spans.push(Span::styled(label, Style::default().fg(Color::Cyan)));
+    }
+    for (key, value) in tags.into_iter().flatten() {
+        spans.push(Span::styled(
+            format!(" {key}:{}", truncate_string(value, 24)),
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+pub(crate) fn scope_line_alias(entry: &AliasEntry, expanded: bool) -> ListItem<'_> {
+    let mut header = vec![
+        Span::raw(if expanded { "- " } else { "+ " }),
+        Span::styled(&entry.alias, Style::default().add_modifier(Modifier::BOLD)),
+    ];
+    match entry.targets.as_slice() {
+        [target] if !expanded => {
+            header.push(Span::raw(" -> "));
+            header.push(Span::raw(&target.index_name));
+            if target.is_write_index {
+                header.push(Span::styled(" (w)", Style::default().fg(Color::Yellow)));
+            }
+        }
+        targets if !expanded && targets.iter().any(|target| target.filtered) => {
+            header.push(Span::styled(
+                format!(" ({} targets, filtered)", targets.len()),
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+        targets => header.push(Span::styled(
+            format!(" ({} targets)", targets.len()),
+            Style::default().fg(Color::Gray),
+        )),
+    }
+    if let [target] = entry.targets.as_slice()
+        && target.filtered
+        && !expanded
+    {
+        header.push(Span::styled(
+            " [filtered]",
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    let mut lines = vec![Line::from(header)];
+    if expanded {
+        for target in &entry.targets {
+            let mut spans = vec![Span::raw("    "), Span::raw(&target.index_name)];
+            if target.is_write_index {
+                spans.push(Span::styled(" write", Style::default().fg(Color::Yellow)));
+            }
+            if target.filtered {
+                spans.push(Span::styled(
+                    " filtered",
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+    }
+    ListItem::new(lines)
+}
+
+pub(crate) fn scope_line_datastream(entry: &DataStreamEntry) -> ListItem<'_> {
+    let status = entry.status.as_deref().unwrap_or("-");
+    let status_span = match status.to_lowercase().as_str() {
+        "green" => Span::styled(status, Style::default().fg(Color::Green)),
+        "yellow" => Span::styled(status, Style::default().fg(Color::Yellow)),
+        "red" => Span::styled(status, Style::default().fg(Color::Red)),
+        _ => Span::styled(status, Style::default().fg(Color::Gray)),
+    };
+    let backing = entry
+        .indices
+        .as_ref()
+        .map(|values| values.len())
+        .unwrap_or(0);
+    let generation = entry
+        .generation
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    ListItem::new(Line::from(vec![
+        Span::styled(&entry.name, Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(" "),
+        status_span,
+        Span::raw(format!(" gen={generation} backing={backing}")),
+    ]))
+}
+
+pub(crate) fn auth_label(cluster: &ClusterConfig, es_url: &str) -> &'static str {
+    match cluster.with_env_credentials().auth {
+        AuthMethod::ApiKey => "api_key",
+        AuthMethod::Basic => "basic",
+        AuthMethod::None if es_url.contains('@') => "basic",
+        AuthMethod::None => "none",
+    }
+}
+
+pub(crate) fn scope_label(app: &App) -> String {
+    let kind = match app.scope_kind {
+        _ if app.scope_pattern.is_some() => "pattern",
+        ScopeKind::Indices => "index",
+        ScopeKind::Aliases => "alias",
+        ScopeKind::DataStreams => "datastream",
+    };
+    let name = app.selected_scope_name().unwrap_or("-");
+    format!("{kind}/{name}")
+}
+
+pub(crate) fn status_summary(app: &App) -> (String, Style) {
+    let hits = app
+        .docs_total
+        .map(|value| format_count(value, app.config.display.raw_numbers))
+        .unwrap_or_else(|| "-".to_string());
+    let took = app
+        .search_took_ms
+        .map(|value| format!("{value}ms"))
+        .unwrap_or_else(|| "-".to_string());
+    let failed = app.search_shards_failed.unwrap_or(0);
+    let timed_out = app.search_timed_out.unwrap_or(false);
+    let mut parts = vec![format!("hits {hits}"), format!("took {took}")];
+    if failed > 0 {
+        parts.push(format!("shard_fail {failed}"));
+    }
+    if timed_out {
+        parts.push("timeout".to_string());
+    }
+    if app.search_terminated_early == Some(true) {
+        parts.push("partial".to_string());
+    }
+    if app.unmasked {
+        parts.push("UNMASKED".to_string());
+    }
+    if let Some(pit) = &app.pit {
+        let remaining = pit.remaining().as_secs();
+        parts.push(format!("pit {}m{:02}s", remaining / 60, remaining % 60));
+    }
+    if let Some(preference) = &app.preference {
+        parts.push(format!("pref {preference}"));
+    }
+    if let Some(routing) = &app.routing {
+        parts.push(format!("routing {routing}"));
+    }
+    if app.sample_seed.is_some() {
+        parts.push("sample".to_string());
+    }
+    if app.last_error.is_some() {
+        parts.push("error".to_string());
+    }
+    let text = format!("status: {}", parts.join(" | "));
+    let style = if failed > 0 || timed_out || app.last_error.is_some() {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    (text, style)
+}
+
+pub(crate) fn focus_label(app: &App) -> &'static str {
+    match (app.input_mode, app.focus, app.show_doc_drawer) {
+        (InputMode::Query, _, _) => "query input",
+        (InputMode::ScopeFilter, _, _) => "scope filter input",
+        (InputMode::Command, _, _) => "command input",
+        (InputMode::TermsPrefix, _, _) => "terms prefix input",
+        (InputMode::ResultsFilter, _, _) => "results filter input",
+        (InputMode::Jump, _, _) => "jump input",
+        (InputMode::DrawerSearch, _, _) => "document search input",
+        (InputMode::ExportPath, _, _) => "export path input",
+        (InputMode::ConfirmIndexAction, _, _) => "index action confirmation",
+        (_, Focus::Results, true) => "document",
+        (_, Focus::Results, false) => "results",
+        (_, Focus::LeftNav, _) => "scope list",
+    }
+}
+
+pub(crate) fn connection_summary(app: &App) -> (String, Style) {
+    match app.connection {
+        ConnectionState::Unknown => ("-".to_string(), Style::default().fg(Color::Gray)),
+        ConnectionState::Connected if app.throttled => (
+            "up, throttled".to_string(),
+            Style::default().fg(Color::Yellow),
+        ),
+        ConnectionState::Connected => ("up".to_string(), Style::default().fg(Color::Green)),
+        ConnectionState::Disconnected { since, .. } => {
+            let retry_in = app
+                .last_fetch
+                .map(|at| {
+                    app.refresh_interval()
+                        .saturating_sub(at.elapsed())
+                        .as_secs()
+                })
+                .unwrap_or(0);
+            let down_for = since.elapsed().as_secs();
+            (
+                format!("DOWN {down_for}s (retry in {retry_in}s)"),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )
+        }
+    }
+}
+
+pub(crate) fn list_focus_style(active: bool) -> Style {
+    if active {
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::Cyan)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().add_modifier(Modifier::BOLD)
+    }
+}
+
+pub(crate) fn log_view_row<'a>(
+    app: &App,
+    doc: &DocEntry,
+    filter: &str,
+    ignore_case: bool,
+) -> Row<'a> {
+    let source = app.display_source(doc);
+    let time = ecs_timestamp_millis(&source)
+        .map(|millis| {
+            let text = app.time_zone.format(millis).replacen('T', " ", 1);
+            text.chars().take(19).collect()
+        })
+        .unwrap_or_else(|| "-".to_string());
+    let level = ecs_text(&source, "log.level").unwrap_or_default();
+    let service = ecs_text(&source, "service.name").unwrap_or_default();
+    let message = ecs_text(&source, "message").unwrap_or_default();
+    let message = truncate_string(message.lines().next().unwrap_or_default(), 512);
+    Row::new(vec![
+        Cell::from(time),
+        Cell::from(Span::styled(level.clone(), log_level_style(&level))),
+        Cell::from(highlight_line_with(
+            &truncate_string(&service, 14),
+            filter,
+            ignore_case,
+        )),
+        Cell::from(highlight_line_with(&message, filter, ignore_case)),
+    ])
+}
+
+pub(crate) fn highlight_color(name: &str) -> Option<Color> {
+    match name.to_ascii_lowercase().as_str() {
+        "red" => Some(Color::Red),
+        "yellow" => Some(Color::Yellow),
+        "green" => Some(Color::Green),
+        "cyan" => Some(Color::Cyan),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "gray" | "grey" => Some(Color::Gray),
+        _ => None,
+    }
+}
+
+pub(crate) fn value_highlight_style(
+    rules: &[HighlightRule],
+    field: &str,
+    value: &Value,
+) -> Option<Style> {
+    rules
+        .iter()
+        .filter(|rule| rule.field == field || rule.field.rsplit('.').next() == Some(field))
+        .find(|rule| rule.matches(value))
+        .and_then(|rule| highlight_color(&rule.color))
+        .map(|color| Style::default().fg(color).add_modifier(Modifier::BOLD))
+}
+
+pub(crate) fn drawer_line_highlight(rules: &[HighlightRule], line: &str) -> Option<Style> {
+    let trimmed = line.trim_start();
+    let (field, value) = match trimmed.strip_prefix('"') {
+        Some(rest) => {
+            let (key, value) = rest.split_once("\": ")?;
+            (key, value.trim_end_matches(','))
+        }
+        None => trimmed.split_once(" = ")?,
+    };
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    value_highlight_style(rules, field, &value)
+}
+
+pub(crate) fn log_level_style(level: &str) -> Style {
+    match level.to_ascii_lowercase().as_str() {
+        "fatal" | "critical" | "emergency" | "alert" | "error" | "err" => {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        }
+        "warn" | "warning" => Style::default().fg(Color::Yellow),
+        "info" | "notice" => Style::default().fg(Color::Green),
+        "debug" | "trace" => Style::default().fg(Color::Gray),
+        _ => Style::default(),
+    }
+}
+
+pub(crate) fn doc_summary(app: &App, doc: &DocEntry, max_len: usize) -> String {
+    let source =
+        serde_json::to_string(&app.display_source(doc)).unwrap_or_else(|_| "<invalid>".into());
+    truncate_string(&source, max_len)
+}
+
+pub(crate) fn drawer_width(total_width: u16) -> u16 {
+    let min = 30;
+    let max = total_width.saturating_sub(2).max(min);
+    let target = total_width.saturating_mul(55) / 100;
+    target.clamp(min, max)
+}
+
+pub(crate) fn status_style(status: &str) -> Style {
+    match status {
+        "green" => Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+        "yellow" => Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+        "red" => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        _ => Style::default().fg(Color::Gray),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use proptest::prelude::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    fn render(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| ui(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer.get(x, y).symbol())
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/snapshots")
+            .join(format!("{name}.txt"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            expected == actual,
+            "snapshot {name} differs (rerun with UPDATE_SNAPSHOTS=1 to accept)\n--- expected\n{expected}\n--- actual\n{actual}"
+        );
+    }
+
+    fn results_app() -> App {
+        let mut app = test_app();
+        app.connection = ConnectionState::Connected;
+        app.health = Some(ClusterHealth {
+            cluster_name: "docker-cluster".to_string(),
+            status: "green".to_string(),
+            number_of_nodes: 1,
+        });
+        app.indices = ["logs-2024.01", "metrics-2024.01"]
+            .iter()
+            .map(|name| IndexEntry {
+                docs_count: Some("1234567".to_string()),
+                store_size: Some("52428800".to_string()),
+                ..test_index(name)
+            })
+            .collect();
+        app.indices_state.select(Some(0));
+        app.documents = vec![
+            DocEntry {
+                version: Some(1),
+                seq_no: Some(4),
+                ..test_doc(
+                    "logs-2024.01",
+                    "a1",
+                    serde_json::json!({ "message": "GET /health 200", "status": 200 }),
+                )
+            },
+            DocEntry {
+                version: Some(2),
+                seq_no: Some(9),
+                ..test_doc(
+                    "logs-2024.01",
+                    "b2",
+                    serde_json::json!({ "message": "POST /login 401", "status": 401 }),
+                )
+            },
+        ];
+        app.docs_total = Some(2);
+        app.search_took_ms = Some(3);
+        app.docs_state.select(Some(0));
+        app
+    }
+
+    #[test]
+    fn numeric_stats_skip_non_numbers_and_missing_fields() {
+        let sources = [
+            serde_json::json!({ "http": { "bytes": 10 } }),
+            serde_json::json!({ "http.bytes": 2.5 }),
+            serde_json::json!({ "http": { "bytes": "12" } }),
+            serde_json::json!({ "message": "no bytes" }),
+        ];
+        let stats = numeric_column_stats(&sources, "http.bytes").unwrap();
+        assert_eq!(stats.summary(), "2.50..10 avg 6.25");
+        assert!(numeric_column_stats(&sources, "message").is_none());
+        assert!(numeric_column_stats(&[], "http.bytes").is_none());
+    }
+
+    #[test]
+    fn snapshot_empty_state() {
+        let mut app = test_app();
+        assert_snapshot("empty_state", &render(&mut app, 100, 24));
+    }
+
+    #[test]
+    fn snapshot_results() {
+        let mut app = results_app();
+        assert_snapshot("results", &render(&mut app, 100, 24));
+    }
+
+    #[test]
+    fn multiline_strings_expand_into_stack_frames() {
+        let value = serde_json::json!({
+            "error": {
+                "stack": "java.lang.IllegalStateException: boom\n\tat com.shop.Cart.add(Cart.java:42)\n\tat java.base/java.lang.Thread.run(Thread.java:833)\n"
+            },
+            "message": "checkout failed"
+        });
+        let lines = json_lines_expanded(&value);
+        assert_eq!(
+            lines,
+            [
+                "{",
+                "  \"error\": {",
+                "    \"stack\":",
+                "      java.lang.IllegalStateException: boom",
+                "          at com.shop.Cart.add(Cart.java:42)",
+                "          at java.base/java.lang.Thread.run(Thread.java:833)",
+                "  },",
+                "  \"message\": \"checkout failed\"",
+                "}",
+            ]
+        );
+        assert_eq!(
+            stack_frame_style(&lines[4]),
+            Some(Style::default().fg(Color::Cyan))
+        );
+        assert_eq!(
+            stack_frame_style(&lines[5]),
+            Some(Style::default().fg(Color::DarkGray))
+        );
+        assert_eq!(stack_frame_style(&lines[7]), None);
+    }
+
+    #[test]
+    fn drawer_search_matches_lines_ignoring_case() {
+        let lines = json_lines_expanded(&serde_json::json!({
+            "message": "Checkout failed",
+            "service": "checkout",
+            "level": "error"
+        }));
+        assert_eq!(drawer_match_lines(&lines, "CHECKOUT"), [2, 3]);
+        assert!(drawer_match_lines(&lines, "").is_empty());
+    }
+
+    #[test]
+    fn highlight_rules_color_levels_and_status_codes() {
+        let rules = default_highlight_rules();
+        let red = Some(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+        let yellow = Some(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+        assert_eq!(
+            value_highlight_style(&rules, "log.level", &serde_json::json!("ERROR")),
+            red
+        );
+        assert_eq!(
+            value_highlight_style(&rules, "status", &serde_json::json!(404)),
+            yellow
+        );
+        assert_eq!(
+            drawer_line_highlight(&rules, "    \"status_code\": 503,"),
+            red
+        );
+        assert_eq!(drawer_line_highlight(&rules, "level = \"warn\""), yellow);
+        assert_eq!(drawer_line_highlight(&rules, "  \"status\": 200"), None);
+    }
+
+    #[test]
+    fn selected_columns_replace_the_preview() {
+        let mut app = results_app();
+        let key = app.note_key("logs-2024.01");
+        app.state
+            .columns
+            .insert(key, vec!["status".to_string(), "message".to_string()]);
+        let screen = render(&mut app, 100, 24);
+        assert!(!screen.contains("preview"));
+        assert!(screen.contains("status"));
+        assert!(screen.contains("401"));
+        assert!(!screen.contains("{\"message\""));
+        assert!(screen.contains("200..401 avg 300.50"));
+    }
+
+    #[test]
+    fn columns_of_another_index_keep_the_preview() {
+        let mut app = results_app();
+        let key = app.note_key("metrics-2024.01");
+        app.state.columns.insert(key, vec!["cpu".to_string()]);
+        assert!(app.result_columns().is_empty());
+        assert!(render(&mut app, 100, 24).contains("preview"));
+        app.indices_state.select(Some(1));
+        assert_eq!(app.result_columns(), ["cpu"]);
+    }
+
+    #[test]
+    fn snapshot_log_view() {
+        let mut app = results_app();
+        for (doc, level) in app.documents.iter_mut().zip(["info", "error"]) {
+            doc.source = serde_json::json!({
+                "@timestamp": "2024-01-15T08:30:00Z",
+                "log": { "level": level },
+                "service": { "name": "gateway" },
+                "message": doc.source["message"].clone(),
+            });
+        }
+        assert_snapshot("log_view", &render(&mut app, 100, 24));
+    }
+
+    #[test]
+    fn snapshot_doc_drawer() {
+        let mut app = results_app();
+        app.focus = Focus::Results;
+        app.show_doc_drawer = true;
+        assert_snapshot("doc_drawer", &render(&mut app, 100, 24));
+    }
+
+    #[test]
+    fn snapshot_errors() {
+        let mut app = results_app();
+        app.last_error = Some("search: http error: 400 Bad Request".to_string());
+        app.search_shards_failed = Some(1);
+        assert_snapshot("errors", &render(&mut app, 160, 24));
+    }
+
+    #[test]
+    fn snapshot_disconnected() {
+        let mut app = test_app();
+        app.connection = ConnectionState::Disconnected {
+            since: Instant::now(),
+            failures: 1,
+        };
+        assert_snapshot("disconnected", &render(&mut app, 100, 24));
+    }
+
+    #[test]
+    fn terminated_searches_are_marked_partial() {
+        let mut app = test_app();
+        app.search_took_ms = Some(12);
+        assert!(!results_summary_line(&app).to_string().contains("partial"));
+        app.terminate_after = Some(1000);
+        app.search_terminated_early = Some(true);
+        assert_eq!(
+            results_summary_line(&app).to_string(),
+            "Results: hits - | took 12ms | terminated early | partial"
+        );
+        assert!(
+            filter_chips_line(&app)
+                .to_string()
+                .contains("terminate_after=1000")
+        );
+    }
+
+    #[test]
+    fn footer_hints_follow_mode_and_focus() {
+        let mut app = test_app();
+        let keys =
+            |app: &App| -> Vec<&str> { footer_hints(app).iter().map(|hint| hint.0).collect() };
+        assert!(keys(&app).contains(&"1-3"));
+        app.focus = Focus::Results;
+        assert!(keys(&app).contains(&"n/p"));
+        app.show_doc_drawer = true;
+        assert!(keys(&app).contains(&"v"));
+        app.input_mode = InputMode::Query;
+        assert_eq!(keys(&app), vec!["Enter", "Esc"]);
+        app.prompt = Some(Prompt::Quit);
+        assert_eq!(keys(&app), vec!["Enter", "Esc"]);
+        app.input_mode = InputMode::Normal;
+        assert_eq!(keys(&app), vec!["c", "d", "Esc"]);
+    }
+
+    #[test]
+    fn forward_navigation_has_a_typeable_key_and_hint() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = test_app();
+        app.nav_forward.push(app.nav_entry());
+        assert!(footer_hints(&app).contains(&("Alt-Right", "forward")));
+        let key = KeyEvent::new(KeyCode::Right, KeyModifiers::ALT);
+        assert!(matches!(
+            crate::keymap::key_action(&app, key),
+            Some(crate::action::Action::NavForward)
+        ));
+    }
+
+    #[test]
+    fn results_title_shows_buffered_range() {
+        assert_eq!(results_title(0, 0, Some(0), false), "Results (0)");
+        assert_eq!(
+            results_title(0, 25, Some(1234), false),
+            "Results (1-25 of 1,234)"
+        );
+        assert_eq!(results_title(20, 5, None, true), "Results (21-25)");
+    }
+
+    #[test]
+    fn empty_results_suggest_next_steps_for_the_situation() {
+        let text = |lines: Vec<Line>| {
+            lines
+                .iter()
+                .map(|line| {
+                    line.spans
+                        .iter()
+                        .map(|span| span.content.as_ref())
+                        .collect()
+                })
+                .collect::<Vec<String>>()
+        };
+        let mut app = test_app();
+        assert_eq!(text(results_empty_state(&app))[0], "Nothing to search yet");
+        app.indices = vec![IndexEntry {
+            docs_count: Some("0".to_string()),
+            ..test_index("logs-1")
+        }];
+        assert_eq!(text(results_empty_state(&app))[0], "No scope selected");
+        app.indices_state.select(Some(0));
+        assert_eq!(
+            text(results_empty_state(&app))[0],
+            "logs-1 has no documents"
+        );
+        app.query = "status:500".to_string();
+        assert_eq!(
+            text(results_empty_state(&app)),
+            [
+                "No documents match",
+                "logs-1: status:500",
+                "",
+                " / edit the query",
+                " :fuzzy on try fuzzy matching",
+            ]
+        );
+        app.scope_filter = "metrics".to_string();
+        assert_eq!(text(scope_empty_state(&app))[0], "No Indices match");
+    }
+
+    #[test]
+    fn freshness_badges_age_and_count_failures() {
+        let badge = |secs, failures| freshness_badge(Duration::from_secs(secs), failures);
+        assert_eq!(
+            badge(12, 0),
+            ("12s ago".to_string(), Style::default().fg(Color::DarkGray))
+        );
+        assert_eq!(badge(45, 0).1, Style::default().fg(Color::Yellow));
+        assert_eq!(
+            badge(200, 1),
+            (
+                "3m ago, 1 failed".to_string(),
+                Style::default().fg(Color::Red)
+            )
+        );
+        assert_eq!(badge(5, 3).1, Style::default().fg(Color::Red));
+        let mut app = test_app();
+        assert_eq!(freshness_title(&app, "TopBar", DataKind::Health).width(), 6);
+        let _ = app.track_fetch(DataKind::Health, Ok(()));
+        let title = freshness_title(&app, "TopBar", DataKind::Health);
+        assert_eq!(title.spans.last().unwrap().content, "0s ago");
+    }
+
+    #[test]
+    fn downgrade_color_maps_to_limited_palettes() {
+        assert_eq!(
+            downgrade_color(Color::Rgb(250, 10, 10), ColorDepth::Basic),
+            Color::LightRed
+        );
+        assert_eq!(
+            downgrade_color(Color::Indexed(3), ColorDepth::Basic),
+            Color::Yellow
+        );
+        assert_eq!(
+            downgrade_color(Color::Rgb(255, 255, 255), ColorDepth::Ansi256),
+            Color::Indexed(231)
+        );
+        assert_eq!(downgrade_color(Color::Cyan, ColorDepth::Basic), Color::Cyan);
+    }
+
+    #[test]
+    fn snapshot_ascii_borders() {
+        let mut app = results_app();
+        app.caps.unicode = false;
+        let screen = render(&mut app, 100, 24);
+        assert!(screen.is_ascii());
+        assert_snapshot("ascii_borders", &screen);
+    }
+
+    #[test]
+    fn snapshot_too_small() {
+        let mut app = results_app();
+        assert_snapshot("too_small", &render(&mut app, 40, 10));
+    }
+
+    fn arb_json(key: &'static str) -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(Value::from),
+            "\\PC{0,8}".prop_map(Value::String),
+        ];
+        leaf.prop_recursive(4, 32, 5, move |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..5).prop_map(Value::Array),
+                prop::collection::btree_map(key, inner, 0..5)
+                    .prop_map(|map| Value::Object(map.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn leaf_count(value: &Value, root: bool) -> usize {
+        match value {
+            Value::Object(map) if !map.is_empty() || root => {
+                map.values().map(|value| leaf_count(value, false)).sum()
+            }
+            Value::Array(values) if !values.is_empty() || root => {
+                values.iter().map(|value| leaf_count(value, false)).sum()
+            }
+            _ => 1,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn flatten_emits_one_line_per_leaf(value in arb_json("\\PC{0,6}")) {
+            let mut out = Vec::new();
+            flatten_json_value(&value, None, &mut out);
+            prop_assert_eq!(out.len(), leaf_count(&value, true));
+            prop_assert!(out.iter().all(|line| line.contains(" = ")));
+        }
+
+        #[test]
+        fn flatten_paths_are_unique_for_plain_keys(value in arb_json("[a-z]{1,3}")) {
+            let mut out = Vec::new();
+            flatten_json_value(&value, None, &mut out);
+            let paths: HashSet<&str> = out
+                .iter()
+                .map(|line| line.split(" = ").next().unwrap_or_default())
+                .collect();
+            prop_assert_eq!(paths.len(), out.len());
+            prop_assert!(!json_lines_flatten(&value).is_empty());
+        }
+
+        #[test]
+        fn highlight_line_preserves_text(line in "\\PC{0,40}", token in "\\PC{0,3}") {
+            let highlighted = highlight_line(&line, &token);
+            let text: String = highlighted
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            prop_assert_eq!(&text, &line);
+            if !token.is_empty() {
+                let marked = highlighted
+                    .spans
+                    .iter()
+                    .filter(|span| span.style.bg == Some(Color::Yellow))
+                    .count();
+                prop_assert_eq!(marked, line.matches(token.as_str()).count());
+            }
+        }
+
+        #[test]
+        fn case_insensitive_highlight_preserves_text(
+            line in "[a-zA-Z ]{0,40}",
+            token in "[a-zA-Z]{0,3}",
+        ) {
+            let highlighted = highlight_line_with(&line, &token, true);
+            let text: String = highlighted
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            prop_assert_eq!(&text, &line);
+            if !token.is_empty() {
+                let lowered = token.to_ascii_lowercase();
+                for span in highlighted.spans.iter().filter(|span| span.style.bg == Some(Color::Yellow)) {
+                    prop_assert_eq!(span.content.to_ascii_lowercase(), lowered.clone());
+                }
+            }
+        }
+    }
+}
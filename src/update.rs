@@ -0,0 +1,228 @@
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/unvalley/index-lens/releases/latest";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+const MIN_BINARY_SIZE: usize = 256 * 1024;
+const EXECUTABLE_MAGIC: [&[u8]; 5] = [
+    b"\x7fELF",
+    b"MZ",
+    &[0xcf, 0xfa, 0xed, 0xfe],
+    &[0xfe, 0xed, 0xfa, 0xcf],
+    &[0xca, 0xfe, 0xba, 0xbe],
+];
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+pub fn version_string() -> String {
+    format!(
+        "index-lens {} ({} {} {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("INDEX_LENS_GIT_SHA"),
+        env!("INDEX_LENS_TARGET"),
+        env!("INDEX_LENS_PROFILE")
+    )
+}
+
+fn github_client(timeout: Duration) -> Result<reqwest::blocking::Client> {
+    reqwest::blocking::Client::builder()
+        .user_agent(format!("index-lens/{}", env!("CARGO_PKG_VERSION")))
+        .timeout(timeout)
+        .build()
+        .context("failed to build http client")
+}
+
+fn fetch_latest_release(client: &reqwest::blocking::Client) -> Result<Release> {
+    client
+        .get(RELEASES_URL)
+        .send()
+        .context("request failed")?
+        .error_for_status()
+        .context("http error")?
+        .json()
+        .context("invalid release json")
+}
+
+fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+    let text = text.trim().trim_start_matches('v');
+    let core = text.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    Some((
+        parts.next()??,
+        parts.next()??,
+        parts.next().flatten().unwrap_or(0),
+    ))
+}
+
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+pub fn spawn_update_check() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let latest =
+            github_client(Duration::from_secs(5)).and_then(|client| fetch_latest_release(&client));
+        if let Ok(release) = latest
+            && is_newer(&release.tag_name, env!("CARGO_PKG_VERSION"))
+        {
+            let _ = sender.send(release.tag_name);
+        }
+    });
+    receiver
+}
+
+fn asset_name() -> String {
+    let suffix = if cfg!(windows) { ".exe" } else { "" };
+    format!("index-lens-{}{suffix}", env!("INDEX_LENS_TARGET"))
+}
+
+fn download(client: &reqwest::blocking::Client, url: &str) -> Result<Vec<u8>> {
+    let bytes = client
+        .get(url)
+        .send()
+        .context("download failed")?
+        .error_for_status()
+        .context("http error")?
+        .bytes()
+        .context("download interrupted")?;
+    Ok(bytes.to_vec())
+}
+
+fn expected_checksum(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (digest, file) = line.trim().split_once(char::is_whitespace)?;
+        (file.trim().trim_start_matches('*') == name).then(|| digest.to_ascii_lowercase())
+    })
+}
+
+fn verify_download(bytes: &[u8], sums: &str, name: &str) -> Result<()> {
+    if bytes.len() < MIN_BINARY_SIZE {
+        anyhow::bail!(
+            "downloaded {name} is only {} bytes, refusing to install it",
+            bytes.len()
+        );
+    }
+    if !EXECUTABLE_MAGIC
+        .iter()
+        .any(|magic| bytes.starts_with(magic))
+    {
+        anyhow::bail!("downloaded {name} is not an executable, refusing to install it");
+    }
+    let expected = expected_checksum(sums, name)
+        .with_context(|| format!("{CHECKSUMS_ASSET} has no entry for {name}"))?;
+    let actual: String = Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if actual != expected {
+        anyhow::bail!("checksum mismatch for {name}: expected {expected}, got {actual}");
+    }
+    Ok(())
+}
+
+pub fn self_update() -> Result<()> {
+    let client = github_client(Duration::from_secs(120))?;
+    let release = fetch_latest_release(&client)?;
+    let current = env!("CARGO_PKG_VERSION");
+    if !is_newer(&release.tag_name, current) {
+        println!("index-lens {current} is up to date");
+        return Ok(());
+    }
+    let name = asset_name();
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .with_context(|| format!("release {} has no asset {name}", release.tag_name))?;
+    let sums = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == CHECKSUMS_ASSET)
+        .with_context(|| {
+            format!(
+                "release {} has no {CHECKSUMS_ASSET}, refusing to install an unverified binary",
+                release.tag_name
+            )
+        })?;
+    println!("downloading {} ({name})", release.tag_name);
+    let sums = download(&client, &sums.browser_download_url)?;
+    let sums = String::from_utf8(sums).context("invalid checksum file")?;
+    let bytes = download(&client, &asset.browser_download_url)?;
+    verify_download(&bytes, &sums, &name)?;
+    let exe = std::env::current_exe().context("cannot locate the running binary")?;
+    replace_binary(&exe, &bytes)?;
+    println!("updated {} to {}", exe.display(), release.tag_name);
+    Ok(())
+}
+
+fn replace_binary(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = exe.with_extension("download");
+    std::fs::write(&staged, bytes)
+        .with_context(|| format!("failed to write {}", staged.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))
+            .context("failed to mark binary executable")?;
+    }
+    if cfg!(windows) {
+        let old = exe.with_extension("old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old).context("failed to move the running binary aside")?;
+    }
+    std::fs::rename(&staged, exe).context("failed to replace the binary")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_newer_compares_semver_tags() {
+        assert!(is_newer("v0.1.0", "0.0.1"));
+        assert!(is_newer("1.2.10", "1.2.9"));
+        assert!(!is_newer("v0.0.1", "0.0.1"));
+        assert!(!is_newer("v0.0.1-rc.1", "0.0.2"));
+        assert!(!is_newer("nightly", "0.0.1"));
+    }
+
+    #[test]
+    fn verify_download_checks_size_format_and_checksum() {
+        let mut bytes = b"\x7fELF".to_vec();
+        bytes.resize(MIN_BINARY_SIZE, 0);
+        let digest: String = Sha256::digest(&bytes)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let sums = format!("{digest}  index-lens-x86_64-unknown-linux-gnu\n");
+        let name = "index-lens-x86_64-unknown-linux-gnu";
+        assert!(verify_download(&bytes, &sums, name).is_ok());
+        assert!(verify_download(&bytes, &sums, "index-lens-aarch64-apple-darwin").is_err());
+        assert!(verify_download(&bytes[..1024], &sums, name).is_err());
+        let mut tampered = bytes.clone();
+        tampered[100] = 1;
+        assert!(verify_download(&tampered, &sums, name).is_err());
+        assert!(verify_download(&vec![0; MIN_BINARY_SIZE], &sums, name).is_err());
+    }
+}